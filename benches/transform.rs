@@ -0,0 +1,141 @@
+//! Parse and transform throughput over representative sources: a small
+//! component, a large page, deeply nested trees and a file full of errors.
+//! Parsing (`parse`) and the full transform (`transform`) are measured
+//! separately, so that changes to the scanner and parser can be told apart
+//! from changes to code generation; `single_pass` is the transform with
+//! `TransformOptions::single_pass`.
+//!
+//! ```text
+//! cargo bench --bench transform
+//! cargo bench --bench transform -- large_page
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use jsx_transformer::jsx_transformer::TransformOptions;
+use jsx_transformer::{parse, transform};
+
+const SMALL_COMPONENT: &str = r#"import { Icon } from "./icon.js";
+
+export function Button({ label, icon, disabled, ...rest }) {
+    return (
+        <button class="button" type="button" disabled={disabled} {...rest}>
+            {icon && <Icon name={icon} />}
+            <span class="button__label">{label}</span>
+        </button>
+    );
+}
+"#;
+
+// A page of `rows` list items, each with attributes, text, expressions and
+// a nested component.
+fn large_page(rows: usize) -> String {
+    let mut items = String::new();
+    for i in 0..rows {
+        items.push_str(&format!(
+            r#"
+                <li class="item" data-index="{i}" onclick={{() => select({i})}}>
+                    <Avatar user={{users[{i}]}} size="small" />
+                    <h3>{{users[{i}].name}}</h3>
+                    <p>Item {i} of the list, &amp; some text around {{count}} values.</p>
+                    {{users[{i}].admin ? <strong>admin</strong> : null}}
+                </li>"#
+        ));
+    }
+    format!(
+        r#"export default function Page({{ users, count, select }}) {{
+    return (
+        <html lang="en">
+            <head><title>Users</title><meta charset="utf-8" /></head>
+            <body>
+                <main><ul>{items}
+                </ul></main>
+            </body>
+        </html>
+    );
+}}
+"#
+    )
+}
+
+// Elements nested `depth` levels deep, alternating with embedded
+// expressions holding JSX.
+fn deeply_nested(depth: usize) -> String {
+    let mut source = String::from("const tree = ");
+    for i in 0..depth {
+        if i % 4 == 3 {
+            source.push_str("<div>{cond && ");
+        } else {
+            source.push_str(&format!("<div class=\"level-{i}\">"));
+        }
+    }
+    source.push_str("leaf");
+    for i in (0..depth).rev() {
+        source.push_str(if i % 4 == 3 { "}</div>" } else { "</div>" });
+    }
+    source.push_str(";\n");
+    source
+}
+
+// Components with unclosed, mismatched and malformed tags among valid ones.
+fn error_heavy(count: usize) -> String {
+    let broken = [
+        "<div><span>unclosed</div>",
+        "<p class=\"a\" id=>bad attribute</p>",
+        "<ul><li>one</li><li>two</ul>",
+        "<Card title={title>missing brace</Card>",
+        "<section><h1>ok</h1></section>",
+    ];
+    (0..count)
+        .map(|i| format!("const c{i} = {};\n", broken[i % broken.len()]))
+        .collect()
+}
+
+fn corpora() -> Vec<(&'static str, String)> {
+    vec![
+        ("small_component", SMALL_COMPONENT.to_string()),
+        ("large_page", large_page(500)),
+        ("deeply_nested", deeply_nested(200)),
+        ("error_heavy", error_heavy(200)),
+    ]
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, source) in corpora() {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse(black_box(&source))));
+    }
+    group.finish();
+}
+
+fn bench_transform(c: &mut Criterion) {
+    let options = TransformOptions::default();
+    let mut group = c.benchmark_group("transform");
+    for (name, source) in corpora() {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| transform(black_box(&source), black_box(&options)))
+        });
+    }
+    group.finish();
+}
+
+// The transform with `single_pass`, the parser driving the transformer
+// without building trees.
+fn bench_single_pass(c: &mut Criterion) {
+    let options = TransformOptions {
+        single_pass: true,
+        ..TransformOptions::default()
+    };
+    let mut group = c.benchmark_group("single_pass");
+    for (name, source) in corpora() {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| transform(black_box(&source), black_box(&options)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_transform, bench_single_pass);
+criterion_main!(benches);
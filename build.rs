@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+// HTML dataset generated into `$OUT_DIR/html_data.rs` (see `html_data.rs`).
+const HTML_DATA: &str = "src/rs/jsx_transformer/html_data.json";
+
+// Exposes the git commit of the build as `SXO_GIT_HASH` for `version()`;
+// "unknown" when built outside a git checkout (e.g. from a published crate).
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SXO_GIT_HASH={hash}");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    println!("cargo:rerun-if-changed={HTML_DATA}");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    std::fs::write(Path::new(&out_dir).join("html_data.rs"), html_data())
+        .expect("cannot write html_data.rs");
+
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
+
+// The dataset as Rust constants, lists sorted for binary search.
+fn html_data() -> String {
+    let json = std::fs::read_to_string(HTML_DATA).expect("cannot read the HTML dataset");
+    let data: Value = serde_json::from_str(&json).expect("the HTML dataset is not valid JSON");
+    let mut code = String::from("// Generated by build.rs from html_data.json; do not edit.\n");
+
+    let lists = [
+        ("HTML_VOID_TAGS", "voidElements"),
+        ("HTML_TAGS", "elements"),
+        ("OBSOLETE_HTML_TAGS", "obsoleteElements"),
+        ("BOOLEAN_ATTRIBUTES", "booleanAttributes"),
+    ];
+    for (name, key) in lists {
+        let list = sorted_strings(&data[key], key);
+        writeln!(code, "pub const {name}: &[&str] = &{list:?};").unwrap();
+    }
+
+    // Elements an attribute is obsolete on, every element when empty
+    let obsolete: BTreeMap<&str, Vec<&str>> = entries(&data, "obsoleteAttributes")
+        .map(|(name, tags)| (name, sorted_strings(tags, name)))
+        .collect();
+    code.push_str("pub const OBSOLETE_HTML_ATTRIBUTES: &[(&str, &[&str])] = &[\n");
+    for (name, tags) in obsolete {
+        writeln!(code, "    ({name:?}, &{tags:?}),").unwrap();
+    }
+    code.push_str("];\n");
+
+    let names: BTreeMap<&str, &str> = entries(&data, "attributeNames")
+        .map(|(name, html)| (name, html.as_str().expect("attribute names are strings")))
+        .collect();
+    writeln!(
+        code,
+        "pub const ATTRIBUTE_NAMES: &[(&str, &str)] = &{:?};",
+        names.into_iter().collect::<Vec<_>>()
+    )
+    .unwrap();
+    code
+}
+
+fn entries<'a>(data: &'a Value, key: &str) -> impl Iterator<Item = (&'a str, &'a Value)> {
+    data[key]
+        .as_object()
+        .unwrap_or_else(|| panic!("`{key}` of the HTML dataset is not an object"))
+        .iter()
+        .map(|(name, value)| (name.as_str(), value))
+}
+
+fn sorted_strings<'a>(value: &'a Value, key: &str) -> Vec<&'a str> {
+    let mut list: Vec<&str> = value
+        .as_array()
+        .unwrap_or_else(|| panic!("`{key}` of the HTML dataset is not an array"))
+        .iter()
+        .map(|s| s.as_str().expect("the HTML dataset lists strings"))
+        .collect();
+    list.sort_unstable();
+    list.dedup();
+    list
+}
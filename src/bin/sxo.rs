@@ -0,0 +1,450 @@
+//! `sxo`: transform, parse and lint JSX files from the command line.
+//!
+//! ```text
+//! sxo transform [<glob>...] [--out-dir <dir>] [--options <sxo.json>]
+//! sxo parse [<glob>...] [--json]
+//! sxo lint [<glob>...]
+//! ```
+//!
+//! Without a glob (or with `-`), the input is read from stdin and the result
+//! written to stdout. Diagnostics go to stderr as `file:line:column: message`.
+//! Exits with 1 when any diagnostic was reported, 2 on usage or I/O errors.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde_json::json;
+
+use jsx_transformer::jsx_transformer::{
+    JsDiagnostic, JsNode, JsParseResult, JsTransformOptions, JsTransformResult, TransformOptions,
+};
+use jsx_transformer::lint;
+
+const USAGE: &str = "\
+Usage:
+  sxo transform [<glob>...] [--out-dir <dir>] [--options <file>]
+  sxo parse [<glob>...] [--json]
+  sxo lint [<glob>...]
+
+Without a glob, or with `-`, reads stdin and writes stdout.
+
+Options:
+  --out-dir <dir>   Write transformed files under <dir> (`.jsx` becomes `.js`,
+                    `.tsx` becomes `.ts`) instead of stdout
+  --options <file>  JSON file of transform options, e.g. `{ \"minify\": true }`
+  --json            Print the parsed nodes as JSON
+  -h, --help        Print this help";
+
+// Directories never searched by globs.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Transform,
+    Parse,
+    Lint,
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    command: Option<Command>,
+    globs: Vec<String>,
+    out_dir: Option<PathBuf>,
+    options: Option<PathBuf>,
+    json: bool,
+    help: bool,
+}
+
+// An input: a file with its path relative to the base of its glob, or stdin.
+struct Input {
+    path: Option<PathBuf>,
+    relative: PathBuf,
+    source: String,
+}
+
+impl Input {
+    fn name(&self) -> String {
+        self.path
+            .as_ref()
+            .map_or_else(|| "<stdin>".to_string(), |p| p.display().to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("sxo: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("sxo: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => parsed.help = true,
+            "--json" => parsed.json = true,
+            "--out-dir" => {
+                let dir = args.next().ok_or("`--out-dir` needs a directory")?;
+                parsed.out_dir = Some(dir.into());
+            }
+            "--options" => {
+                let file = args.next().ok_or("`--options` needs a file")?;
+                parsed.options = Some(file.into());
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{flag}`")),
+            _ if parsed.command.is_none() && !parsed.help => {
+                parsed.command = Some(match arg.as_str() {
+                    "transform" => Command::Transform,
+                    "parse" => Command::Parse,
+                    "lint" => Command::Lint,
+                    other => return Err(format!("unknown command `{other}`")),
+                });
+            }
+            _ => parsed.globs.push(arg),
+        }
+    }
+    if parsed.command.is_none() && !parsed.help {
+        return Err("missing command".to_string());
+    }
+    Ok(parsed)
+}
+
+// Runs the command; Ok(false) when diagnostics were reported.
+fn run(args: &Args) -> Result<bool, String> {
+    let inputs = read_inputs(&args.globs)?;
+    let mut ok = true;
+    match args.command {
+        Some(Command::Transform) => {
+            let options = read_options(args.options.as_deref())?;
+            for input in &inputs {
+                let result = JsTransformResult::transform(&input.source, &options);
+                report(&input.name(), &result.diagnostics);
+                let Some(code) = result.code else {
+                    ok = false;
+                    continue;
+                };
+                match &args.out_dir {
+                    Some(dir) => {
+                        let out = dir.join(output_path(&input.relative));
+                        if let Some(parent) = out.parent() {
+                            fs::create_dir_all(parent)
+                                .map_err(|e| format!("{}: {e}", parent.display()))?;
+                        }
+                        fs::write(&out, code).map_err(|e| format!("{}: {e}", out.display()))?;
+                    }
+                    None => write_stdout(&code)?,
+                }
+            }
+        }
+        Some(Command::Parse) => {
+            let mut results = Vec::new();
+            for input in &inputs {
+                let result = JsParseResult::parse(&input.source);
+                report(&input.name(), &result.diagnostics);
+                ok &= result.diagnostics.is_empty();
+                if !args.json {
+                    for root in &result.nodes {
+                        let (line, column) = line_column(&input.source, root.span.0);
+                        write_stdout(&format!(
+                            "{}:{line}:{column}: {}\n",
+                            input.name(),
+                            describe(&root.node)
+                        ))?;
+                    }
+                }
+                results.push((input.name(), result));
+            }
+            if args.json {
+                let json = match (&args.globs[..], &results[..]) {
+                    // A single input prints its result as is
+                    (globs, [(_, result)]) if globs.iter().all(|g| g == "-") => {
+                        serde_json::to_string_pretty(result)
+                    }
+                    _ => {
+                        let files: Vec<_> = results
+                            .iter()
+                            .map(|(file, result)| json!({ "file": file, "result": result }))
+                            .collect();
+                        serde_json::to_string_pretty(&files)
+                    }
+                }
+                .map_err(|e| e.to_string())?;
+                write_stdout(&format!("{json}\n"))?;
+            }
+        }
+        Some(Command::Lint) => {
+            for input in &inputs {
+                for d in lint::lint(&input.source) {
+                    ok = false;
+                    eprintln!(
+                        "{}:{}:{}: {} [{}] {}",
+                        input.name(),
+                        d.line,
+                        d.column,
+                        d.severity.as_str(),
+                        d.rule,
+                        d.message
+                    );
+                }
+            }
+        }
+        None => {}
+    }
+    Ok(ok)
+}
+
+fn read_options(path: Option<&Path>) -> Result<TransformOptions, String> {
+    let defaults = TransformOptions::default();
+    let Some(path) = path else {
+        return Ok(defaults);
+    };
+    let json = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let options: JsTransformOptions =
+        serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(options.apply(&defaults))
+}
+
+fn read_inputs(globs: &[String]) -> Result<Vec<Input>, String> {
+    if globs.is_empty() || globs.iter().all(|g| g == "-") {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("<stdin>: {e}"))?;
+        return Ok(vec![Input {
+            path: None,
+            relative: PathBuf::from("stdin.jsx"),
+            source,
+        }]);
+    }
+
+    let mut inputs = Vec::new();
+    for glob in globs.iter().filter(|g| *g != "-") {
+        let files = expand_glob(glob)?;
+        if files.is_empty() {
+            return Err(format!("no files match `{glob}`"));
+        }
+        for (path, relative) in files {
+            let source =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            inputs.push(Input {
+                path: Some(path),
+                relative,
+                source,
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+// Files matching `glob`, sorted, with their path relative to the part of the
+// glob before the first wildcard. `*` and `?` match within a path segment,
+// `**` matches any number of directories.
+fn expand_glob(glob: &str) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let segments: Vec<&str> = glob.split('/').collect();
+    let literal = segments
+        .iter()
+        .position(|s| s.contains(['*', '?']))
+        .unwrap_or(segments.len());
+    if literal == segments.len() {
+        let path = PathBuf::from(glob);
+        let relative = path.file_name().map(PathBuf::from).unwrap_or_default();
+        return Ok(if path.is_file() {
+            vec![(path, relative)]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let base = match segments[..literal].join("/") {
+        base if base.is_empty() && glob.starts_with('/') => PathBuf::from("/"),
+        base if base.is_empty() => PathBuf::from("."),
+        base => PathBuf::from(base),
+    };
+    let mut files = Vec::new();
+    walk(&base, &mut PathBuf::new(), &segments[literal..], &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn walk(
+    dir: &Path,
+    relative: &mut PathBuf,
+    pattern: &[&str],
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("{}: {e}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        relative.push(&name);
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(&path, relative, pattern, files)?;
+            }
+        } else if matches_path(pattern, &relative_segments(relative)) {
+            files.push((path, relative.clone()));
+        }
+        relative.pop();
+    }
+    Ok(())
+}
+
+fn relative_segments(path: &Path) -> Vec<String> {
+    path.iter()
+        .map(|s| s.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn matches_path(pattern: &[&str], path: &[String]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches_path(&pattern[1..], path)
+                || (!path.is_empty() && matches_path(pattern, &path[1..]))
+        }
+        (Some(segment), Some(name)) => {
+            matches_segment(segment.as_bytes(), name.as_bytes())
+                && matches_path(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn matches_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches_segment(&pattern[1..], name)
+                || (!name.is_empty() && matches_segment(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => matches_segment(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) => p == n && matches_segment(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+// `.jsx` and `.tsx` files become `.js` and `.ts` files.
+fn output_path(relative: &Path) -> PathBuf {
+    match relative.extension().and_then(|e| e.to_str()) {
+        Some("jsx") => relative.with_extension("js"),
+        Some("tsx") => relative.with_extension("ts"),
+        _ => relative.to_path_buf(),
+    }
+}
+
+fn report(name: &str, diagnostics: &[JsDiagnostic]) {
+    for d in diagnostics {
+        match (d.line, d.column) {
+            (Some(line), Some(column)) => {
+                eprintln!("{name}:{line}:{column}: {}: {}", d.code, d.message)
+            }
+            _ => eprintln!("{name}: {}: {}", d.code, d.message),
+        }
+    }
+}
+
+// 1-based line and column (in characters) of a UTF-16 offset.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut units = 0;
+    let (mut line, mut column) = (1, 1);
+    for c in source.chars() {
+        if units >= offset {
+            break;
+        }
+        units += c.len_utf16();
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+fn describe(node: &JsNode) -> String {
+    match node {
+        JsNode::Element { tag, .. } => format!("<{tag}>"),
+        JsNode::Fragment { .. } => "<>".to_string(),
+        JsNode::Text { .. } => "text".to_string(),
+        JsNode::Expression { .. } | JsNode::EmbeddedExpression { .. } => "expression".to_string(),
+    }
+}
+
+fn write_stdout(s: &str) -> Result<(), String> {
+    io::stdout()
+        .write_all(s.as_bytes())
+        .map_err(|e| format!("<stdout>: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    fn path(s: &str) -> Vec<String> {
+        s.split('/').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["transform", "src/**/*.jsx", "--out-dir", "dist"]).unwrap();
+        assert_eq!(parsed.command, Some(Command::Transform));
+        assert_eq!(parsed.globs, ["src/**/*.jsx"]);
+        assert_eq!(parsed.out_dir, Some(PathBuf::from("dist")));
+
+        assert!(args(&["parse", "--json"]).unwrap().json);
+        assert!(args(&["--help"]).unwrap().help);
+        assert!(args(&[]).is_err());
+        assert!(args(&["build"]).is_err());
+        assert!(args(&["lint", "--fix"]).is_err());
+        assert!(args(&["transform", "--options"]).is_err());
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(matches_path(&["*.jsx"], &path("app.jsx")));
+        assert!(!matches_path(&["*.jsx"], &path("pages/app.jsx")));
+        assert!(matches_path(&["**", "*.jsx"], &path("app.jsx")));
+        assert!(matches_path(&["**", "*.jsx"], &path("pages/blog/app.jsx")));
+        assert!(matches_path(
+            &["pages", "**", "index.?sx"],
+            &path("pages/a/index.tsx")
+        ));
+        assert!(!matches_path(
+            &["pages", "*", "index.jsx"],
+            &path("pages/a/b/index.jsx")
+        ));
+        assert!(!matches_path(&["*.jsx"], &path("app.js")));
+    }
+
+    #[test]
+    fn test_output_path_and_location() {
+        assert_eq!(output_path(Path::new("a/b.jsx")), PathBuf::from("a/b.js"));
+        assert_eq!(output_path(Path::new("b.tsx")), PathBuf::from("b.ts"));
+        assert_eq!(output_path(Path::new("b.mdx")), PathBuf::from("b.mdx"));
+        assert_eq!(line_column("a\né <p/>", 4), (2, 3));
+    }
+}
@@ -4,11 +4,76 @@ const CAMEL_REPLACE = /[A-Z0-9]/g;
 const ON_ANI = /^on(Ani|Tra|Tou|BeforeInp|Compo)/;
 
 function __jsxList(value) {
-    if (Array.isArray(value)) return value.join("");
+    if (Array.isArray(value)) {
+        // Items rendered by async templates or callbacks
+        if (value.some(isThenable)) return Promise.all(value).then((items) => items.join(""));
+        return value.join("");
+    }
     return value == null ? "" : value;
 }
 
+// Tag for templates of JSX that uses `await`: resolves interpolated promises
+// (and arrays of them) and returns a promise of the joined string.
+async function __jsxAsync(strings, ...values) {
+    const resolved = await Promise.all(values.map(resolveAsync));
+    let out = strings[0];
+    for (let i = 0; i < resolved.length; i++) {
+        out += `${resolved[i]}${strings[i + 1]}`;
+    }
+    return out;
+}
+
+async function resolveAsync(value) {
+    const resolved = await value;
+    if (Array.isArray(resolved)) return (await Promise.all(resolved.map(resolveAsync))).join("");
+    if (resolved != null && typeof resolved[Symbol.asyncIterator] === "function") {
+        let out = "";
+        for await (const chunk of resolved) out += chunk;
+        return out;
+    }
+    return resolved;
+}
+
+// Streamed expression: an async iterable over the rendered chunks of a sync or
+// async iterable, so streaming renderers can flush each chunk as it resolves.
+function __jsxStream(iterable) {
+    return {
+        async *[Symbol.asyncIterator]() {
+            if (iterable == null) return;
+            for await (const chunk of iterable) {
+                const value = await resolveAsync(chunk);
+                yield value == null ? "" : `${value}`;
+            }
+        },
+    };
+}
+
+// Flattens a value produced by stream output (generators yielding strings,
+// promises, arrays or (async) iterables) into an async iterable of strings.
+async function* __jsxRenderStream(value) {
+    const resolved = await value;
+    if (resolved == null) return;
+    if (typeof resolved === "string") {
+        if (resolved) yield resolved;
+        return;
+    }
+    if (typeof resolved[Symbol.iterator] === "function" || typeof resolved[Symbol.asyncIterator] === "function") {
+        for await (const chunk of resolved) yield* __jsxRenderStream(chunk);
+        return;
+    }
+    yield `${resolved}`;
+}
+
+function isThenable(value) {
+    return value != null && typeof value.then === "function";
+}
+
 function __jsxComponent(Component, props, children) {
+    // Children rendered by an async template
+    if (isThenable(children)) {
+        return children.then((resolved) => __jsxComponent(Component, props, resolved));
+    }
+
     // Accept both array-of-prop-objects (current transformer output) or a single object.
     let finalProps;
     if (Array.isArray(props)) {
@@ -63,6 +128,11 @@ function __jsxComponent(Component, props, children) {
     return Array.isArray(result) ? result.join("") : result;
 }
 
+// Explicit fragment node: renders its children as-is.
+function __jsxFragment({ children }) {
+    return children == null ? "" : children;
+}
+
 function __jsxSpread(obj) {
     const result = [];
     for (const [propKey, propValue] of Object.entries(obj)) {
@@ -81,6 +151,21 @@ function __jsxSpread(obj) {
     return result.length ? ` ${result.join(" ")}` : "";
 }
 
+// Expression attribute values with `attribute_helper`: escapes `&` and both
+// quotes so the value stays inside its attribute.
+function __jsxAttr(value) {
+    return String(value).replace(/[&"']/g, (c) => (c === "&" ? "&amp;" : c === '"' ? "&quot;" : "&#39;"));
+}
+
+// Event handlers emitted as `data-action="click:${__jsxAction(fn)}"` markers.
+// The client runtime looks handlers up by id in `globalThis.__jsxActions`.
+function __jsxAction(handler) {
+    const actions = (globalThis.__jsxActions ??= new Map());
+    const id = `a${actions.size}`;
+    actions.set(id, handler);
+    return id;
+}
+
 function normalizeAttributeName(name) {
     const lowerCased = name.toLowerCase();
 
@@ -110,3 +195,9 @@ function normalizeAttributeName(name) {
 globalThis.__jsxComponent = __jsxComponent;
 globalThis.__jsxSpread = __jsxSpread;
 globalThis.__jsxList = __jsxList;
+globalThis.__jsxAction = __jsxAction;
+globalThis.__jsxAttr = __jsxAttr;
+globalThis.__jsxFragment = __jsxFragment;
+globalThis.__jsxAsync = __jsxAsync;
+globalThis.__jsxStream = __jsxStream;
+globalThis.__jsxRenderStream = __jsxRenderStream;
@@ -361,3 +361,61 @@ test("should throw when component is not function or string", () => {
     }
     assert.ok(threw, "Expected __jsxComponent to throw for invalid component type");
 });
+
+test("should register event handlers and return their ids", () => {
+    const save = () => "saved";
+    const id = __jsxAction(save);
+    assert.strictEqual(globalThis.__jsxActions.get(id), save);
+    assert.notStrictEqual(__jsxAction(() => {}), id);
+});
+
+test("should render fragment children through __jsxComponent", () => {
+    assert.strictEqual(__jsxComponent(__jsxFragment, [], "<li>a</li><li>b</li>"), "<li>a</li><li>b</li>");
+    assert.strictEqual(__jsxComponent(__jsxFragment, []), "");
+});
+
+test("should resolve async templates, lists and component children", async () => {
+    const Card = ({ children }) => `<div>${children}</div>`;
+    const load = async (i) => `#${i}`;
+    const html = await __jsxAsync`<main>${__jsxComponent(Card, [], __jsxAsync`${Promise.resolve("Ada")}`)}${__jsxList(
+        [1, 2].map(async (i) => __jsxAsync`<li>${load(i)}</li>`),
+    )}</main>`;
+    assert.strictEqual(html, "<main><div>Ada</div><li>#1</li><li>#2</li></main>");
+});
+
+test("should stream chunks of sync and async iterables", async () => {
+    async function* rows() {
+        yield "<tr>1</tr>";
+        yield Promise.resolve("<tr>2</tr>");
+    }
+    const chunks = [];
+    for await (const chunk of __jsxStream(rows())) chunks.push(chunk);
+    assert.deepStrictEqual(chunks, ["<tr>1</tr>", "<tr>2</tr>"]);
+
+    const users = new Map([["a", "Ada"]]);
+    const html = await __jsxAsync`<ul>${__jsxStream(Array.from(users.values(), (u) => __jsxAsync`<li>${u}</li>`))}</ul>`;
+    assert.strictEqual(html, "<ul><li>Ada</li></ul>");
+});
+
+test("should flatten stream output into string chunks", async () => {
+    const title = async () => "Title";
+    const page = (function* () {
+        yield "<main><h1>";
+        yield title();
+        yield "</h1>";
+        yield ["<li>1</li>", Promise.resolve("<li>2</li>")];
+        yield __jsxStream(new Set(["<p>a</p>"]).values());
+        yield null;
+        yield 3;
+        yield "</main>";
+    }).call(this);
+    const chunks = [];
+    for await (const chunk of __jsxRenderStream(page)) chunks.push(chunk);
+    assert.deepStrictEqual(chunks, ["<main><h1>", "Title", "</h1>", "<li>1</li>", "<li>2</li>", "<p>a</p>", "3", "</main>"]);
+});
+
+test("should escape attribute values with __jsxAttr", () => {
+    const title = `Say "hi" & 'bye'`;
+    assert.strictEqual(`<p title="${__jsxAttr(title)}"></p>`, `<p title="Say &quot;hi&quot; &amp; &#39;bye&#39;"></p>`);
+    assert.strictEqual(__jsxAttr(42), "42");
+});
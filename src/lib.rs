@@ -1,59 +1,198 @@
-use wasm_bindgen::prelude::*;
-
+#[cfg(feature = "napi")]
+pub mod napi;
 mod rs;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+pub use rs::editor;
+pub use rs::extract;
+pub use rs::html_to_jsx;
 pub use rs::jsx_parser;
 pub use rs::jsx_transformer;
+pub use rs::lint;
+pub use rs::testing;
+
+use std::borrow::Cow;
+
+use jsx_parser::{ParseError, SpannedNode};
+use jsx_transformer::{JSXError, TransformOptions, TransformOutput};
+
+/// Asserts that two values are equal up to whitespace (see
+/// `jsx_parser::EqIgnoreWhitespace`): transformed code, JSX source or
+/// `JSXNode` trees. On failure both values are printed, as by `assert_eq!`.
+///
+/// ```
+/// use jsx_transformer::jsx_transformer::TransformOptions;
+/// use jsx_transformer::{assert_jsx_eq, transform};
+///
+/// let output = transform("<p>\n  {a}\n</p>", &TransformOptions::default()).unwrap();
+/// assert_jsx_eq!(output.code.as_str(), "`<p>${a}</p>`");
+/// ```
+#[macro_export]
+macro_rules! assert_jsx_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !$crate::jsx_parser::EqIgnoreWhitespace::eq_ignore_whitespace(left, right) {
+                    panic!(
+                        "assertion `left == right` failed (ignoring whitespace)\n  left: {:?}\n right: {:?}",
+                        left, right
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Transforms the JSX found in a JavaScript source; the native entry point
+/// of the crate, with the same semantics as the WASM `transform` export.
+/// See `jsx_transformer::jsx_transform`.
+pub fn transform(source: &str, options: &TransformOptions) -> Result<TransformOutput, JSXError> {
+    jsx_transformer::jsx_transform(source, options)
+}
+
+/// Transforms several files, given as `(filename, source)` pairs, returning
+/// their results in the same order. With the `parallel` feature, files are
+/// transformed in parallel on native targets. When `source_annotations` is
+/// enabled, each file is annotated with its own name.
+pub fn transform_many(
+    inputs: &[(&str, &str)],
+    options: &TransformOptions,
+) -> Vec<Result<TransformOutput, JSXError>> {
+    let transform_file = |&(filename, source): &(&str, &str)| match &options.source_annotations {
+        Some(_) => {
+            let options = TransformOptions {
+                source_annotations: Some(filename.to_string()),
+                ..options.clone()
+            };
+            transform(source, &options)
+        }
+        None => transform(source, options),
+    };
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        use rayon::prelude::*;
+        inputs.par_iter().map(transform_file).collect()
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    {
+        inputs.iter().map(transform_file).collect()
+    }
+}
 
-// When the `console_error_panic_hook` feature is enabled, we can call the
-// `set_panic_hook` function at least once during initialization, and then
-// we will get better error messages if our code ever panics.
-#[cfg(feature = "console_error_panic_hook")]
-pub fn set_panic_hook() {
-    console_error_panic_hook::set_once();
+/// Parses the JSX nodes of a JavaScript source, found as `transform` finds
+/// them, with their byte spans in `source`. Fails with every parse error.
+pub fn parse(source: &str) -> Result<Vec<SpannedNode>, Vec<ParseError>> {
+    jsx_transformer::parse_jsx(source)
 }
 
-// Initialize the WASM module
-// WASM entrypoint for initialization
-#[wasm_bindgen(start)]
-pub fn init() {
-    set_panic_hook();
+/// Inputs of the fuzz entry points are cut to this many bytes, which bounds
+/// the time and memory spent on any input.
+pub const FUZZ_INPUT_LIMIT: usize = 16 * 1024;
+
+/// Entry point for fuzz targets: `parse` of arbitrary bytes, read as UTF-8
+/// with invalid sequences replaced and cut to `FUZZ_INPUT_LIMIT`. It must
+/// not panic on any input; a panic is a bug for the fuzzer to report.
+///
+/// ```text
+/// fuzz_target!(|data: &[u8]| { let _ = jsx_transformer::parse_no_panic(data); });
+/// ```
+pub fn parse_no_panic(data: &[u8]) -> Result<Vec<SpannedNode>, Vec<ParseError>> {
+    parse(&fuzz_input(data))
+}
+
+/// Entry point for fuzz targets: `transform` of arbitrary bytes, read as
+/// for `parse_no_panic`. It must not panic on any input either.
+pub fn transform_no_panic(
+    data: &[u8],
+    options: &TransformOptions,
+) -> Result<TransformOutput, JSXError> {
+    transform(&fuzz_input(data), options)
 }
 
-/// Expose the Rust jsx_transformer function to JS/WASM
-#[wasm_bindgen]
-pub fn jsx(input: &str) -> Result<String, JsValue> {
-    jsx_transformer::jsx_transformer(input).map_err(|e| JsValue::from_str(&format!("{e}")))
+fn fuzz_input(data: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(&data[..data.len().min(FUZZ_INPUT_LIMIT)])
 }
 
-// TODO: bring test from query/jsx_parser
 #[cfg(test)]
 mod tests {
     use super::*;
+    use jsx_parser::JSXNode;
+
+    #[test]
+    fn test_native_transform_and_parse() {
+        let source = "const a = <p class=\"x\">{b}</p>;";
+        let output = transform(source, &TransformOptions::default()).unwrap();
+        assert_eq!(output.code, "const a = `<p class=\"x\">${b}</p>`;");
+
+        let nodes = parse(source).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(
+            &source[nodes[0].1 .0..nodes[0].1 .1],
+            "<p class=\"x\">{b}</p>"
+        );
+        assert!(matches!(&nodes[0].0, JSXNode::Element { tag, .. } if tag == "p"));
 
-    fn normalize_ws(s: &str) -> String {
-        s.split_whitespace().collect::<Vec<_>>().join(" ")
+        let errors = parse("const a = <p>;\nconst b = <div></span>;").unwrap_err();
+        assert!(!errors.is_empty());
     }
 
     #[test]
-    fn test_transform_jsx_export() {
-        let input = r#"\
-            <App>
-                <Header title="Welcome!" />
-                <Content>
-                    <p>This is a <strong>complex</strong> JSX example.</p>
-                    <CustomComponent prop1={42} prop2="hello" />
-                </Content>
-                <Footer />
-            </App>
-        "#;
-        let result = jsx(input);
-
-        println!("Transformed JSX: {}", result.clone().unwrap());
-
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        let expected_snippet = "${__jsxComponent(App, [], `${__jsxComponent(Header, [{\"title\":\"Welcome!\"}])}${__jsxComponent(Content, [], `<p>This is a <strong>complex</strong> JSX example.</p>${__jsxComponent(CustomComponent, [{\"prop1\":42},{\"prop2\":\"hello\"}])}`)}${__jsxComponent(Footer, [])}`)}";
-        assert!(normalize_ws(&output).contains(&normalize_ws(expected_snippet)));
+    fn test_transform_many() {
+        let inputs: Vec<(String, String)> = (0..16)
+            .map(|i| (format!("page{i}.jsx"), format!("const a = <p>{i}</p>;")))
+            .collect();
+        let mut inputs: Vec<(&str, &str)> = inputs
+            .iter()
+            .map(|(name, source)| (name.as_str(), source.as_str()))
+            .collect();
+        inputs.push(("bad.jsx", "const b = <p>;"));
+
+        let results = transform_many(&inputs, &TransformOptions::default());
+        assert_eq!(results.len(), 17);
+        for (i, result) in results[..16].iter().enumerate() {
+            assert_eq!(
+                result.as_ref().unwrap().code,
+                format!("const a = `<p>{i}</p>`;")
+            );
+        }
+        assert!(matches!(results[16], Err(JSXError::ParsingError(_))));
+
+        let options = TransformOptions {
+            source_annotations: Some("ignored.jsx".to_string()),
+            ..Default::default()
+        };
+        let results = transform_many(&inputs[..2], &options);
+        assert!(results[0].as_ref().unwrap().code.contains("page0.jsx:1:11"));
+        assert!(results[1].as_ref().unwrap().code.contains("page1.jsx:1:11"));
+    }
+
+    #[test]
+    fn test_fuzz_entry_points() {
+        // Deterministic pseudo-random inputs over a JSX-heavy alphabet
+        let alphabet = "<>/{}=\"'` \nab-.:$_é😀\\...;()[]*!&|?";
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        let mut seed: u64 = 0x5eed;
+        let options = TransformOptions::default();
+        for _ in 0..3000 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let len = (seed >> 33) as usize % 48;
+            let input: String = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    alphabet[(seed >> 33) as usize % alphabet.len()]
+                })
+                .collect();
+            let _ = parse_no_panic(input.as_bytes());
+            let _ = transform_no_panic(input.as_bytes(), &options);
+        }
+
+        // Invalid UTF-8, and nesting as deep as the limit allows
+        assert!(parse_no_panic(&[0xff, b'<', b'p', b'>', 0xc3]).is_err());
+        let deep = "<a>".repeat(FUZZ_INPUT_LIMIT);
+        assert!(transform_no_panic(deep.as_bytes(), &options).is_err());
     }
 }
@@ -0,0 +1,45 @@
+//! Node.js native addon: `transform` and `parse` with the same results as the
+//! WASM exports, without instantiating the WASM module.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::jsx_transformer::{
+    JsParseResult, JsTransformOptions, JsTransformResult, TransformOptions,
+};
+
+/// Transforms `source`, returning `{ code, map, diagnostics, metadata }`.
+/// `options` is an optional plain object of `JsTransformOptions` fields.
+#[napi]
+pub fn transform(source: String, options: Option<Value>) -> Result<Value> {
+    let result = match transform_options(options) {
+        Ok(options) => JsTransformResult::transform(&source, &options),
+        Err(e) => JsTransformResult::error("invalid-options", e),
+    };
+    to_value(&result)
+}
+
+/// Parses the JSX nodes of `source`, returning `{ nodes, diagnostics }`.
+#[napi]
+pub fn parse(source: String) -> Result<Value> {
+    to_value(&JsParseResult::parse(&source))
+}
+
+// Reads the options object passed from JS; `undefined` and `null` select
+// the defaults.
+fn transform_options(options: Option<Value>) -> std::result::Result<TransformOptions, String> {
+    let defaults = TransformOptions::default();
+    match options.filter(|options| !options.is_null()) {
+        Some(options) => {
+            let options: JsTransformOptions =
+                serde_json::from_value(options).map_err(|e| format!("Invalid options: {e}"))?;
+            Ok(options.apply(&defaults))
+        }
+        None => Ok(defaults),
+    }
+}
+
+fn to_value(result: &impl serde::Serialize) -> Result<Value> {
+    serde_json::to_value(result).map_err(|e| Error::from_reason(e.to_string()))
+}
@@ -0,0 +1,274 @@
+//! Structural diff of two JSX trees, e.g. a node before and after an edit,
+//! as patch hints for hot reload: which elements, attributes and texts were
+//! inserted, removed or changed, with their spans in both sources.
+
+use std::collections::HashMap;
+
+use super::tree::{children, SpannedRoot};
+use crate::jsx_parser::{ExpressionPart, JSXAttribute, JSXNode};
+
+/// What changed about a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// A node of the new tree with no counterpart in the old one.
+    Inserted,
+    /// A node of the old tree with no counterpart in the new one.
+    Removed,
+    /// A node replaced by one of another kind or tag.
+    Replaced,
+    /// The content of a text or expression node, or the code around the JSX
+    /// of an embedded expression.
+    TextChanged,
+    AttributeAdded(String),
+    AttributeRemoved(String),
+    AttributeChanged(String),
+}
+
+/// A change between two trees. `path` holds the child indices leading to
+/// the node from the root (children of elements and fragments, JSX parts of
+/// embedded expressions), in the new tree except for removals. `old` and
+/// `new` are its spans in each source: the node, or its opening tag for
+/// attribute changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOp {
+    pub kind: DiffKind,
+    pub path: Vec<usize>,
+    pub old: Option<(usize, usize)>,
+    pub new: Option<(usize, usize)>,
+}
+
+/// Changes turning `old` into `new`, in document order. Children of the same
+/// kind, tag and `key` are paired, keeping the longest common sequence, and
+/// compared in place; the others at the same place are replaced, the rest
+/// removed or inserted. An empty result means the trees are equal.
+pub fn diff(old: &SpannedRoot, new: &SpannedRoot) -> Vec<DiffOp> {
+    let mut differ = Differ {
+        old: Spans::of(old),
+        new: Spans::of(new),
+        ops: Vec::new(),
+    };
+    differ.node(&old.node, &new.node, &mut Vec::new());
+    differ.ops
+}
+
+type Span = (usize, usize);
+
+// Span of each node of a tree, and of the opening tag of its elements, by
+// node address.
+struct Spans(HashMap<*const JSXNode, (Span, Option<Span>)>);
+
+impl Spans {
+    fn of(root: &SpannedRoot) -> Self {
+        let mut elements = root.elements.iter();
+        let spans = root
+            .nodes()
+            .map(|(node, span)| {
+                let open = match node {
+                    JSXNode::Element { .. } => elements.next().map(|spans| spans.open),
+                    _ => None,
+                };
+                (node as *const JSXNode, (span, open))
+            })
+            .collect();
+        Self(spans)
+    }
+
+    fn node(&self, node: &JSXNode) -> Option<Span> {
+        self.0.get(&(node as *const JSXNode)).map(|(span, _)| *span)
+    }
+
+    fn open_tag(&self, node: &JSXNode) -> Option<Span> {
+        self.0
+            .get(&(node as *const JSXNode))
+            .and_then(|(_, open)| *open)
+    }
+}
+
+struct Differ {
+    old: Spans,
+    new: Spans,
+    ops: Vec<DiffOp>,
+}
+
+impl Differ {
+    fn push(&mut self, kind: DiffKind, path: &[usize], old: &JSXNode, new: &JSXNode) {
+        let (old, new) = match kind {
+            DiffKind::AttributeAdded(_)
+            | DiffKind::AttributeRemoved(_)
+            | DiffKind::AttributeChanged(_) => (self.old.open_tag(old), self.new.open_tag(new)),
+            _ => (self.old.node(old), self.new.node(new)),
+        };
+        self.ops.push(DiffOp {
+            kind,
+            path: path.to_vec(),
+            old,
+            new,
+        });
+    }
+
+    // Two nodes at the same place of their trees.
+    fn node(&mut self, old: &JSXNode, new: &JSXNode, path: &mut Vec<usize>) {
+        if !same_kind(old, new) {
+            self.push(DiffKind::Replaced, path, old, new);
+            return;
+        }
+        match (old, new) {
+            (JSXNode::Text(a), JSXNode::Text(b))
+            | (JSXNode::Expression(a), JSXNode::Expression(b))
+                if a != b =>
+            {
+                self.push(DiffKind::TextChanged, path, old, new);
+            }
+            (
+                JSXNode::Element {
+                    attributes: old_attributes,
+                    ..
+                },
+                JSXNode::Element {
+                    attributes: new_attributes,
+                    ..
+                },
+            ) => {
+                for kind in attribute_changes(old_attributes, new_attributes) {
+                    self.push(kind, path, old, new);
+                }
+            }
+            (
+                JSXNode::EmbeddedExpression { parts: a, .. },
+                JSXNode::EmbeddedExpression { parts: b, .. },
+            ) if code(a).ne(code(b)) => {
+                self.push(DiffKind::TextChanged, path, old, new);
+            }
+            _ => {}
+        }
+        self.children(old, new, path);
+    }
+
+    fn children(&mut self, old: &JSXNode, new: &JSXNode, path: &mut Vec<usize>) {
+        let old_children: Vec<&JSXNode> = children(old).collect();
+        let new_children: Vec<&JSXNode> = children(new).collect();
+        let pairs = common_sequence(&old_children, &new_children);
+
+        let (mut i, mut j) = (0, 0);
+        for (pi, pj) in pairs
+            .into_iter()
+            .chain([(old_children.len(), new_children.len())])
+        {
+            // Unpaired children at the same place replace each other
+            let replaced = (pi - i).min(pj - j);
+            for k in 0..replaced {
+                path.push(j + k);
+                self.push(
+                    DiffKind::Replaced,
+                    path,
+                    old_children[i + k],
+                    new_children[j + k],
+                );
+                path.pop();
+            }
+            for (index, child) in old_children.iter().enumerate().take(pi).skip(i + replaced) {
+                path.push(index);
+                let span = self.old.node(child);
+                self.ops.push(DiffOp {
+                    kind: DiffKind::Removed,
+                    path: path.clone(),
+                    old: span,
+                    new: None,
+                });
+                path.pop();
+            }
+            for (index, child) in new_children.iter().enumerate().take(pj).skip(j + replaced) {
+                path.push(index);
+                let span = self.new.node(child);
+                self.ops.push(DiffOp {
+                    kind: DiffKind::Inserted,
+                    path: path.clone(),
+                    old: None,
+                    new: span,
+                });
+                path.pop();
+            }
+            if pi < old_children.len() {
+                path.push(pj);
+                self.node(old_children[pi], new_children[pj], path);
+                path.pop();
+            }
+            (i, j) = (pi + 1, pj + 1);
+        }
+    }
+}
+
+// Nodes that can be compared in place: same variant, tag and `key`.
+fn same_kind(a: &JSXNode, b: &JSXNode) -> bool {
+    match (a, b) {
+        (JSXNode::Element { tag: a_tag, .. }, JSXNode::Element { tag: b_tag, .. }) => {
+            a_tag == b_tag && a.get_attr("key") == b.get_attr("key")
+        }
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
+
+// Index pairs of the longest common sequence of nodes of the same kind.
+fn common_sequence(old: &[&JSXNode], new: &[&JSXNode]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if same_kind(old[i], new[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < old.len() && j < new.len() {
+        if same_kind(old[i], new[j]) {
+            pairs.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+// Attribute changes by name as written: removed, changed, then added. A
+// repeated name counts with its last value, the one that wins.
+fn attribute_changes(old: &[JSXAttribute], new: &[JSXAttribute]) -> Vec<DiffKind> {
+    fn last<'a>(attributes: &'a [JSXAttribute], name: &str) -> Option<&'a JSXAttribute> {
+        attributes.iter().rev().find(|a| a.name == name)
+    }
+    let mut changes = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+    for attribute in old {
+        if seen.contains(&attribute.name.as_str()) {
+            continue;
+        }
+        seen.push(&attribute.name);
+        match last(new, &attribute.name) {
+            None => changes.push(DiffKind::AttributeRemoved(attribute.name.clone())),
+            Some(value) if Some(value) != last(old, &attribute.name) => {
+                changes.push(DiffKind::AttributeChanged(attribute.name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for attribute in new {
+        if !seen.contains(&attribute.name.as_str()) {
+            seen.push(&attribute.name);
+            changes.push(DiffKind::AttributeAdded(attribute.name.clone()));
+        }
+    }
+    changes
+}
+
+// The code chunks of an embedded expression, without its JSX.
+fn code(parts: &[ExpressionPart]) -> impl Iterator<Item = &str> {
+    parts.iter().filter_map(|part| match part {
+        ExpressionPart::Code(code) => Some(code.as_str()),
+        ExpressionPart::Jsx { .. } => None,
+    })
+}
@@ -0,0 +1,14 @@
+//! Editor features built on the parser: syntax highlighting data, node
+//! lookup, outline and structural diff for the JSX of a source.
+
+mod diff;
+mod outline;
+mod semantic_tokens;
+mod tree;
+
+pub use diff::{diff, DiffKind, DiffOp};
+pub use outline::{outline, OutlineItem, OutlineKind};
+pub use semantic_tokens::{
+    encode_semantic_tokens, semantic_tokens, SemanticToken, SemanticTokenKind, SEMANTIC_TOKEN_TYPES,
+};
+pub use tree::{find_node_at, parse_tree, NodeAt, SpannedRoot};
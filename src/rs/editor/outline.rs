@@ -0,0 +1,69 @@
+//! Outline of the JSX of a source: its elements, components and fragments as
+//! a tree, for editor outline views and folding ranges.
+
+use super::tree::{children, parse_tree};
+use crate::jsx_parser::JSXNode;
+use crate::jsx_transformer::tags_attrs::{classify_tag, TagType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineKind {
+    Element,
+    Component,
+    Fragment,
+}
+
+/// An element, component or fragment with its byte span and the items nested
+/// in it. Text and expressions are left out, the JSX inside expressions is
+/// kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    pub kind: OutlineKind,
+    /// Tag name, `<>` for fragments.
+    pub name: String,
+    pub span: (usize, usize),
+    pub children: Vec<OutlineItem>,
+}
+
+/// The outline of the JSX nodes of a JavaScript source, in document order.
+pub fn outline(source: &str) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    for root in parse_tree(source) {
+        let mut spans = root.spans.iter().copied();
+        collect(&root.node, &mut spans, &mut items);
+    }
+    items
+}
+
+// Adds the item of `node` to `items`, or the items of its descendants when
+// it is not an element or fragment. `spans` yields the spans of `node` and of
+// its descendants in document order.
+fn collect(
+    node: &JSXNode,
+    spans: &mut impl Iterator<Item = (usize, usize)>,
+    items: &mut Vec<OutlineItem>,
+) {
+    let span = spans.next().unwrap_or_default();
+    let (kind, name) = match node {
+        JSXNode::Element { tag, .. } => match classify_tag(tag) {
+            TagType::Component => (OutlineKind::Component, tag.clone()),
+            _ => (OutlineKind::Element, tag.clone()),
+        },
+        JSXNode::Fragment { .. } => (OutlineKind::Fragment, "<>".to_string()),
+        _ => {
+            for child in children(node) {
+                collect(child, spans, items);
+            }
+            return;
+        }
+    };
+    let mut nested = Vec::new();
+    for child in children(node) {
+        collect(child, spans, &mut nested);
+    }
+    items.push(OutlineItem {
+        kind,
+        name,
+        span,
+        children: nested,
+    });
+}
@@ -0,0 +1,125 @@
+//! Classified ranges of the JSX of a source, for semantic highlighting, and
+//! their encoding as LSP `textDocument/semanticTokens` data.
+
+use crate::jsx_parser::{tokenize, JsxTokenKind};
+use crate::jsx_transformer::tags_attrs::{classify_tag, TagType};
+
+/// LSP token types of the kinds, indexed by `SemanticTokenKind as usize`:
+/// the legend to register with the client.
+pub const SEMANTIC_TOKEN_TYPES: &[&str] =
+    &["type", "class", "property", "string", "variable", "string"];
+
+/// Kind of a `SemanticToken`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticTokenKind {
+    /// Name of an HTML element or custom element, e.g. `div` or `my-icon`.
+    Tag,
+    /// Name of a component, e.g. `Card` or `Foo.Bar`.
+    Component,
+    Attribute,
+    /// Quoted attribute value.
+    String,
+    /// JavaScript of an expression or spread attribute.
+    Expression,
+    Text,
+}
+
+impl SemanticTokenKind {
+    /// The LSP standard token type, e.g. `class` for components.
+    pub fn lsp_type(self) -> &'static str {
+        SEMANTIC_TOKEN_TYPES[self as usize]
+    }
+}
+
+/// A classified range, by byte span (start, end) in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub kind: SemanticTokenKind,
+    pub span: (usize, usize),
+}
+
+/// Classified ranges of the JSX of a JavaScript source, in order. Tags are
+/// classified with the default rules of the transformer; punctuation and
+/// whitespace-only text are left out.
+pub fn semantic_tokens(source: &str) -> Vec<SemanticToken> {
+    tokenize(source)
+        .tokens
+        .into_iter()
+        .filter_map(|token| {
+            let text = &source[token.span.0..token.span.1];
+            let kind = match token.kind {
+                JsxTokenKind::TagName => match classify_tag(text) {
+                    TagType::Component => SemanticTokenKind::Component,
+                    _ => SemanticTokenKind::Tag,
+                },
+                JsxTokenKind::AttrName => SemanticTokenKind::Attribute,
+                JsxTokenKind::AttrValue => SemanticTokenKind::String,
+                JsxTokenKind::Expression | JsxTokenKind::Spread => SemanticTokenKind::Expression,
+                JsxTokenKind::Text if !text.trim().is_empty() => SemanticTokenKind::Text,
+                _ => return None,
+            };
+            Some(SemanticToken {
+                kind,
+                span: token.span,
+            })
+        })
+        .collect()
+}
+
+/// Encodes tokens of `source` as LSP semantic tokens data: five integers per
+/// token (delta line, delta start, length, token type, modifiers), with
+/// positions in UTF-16 code units. Tokens spanning several lines are split
+/// into one token per line.
+pub fn encode_semantic_tokens(source: &str, tokens: &[SemanticToken]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let (mut prev_line, mut prev_start) = (0, 0);
+    // Position of `offset` as line and UTF-16 character
+    let (mut offset, mut line, mut character) = (0, 0u32, 0u32);
+
+    for token in tokens {
+        let (start, end) = token.span;
+        if start < offset {
+            continue;
+        }
+        for c in source[offset..start].chars() {
+            if c == '\n' {
+                line += 1;
+                character = 0;
+            } else {
+                character += c.len_utf16() as u32;
+            }
+        }
+
+        let mut segment_start = character;
+        let mut length = 0;
+        let mut push = |line: u32, start: u32, length: u32| {
+            if length == 0 {
+                return;
+            }
+            let delta_start = if line == prev_line {
+                start - prev_start
+            } else {
+                start
+            };
+            data.extend([line - prev_line, delta_start, length, token.kind as u32, 0]);
+            prev_line = line;
+            prev_start = start;
+        };
+        for c in source[start..end].chars() {
+            if c == '\n' {
+                push(line, segment_start, length);
+                line += 1;
+                character = 0;
+                segment_start = 0;
+                length = 0;
+            } else {
+                let width = c.len_utf16() as u32;
+                character += width;
+                length += width;
+            }
+        }
+        push(line, segment_start, length);
+        offset = end;
+    }
+    data
+}
@@ -0,0 +1,143 @@
+//! JSX nodes of a source with the spans of all their descendants, and lookup
+//! of the node at a cursor position (hover, selection expansion).
+
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::{ElementSpans, ExpressionPart, JSXNode, Parser};
+
+/// A top-level JSX node of a source with the byte spans (start, end) of it
+/// and of all its descendants, in document order: `spans[0]` is the span of
+/// `node`.
+#[derive(Debug, PartialEq)]
+pub struct SpannedRoot {
+    pub node: JSXNode,
+    pub spans: Vec<(usize, usize)>,
+    /// Tag and children spans of its elements, in document order.
+    pub elements: Vec<ElementSpans>,
+}
+
+/// The innermost node containing an offset, with its ancestors from the
+/// outermost (a root) to its parent.
+#[derive(Debug, PartialEq)]
+pub struct NodeAt<'a> {
+    pub node: &'a JSXNode,
+    pub span: (usize, usize),
+    pub ancestors: Vec<(&'a JSXNode, (usize, usize))>,
+}
+
+impl SpannedRoot {
+    /// Every node of the tree with its span, in document order.
+    pub fn nodes(&self) -> impl Iterator<Item = (&JSXNode, (usize, usize))> {
+        let mut nodes = Vec::with_capacity(self.spans.len());
+        preorder(&self.node, &mut nodes);
+        nodes.into_iter().zip(self.spans.iter().copied())
+    }
+
+    /// Span of `node`, which must be a node of this tree (it is found by
+    /// address, not by value); None otherwise.
+    pub fn raw_span(&self, node: &JSXNode) -> Option<(usize, usize)> {
+        self.nodes()
+            .find(|(n, _)| std::ptr::eq(*n, node))
+            .map(|(_, span)| span)
+    }
+
+    /// Source of `node` exactly as written, `source` being the text the tree
+    /// was parsed from.
+    pub fn raw<'s>(&self, source: &'s str, node: &JSXNode) -> Option<&'s str> {
+        let (start, end) = self.raw_span(node)?;
+        source.get(start..end)
+    }
+}
+
+/// The JSX nodes of a JavaScript source, found as the transform finds them.
+/// Nodes that fail to parse are left out.
+pub fn parse_tree(source: &str) -> Vec<SpannedRoot> {
+    let mut roots = Vec::new();
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        let mut p = Parser::new(&source[next..]);
+        match p.parse_next_with_span() {
+            Some(Ok((node, (_, end)))) => {
+                let shift = |(start, end): (usize, usize)| (next + start, next + end);
+                let spans = p.node_spans().iter().copied().map(shift).collect();
+                let elements = p
+                    .element_spans()
+                    .iter()
+                    .map(|spans| ElementSpans {
+                        open: shift(spans.open),
+                        close: spans.close.map(shift),
+                        children: shift(spans.children),
+                    })
+                    .collect();
+                roots.push(SpannedRoot {
+                    node,
+                    spans,
+                    elements,
+                });
+                i = next + end;
+            }
+            Some(Err(_)) => i = next + 1,
+            None => break,
+        }
+    }
+    roots
+}
+
+/// The innermost node whose span contains the byte `offset`, or None when the
+/// offset is outside any JSX.
+pub fn find_node_at(roots: &[SpannedRoot], offset: usize) -> Option<NodeAt<'_>> {
+    let root = roots.iter().find(|root| contains(root.spans[0], offset))?;
+    let mut ancestors = Vec::new();
+    let mut index = 0;
+    let (mut node, mut span) = (&root.node, root.spans[0]);
+    'descend: loop {
+        index += 1;
+        for child in children(node) {
+            let child_span = root.spans[index];
+            if contains(child_span, offset) {
+                ancestors.push((node, span));
+                (node, span) = (child, child_span);
+                continue 'descend;
+            }
+            index += subtree_len(child);
+        }
+        return Some(NodeAt {
+            node,
+            span,
+            ancestors,
+        });
+    }
+}
+
+#[inline]
+fn contains((start, end): (usize, usize), offset: usize) -> bool {
+    start <= offset && offset < end
+}
+
+// Child nodes in document order: the children of elements and fragments, the
+// JSX parts of embedded expressions.
+pub(crate) fn children(node: &JSXNode) -> Box<dyn Iterator<Item = &JSXNode> + '_> {
+    match node {
+        JSXNode::Element { children, .. } | JSXNode::Fragment { children } => {
+            Box::new(children.iter())
+        }
+        JSXNode::EmbeddedExpression { parts, .. } => {
+            Box::new(parts.iter().filter_map(|part| match part {
+                ExpressionPart::Jsx { node, .. } => Some(node),
+                ExpressionPart::Code(_) => None,
+            }))
+        }
+        JSXNode::Text(_) | JSXNode::Expression(_) => Box::new(std::iter::empty()),
+    }
+}
+
+fn preorder<'a>(node: &'a JSXNode, nodes: &mut Vec<&'a JSXNode>) {
+    nodes.push(node);
+    for child in children(node) {
+        preorder(child, nodes);
+    }
+}
+
+// Number of nodes in the subtree of `node`, itself included.
+fn subtree_len(node: &JSXNode) -> usize {
+    1 + children(node).map(subtree_len).sum::<usize>()
+}
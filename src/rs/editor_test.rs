@@ -0,0 +1,229 @@
+use crate::editor::{
+    diff, encode_semantic_tokens, find_node_at, outline, parse_tree, semantic_tokens, DiffKind,
+    OutlineKind, SemanticTokenKind, SEMANTIC_TOKEN_TYPES,
+};
+use crate::jsx_parser::JSXNode;
+
+#[test]
+fn test_semantic_tokens() {
+    use SemanticTokenKind::*;
+
+    let source = r#"const a = <Card title="Hi" {...rest}><my-icon /> Hello {name} </Card>;"#;
+    let tokens: Vec<_> = semantic_tokens(source)
+        .iter()
+        .map(|t| (t.kind, &source[t.span.0..t.span.1]))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (Component, "Card"),
+            (Attribute, "title"),
+            (String, "\"Hi\""),
+            (Expression, "...rest"),
+            (Tag, "my-icon"),
+            (Text, " Hello "),
+            (Expression, "name"),
+            (Component, "Card"),
+        ]
+    );
+    assert_eq!(Component.lsp_type(), "class");
+    assert_eq!(SEMANTIC_TOKEN_TYPES.len(), Text as usize + 1);
+}
+
+#[test]
+fn test_encode_semantic_tokens() {
+    // `é` is one UTF-16 unit, `😀` two; the text child spans two lines
+    let source = "const é = <p title=\"😀\">a\nbc</p>;";
+    let data = encode_semantic_tokens(source, &semantic_tokens(source));
+    assert_eq!(
+        data,
+        vec![
+            0, 11, 1, 0, 0, // p
+            0, 2, 5, 2, 0, // title
+            0, 6, 4, 3, 0, // "😀"
+            0, 5, 1, 5, 0, // a
+            1, 0, 2, 5, 0, // bc
+            0, 4, 1, 0, 0, // p
+        ]
+    );
+}
+
+#[test]
+fn test_find_node_at() {
+    let source = "const a = <ul>{items.map(i => <li>{i}</li>)}</ul>;\nconst b = <p>ok</p>;";
+    let roots = parse_tree(source);
+    assert_eq!(roots.len(), 2);
+
+    let offset = source.find("{i}").unwrap() + 1;
+    let found = find_node_at(&roots, offset).unwrap();
+    assert_eq!(found.node, &JSXNode::Expression("i".to_string()));
+    assert_eq!(&source[found.span.0..found.span.1], "{i}");
+    let ancestors: Vec<&str> = found
+        .ancestors
+        .iter()
+        .map(|(_, (start, end))| &source[*start..*end])
+        .collect();
+    assert_eq!(
+        ancestors,
+        [
+            "<ul>{items.map(i => <li>{i}</li>)}</ul>",
+            "{items.map(i => <li>{i}</li>)}",
+            "<li>{i}</li>",
+        ]
+    );
+
+    let found = find_node_at(&roots, source.find("ok").unwrap()).unwrap();
+    assert_eq!(found.node, &JSXNode::Text("ok".to_string()));
+    assert_eq!(found.ancestors.len(), 1);
+
+    // On a tag name, the element itself
+    let found = find_node_at(&roots, source.find("<p>").unwrap() + 1).unwrap();
+    assert!(matches!(found.node, JSXNode::Element { tag, .. } if tag == "p"));
+
+    let p = &roots[1].elements[0];
+    assert_eq!(&source[p.open.0..p.open.1], "<p>");
+    assert_eq!(
+        p.close.map(|(start, end)| &source[start..end]),
+        Some("</p>")
+    );
+
+    assert!(find_node_at(&roots, 0).is_none());
+    assert!(find_node_at(&roots, source.len()).is_none());
+}
+
+#[test]
+fn test_outline() {
+    let source = "const a = <Layout>\n  <ul>{items.map(i => <li>{i}</li>)}</ul>\n  <>text</>\n</Layout>;\nconst b = <p/>;";
+    let items = outline(source);
+    let summary: Vec<(OutlineKind, &str, &str)> = items
+        .iter()
+        .map(|item| {
+            (
+                item.kind,
+                item.name.as_str(),
+                &source[item.span.0..item.span.1],
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                OutlineKind::Component,
+                "Layout",
+                "<Layout>\n  <ul>{items.map(i => <li>{i}</li>)}</ul>\n  <>text</>\n</Layout>"
+            ),
+            (OutlineKind::Element, "p", "<p/>"),
+        ]
+    );
+
+    let layout = &items[0].children;
+    assert_eq!(layout.len(), 2);
+    assert_eq!(layout[0].name, "ul");
+    assert_eq!(layout[1].kind, OutlineKind::Fragment);
+    assert!(layout[1].children.is_empty());
+    // JSX inside expressions is nested in the enclosing element
+    let li = &layout[0].children;
+    assert_eq!(li.len(), 1);
+    assert_eq!(&source[li[0].span.0..li[0].span.1], "<li>{i}</li>");
+    assert!(items[1].children.is_empty());
+
+    assert!(outline("const a = 1 < 2;").is_empty());
+}
+
+#[test]
+fn test_raw_node_text() {
+    let source = "const a = <p  class='x'>Hi {name}&nbsp;</p>;";
+    let roots = parse_tree(source);
+    let root = &roots[0];
+    assert_eq!(
+        root.raw(source, &root.node),
+        Some("<p  class='x'>Hi {name}&nbsp;</p>")
+    );
+
+    let raw: Vec<&str> = root
+        .nodes()
+        .map(|(_, (start, end))| &source[start..end])
+        .collect();
+    assert_eq!(
+        raw,
+        [
+            "<p  class='x'>Hi {name}&nbsp;</p>",
+            "Hi ",
+            "{name}",
+            "&nbsp;"
+        ]
+    );
+
+    let JSXNode::Element { children, .. } = &root.node else {
+        panic!("expected an element");
+    };
+    assert_eq!(root.raw(source, &children[1]), Some("{name}"));
+    // Equal by value but not a node of the tree
+    assert_eq!(
+        root.raw_span(&JSXNode::Expression("name".to_string())),
+        None
+    );
+}
+
+#[test]
+fn test_diff() {
+    let old =
+        r#"<ul class="a" hidden><li>one</li><li key="x">two</li>{items.map(i => <b>{i}</b>)}</ul>"#;
+    let new =
+        r#"<ul class="b" id="list"><p>zero</p><li>one!</li>{items.map(i => <i>{i}</i>)}</ul>"#;
+    let (old_root, new_root) = (&parse_tree(old)[0], &parse_tree(new)[0]);
+    let ops = diff(old_root, new_root);
+    fn text(source: &str, span: Option<(usize, usize)>) -> Option<&str> {
+        span.map(|(start, end)| &source[start..end])
+    }
+    let summary: Vec<_> = ops
+        .iter()
+        .map(|op| {
+            (
+                op.kind.clone(),
+                op.path.clone(),
+                text(old, op.old),
+                text(new, op.new),
+            )
+        })
+        .collect();
+    let ul = (
+        Some(r#"<ul class="a" hidden>"#),
+        Some(r#"<ul class="b" id="list">"#),
+    );
+    assert_eq!(
+        summary,
+        vec![
+            (
+                DiffKind::AttributeChanged("class".into()),
+                vec![],
+                ul.0,
+                ul.1
+            ),
+            (
+                DiffKind::AttributeRemoved("hidden".into()),
+                vec![],
+                ul.0,
+                ul.1
+            ),
+            (DiffKind::AttributeAdded("id".into()), vec![], ul.0, ul.1),
+            (DiffKind::Inserted, vec![0], None, Some("<p>zero</p>")),
+            (DiffKind::TextChanged, vec![1, 0], Some("one"), Some("one!")),
+            (
+                DiffKind::Removed,
+                vec![1],
+                Some(r#"<li key="x">two</li>"#),
+                None
+            ),
+            (
+                DiffKind::Replaced,
+                vec![2, 0],
+                Some("<b>{i}</b>"),
+                Some("<i>{i}</i>")
+            ),
+        ]
+    );
+
+    assert!(diff(old_root, &parse_tree(old)[0]).is_empty());
+}
@@ -0,0 +1,31 @@
+//! JSX regions of a JavaScript source, for tools that only need to find them
+//! (highlighters, formatters of embedded markup, i18n extractors) and not
+//! transform them.
+//!
+//! Regions are found as the transform finds them: a `<` in a string, a
+//! comment, the text of a template literal or a comparison does not start
+//! JSX. Regions that fail to parse are left out, and scanning resumes as the
+//! transform does after an error (see `jsx_parser::recovery_offset`).
+
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::{recovery_offset, Parser, Span};
+
+/// The top-level JSX nodes of `source` in document order, with their byte
+/// spans and text. The JSX of embedded expressions is part of the node
+/// enclosing it.
+pub fn jsx_slices(source: &str) -> Vec<(Span, String)> {
+    let mut slices = Vec::new();
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        match Parser::new(&source[next..]).parse_next_with_span() {
+            Some(Ok((_, (start, end)))) => {
+                let span = Span::new(next + start, next + end);
+                slices.push((span, source[span.start..span.end].to_string()));
+                i = span.end;
+            }
+            Some(Err(_)) => i = recovery_offset(source, next),
+            None => break,
+        }
+    }
+    slices
+}
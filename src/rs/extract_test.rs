@@ -0,0 +1,25 @@
+use crate::extract::jsx_slices;
+use crate::jsx_parser::Span;
+
+#[test]
+fn test_jsx_slices() {
+    let source = "const a = <p>{x < y && <b/>}</p>;\nconst b = <><i>ok</i></>;";
+    let slices = jsx_slices(source);
+    let texts: Vec<&str> = slices.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(texts, ["<p>{x < y && <b/>}</p>", "<><i>ok</i></>"]);
+    for (span, text) in &slices {
+        assert_eq!(&source[span.start..span.end], text);
+    }
+    assert_eq!(slices[0].0, Span::new(10, 32));
+}
+
+#[test]
+fn test_jsx_slices_skip_strings_comments_and_errors() {
+    let source = r#"const s = "<p>no</p>"; // <b>no</b>
+/* <i>no</i> */ const t = `<em>no</em>`;
+if (a < b) {}
+const bad = <div><span></div>;
+const ok = <a href="/">yes</a>;"#;
+    let texts: Vec<String> = jsx_slices(source).into_iter().map(|(_, t)| t).collect();
+    assert_eq!(texts, [r#"<a href="/">yes</a>"#]);
+}
@@ -0,0 +1,208 @@
+mod parser;
+
+use crate::jsx_transformer::tags_attrs::HTML_VOID_TAGS;
+use crate::jsx_transformer::{format_diagnostic, JSXError, JSXErrorKind};
+use parser::{parse_html, HtmlNode};
+
+// Elements whose children are emitted as a template literal expression so
+// their raw text (CSS, scripts) survives untouched.
+const RAW_EXPRESSION_TAGS: [&str; 2] = ["script", "style"];
+
+/// Options for converting HTML to JSX.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlToJsxOptions {
+    /// Emit `style` attributes as objects, e.g. `style={{ fontSize: "12px" }}`,
+    /// as React-style JSX expects. Off by default: sxo renders `style`
+    /// strings as written.
+    pub style_objects: bool,
+}
+
+// Converts an HTML fragment into equivalent JSX source.
+// - `class` and `for` become `className` and `htmlFor`
+// - Void elements self-close, comments become `{/* */}` expressions
+// - Several root nodes are wrapped in a fragment
+pub fn html_to_jsx(html: &str) -> Result<String, JSXError> {
+    html_to_jsx_with_options(html, &HtmlToJsxOptions::default())
+}
+
+// Same as `html_to_jsx`, with explicit conversion options.
+pub fn html_to_jsx_with_options(
+    html: &str,
+    options: &HtmlToJsxOptions,
+) -> Result<String, JSXError> {
+    let nodes = parse_html(html).map_err(|(pos, message)| {
+        JSXError::with_kind(JSXErrorKind::ParsingError(format_diagnostic(
+            html, pos, message,
+        )))
+    })?;
+
+    // Whitespace around the fragment is not content
+    let is_blank = |n: &HtmlNode| matches!(n, HtmlNode::Text(t) if t.trim().is_empty());
+    let start = nodes
+        .iter()
+        .position(|n| !is_blank(n))
+        .unwrap_or(nodes.len());
+    let end = nodes
+        .iter()
+        .rposition(|n| !is_blank(n))
+        .map_or(start, |i| i + 1);
+    let nodes = &nodes[start..end];
+
+    let mut out = String::with_capacity(html.len() + 16);
+    let wrap = nodes.iter().filter(|n| !is_blank(n)).count() > 1;
+    if wrap {
+        out.push_str("<>");
+    }
+    for node in nodes {
+        write_node(&mut out, node, false, options);
+    }
+    if wrap {
+        out.push_str("</>");
+    }
+    Ok(out)
+}
+
+fn write_node(out: &mut String, node: &HtmlNode, foreign: bool, options: &HtmlToJsxOptions) {
+    match node {
+        HtmlNode::Text(text) => {
+            for c in text.chars() {
+                match c {
+                    '{' => out.push_str("{\"{\"}"),
+                    '}' => out.push_str("{\"}\"}"),
+                    '<' => out.push_str("{\"<\"}"),
+                    c => out.push(c),
+                }
+            }
+        }
+        HtmlNode::Comment(text) => {
+            out.push_str("{/*");
+            out.push_str(&text.replace("*/", "* /"));
+            out.push_str("*/}");
+        }
+        HtmlNode::Element {
+            tag,
+            attributes,
+            children,
+        } => {
+            let lower = tag.to_lowercase();
+            // SVG and MathML names are case-sensitive; HTML names are not
+            let foreign =
+                (foreign || lower == "svg" || lower == "math") && lower != "foreignobject";
+            let tag = if foreign { tag.clone() } else { lower };
+            out.push('<');
+            out.push_str(&tag);
+            for (name, value) in attributes {
+                out.push(' ');
+                write_attribute(out, name, value.as_deref(), foreign, options);
+            }
+            if children.is_empty() || HTML_VOID_TAGS.contains(&tag.as_str()) {
+                out.push_str(" />");
+                return;
+            }
+            out.push('>');
+            if RAW_EXPRESSION_TAGS.contains(&tag.as_str()) {
+                for child in children {
+                    if let HtmlNode::Text(text) = child {
+                        out.push_str("{`");
+                        out.push_str(&escape_template(text));
+                        out.push_str("`}");
+                    }
+                }
+            } else {
+                for child in children {
+                    write_node(out, child, foreign, options);
+                }
+            }
+            out.push_str("</");
+            out.push_str(&tag);
+            out.push('>');
+        }
+    }
+}
+
+fn write_attribute(
+    out: &mut String,
+    name: &str,
+    value: Option<&str>,
+    foreign: bool,
+    options: &HtmlToJsxOptions,
+) {
+    let name = if foreign {
+        name.to_string()
+    } else {
+        name.to_lowercase()
+    };
+    out.push_str(match name.as_str() {
+        "class" => "className",
+        "for" => "htmlFor",
+        name => name,
+    });
+    let Some(value) = value else {
+        return;
+    };
+    if name == "style" && options.style_objects {
+        out.push_str("={");
+        out.push_str(&style_object(value));
+        out.push('}');
+    } else if !value.contains('"') {
+        out.push_str(&format!("=\"{value}\""));
+    } else if !value.contains('\'') {
+        out.push_str(&format!("='{value}'"));
+    } else {
+        out.push_str(&format!("={{{}}}", js_string(value)));
+    }
+}
+
+// `color: red; font-size: 12px` as `{ color: "red", fontSize: "12px" }`.
+fn style_object(style: &str) -> String {
+    let entries: Vec<String> = style
+        .split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(property, value)| {
+            let property = property.trim();
+            let key = if property.starts_with("--") {
+                js_string(property)
+            } else {
+                style_property_name(property)
+            };
+            format!("{key}: {}", js_string(value.trim()))
+        })
+        .collect();
+    if entries.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", entries.join(", "))
+    }
+}
+
+// CSS property name in camelCase: `font-size` to `fontSize`,
+// `-webkit-transition` to `WebkitTransition`, `-ms-transform` to `msTransform`.
+fn style_property_name(property: &str) -> String {
+    let property = property.to_lowercase();
+    let property = property
+        .strip_prefix("-ms-")
+        .map_or(property.clone(), |p| format!("ms-{p}"));
+    let mut out = String::with_capacity(property.len());
+    let mut upper = false;
+    for c in property.chars() {
+        if c == '-' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn js_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn escape_template(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
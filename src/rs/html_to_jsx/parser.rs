@@ -0,0 +1,173 @@
+use crate::jsx_transformer::tags_attrs::HTML_VOID_TAGS;
+
+// Elements whose content is raw text: no tags or comments inside.
+const RAW_TEXT_TAGS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+// Error messages
+const ERR_UNCLOSED_COMMENT: &str = "Unclosed comment";
+const ERR_UNCLOSED_START_TAG: &str = "Unclosed start tag";
+const ERR_UNTERMINATED_ATTR: &str = "Unterminated attribute value";
+
+pub(crate) type Attributes = Vec<(String, Option<String>)>;
+
+// An element still open while parsing: tag, attributes and children so far.
+type OpenElement = (String, Attributes, Vec<HtmlNode>);
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum HtmlNode {
+    Element {
+        tag: String,
+        attributes: Attributes,
+        children: Vec<HtmlNode>,
+    },
+    Text(String),
+    Comment(String),
+}
+
+/// Lenient HTML fragment parser: end tags close the nearest matching open
+/// element, stray end tags are ignored and open elements close at the end.
+/// Errors carry the byte position they occurred at.
+pub(crate) fn parse_html(html: &str) -> Result<Vec<HtmlNode>, (usize, &'static str)> {
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut roots: Vec<HtmlNode> = Vec::new();
+    let mut pos = 0;
+
+    while pos < html.len() {
+        let rest = &html[pos..];
+        let children = match stack.last_mut() {
+            Some((_, _, children)) => children,
+            None => &mut roots,
+        };
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or((pos, ERR_UNCLOSED_COMMENT))?;
+            children.push(HtmlNode::Comment(comment[..end].to_string()));
+            pos += 4 + end + 3;
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            // Doctype and processing instructions have no JSX equivalent
+            pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+        } else if let Some(name) = rest.strip_prefix("</").filter(|r| starts_name(r)) {
+            let end = name.find('>').ok_or((pos, ERR_UNCLOSED_START_TAG))?;
+            let tag = name[..end].trim().to_lowercase();
+            if let Some(open) = stack.iter().rposition(|(t, _, _)| t.to_lowercase() == tag) {
+                while stack.len() > open {
+                    close_element(&mut stack, &mut roots);
+                }
+            }
+            pos += 2 + end + 1;
+        } else if rest.starts_with('<') && starts_name(&rest[1..]) {
+            let (tag, attributes, self_closing, len) =
+                parse_start_tag(rest).map_err(|e| (pos, e))?;
+            pos += len;
+            let lower = tag.to_lowercase();
+            if self_closing || HTML_VOID_TAGS.contains(&lower.as_str()) {
+                children.push(HtmlNode::Element {
+                    tag,
+                    attributes,
+                    children: Vec::new(),
+                });
+            } else if RAW_TEXT_TAGS.contains(&lower.as_str()) {
+                let content = &html[pos..];
+                let end = find_ignore_case(content, &format!("</{lower}")).unwrap_or(content.len());
+                let mut raw = Vec::new();
+                if end > 0 {
+                    raw.push(HtmlNode::Text(content[..end].to_string()));
+                }
+                children.push(HtmlNode::Element {
+                    tag,
+                    attributes,
+                    children: raw,
+                });
+                pos += end;
+                pos += html[pos..].find('>').map_or(html.len() - pos, |e| e + 1);
+            } else {
+                stack.push((tag, attributes, Vec::new()));
+            }
+        } else {
+            let end = rest[1..].find('<').map_or(rest.len(), |i| i + 1);
+            children.push(HtmlNode::Text(rest[..end].to_string()));
+            pos += end;
+        }
+    }
+    while !stack.is_empty() {
+        close_element(&mut stack, &mut roots);
+    }
+    Ok(roots)
+}
+
+fn close_element(stack: &mut Vec<OpenElement>, roots: &mut Vec<HtmlNode>) {
+    if let Some((tag, attributes, children)) = stack.pop() {
+        let node = HtmlNode::Element {
+            tag,
+            attributes,
+            children,
+        };
+        match stack.last_mut() {
+            Some((_, _, siblings)) => siblings.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+#[inline]
+fn starts_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        haystack
+            .get(i..i + needle.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(needle))
+    })
+}
+
+// Parses `<tag attr="v" flag>` at the start of `s`. Returns the tag, its
+// attributes, whether it was written self-closing, and the consumed length.
+fn parse_start_tag(s: &str) -> Result<(String, Attributes, bool, usize), &'static str> {
+    let is_name_end = |c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=';
+    let name_len = s[1..].find(is_name_end).ok_or(ERR_UNCLOSED_START_TAG)?;
+    let tag = s[1..1 + name_len].to_string();
+    let mut attributes = Vec::new();
+    let mut i = 1 + name_len;
+    loop {
+        i += s[i..].len() - s[i..].trim_start().len();
+        let rest = &s[i..];
+        if rest.starts_with('>') {
+            return Ok((tag, attributes, false, i + 1));
+        }
+        if rest.starts_with("/>") {
+            return Ok((tag, attributes, true, i + 2));
+        }
+        if rest.is_empty() {
+            return Err(ERR_UNCLOSED_START_TAG);
+        }
+        if rest.starts_with('/') {
+            i += 1;
+            continue;
+        }
+        let len = rest[1..].find(is_name_end).map_or(rest.len(), |l| l + 1);
+        let name = rest[..len].to_string();
+        i += len;
+        let after = s[i..].trim_start();
+        if let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            i = s.len() - value.len();
+            let (value, len) = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = value[1..].find(quote).ok_or(ERR_UNTERMINATED_ATTR)?;
+                    (value[1..1 + end].to_string(), end + 2)
+                }
+                _ => {
+                    let end = value
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(value.len());
+                    (value[..end].to_string(), end)
+                }
+            };
+            attributes.push((name, Some(value)));
+            i += len;
+        } else {
+            attributes.push((name, None));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::html_to_jsx::{html_to_jsx, html_to_jsx_with_options, HtmlToJsxOptions};
+use crate::jsx_parser::{JSXNode, Parser};
+use crate::jsx_transformer::{jsx_transformer, JSXError};
+
+#[test]
+fn test_html_to_jsx_attributes_and_voids() {
+    assert_eq!(
+        html_to_jsx(
+            r#"<!DOCTYPE html>
+<DIV CLASS="card" data-id=7 hidden><label for="name">Name</label><input id=name disabled><br><img src="a.png" alt='say "hi"'/></DIV>"#
+        )
+        .unwrap(),
+        r#"<div className="card" data-id="7" hidden><label htmlFor="name">Name</label><input id="name" disabled /><br /><img src="a.png" alt='say "hi"' /></div>"#
+    );
+    assert_eq!(
+        html_to_jsx(r#"<p title="it's &quot;x&quot;" data-x='a"b&#39;'></p>"#).unwrap(),
+        r#"<p title="it's &quot;x&quot;" data-x='a"b&#39;' />"#
+    );
+    assert_eq!(
+        html_to_jsx(r#"<p title='both " and &apos;'>x</p>"#).unwrap(),
+        r#"<p title='both " and &apos;'>x</p>"#
+    );
+}
+
+#[test]
+fn test_html_to_jsx_text_comments_and_roots() {
+    assert_eq!(
+        html_to_jsx("\n  <h1>Hi {name}</h1>\n  <!-- note */ -->\n  <p>a<b>b</p>\n").unwrap(),
+        "<><h1>Hi {\"{\"}name{\"}\"}</h1>\n  {/* note * / */}\n  <p>a<b>b</b></p></>"
+    );
+    assert_eq!(
+        html_to_jsx("<style>a { color: `red` }</style><p>x</i></p>").unwrap(),
+        "<><style>{`a { color: \\`red\\` }`}</style><p>x</p></>"
+    );
+    assert!(matches!(
+        html_to_jsx("<p>x<!-- open"),
+        Err(JSXError::ParsingError(_))
+    ));
+}
+
+#[test]
+fn test_html_to_jsx_foreign_content() {
+    assert_eq!(
+        html_to_jsx(r#"<svg viewBox="0 0 2 2"><linearGradient gradientUnits="x"/><path stroke-width="2"></path></svg>"#)
+            .unwrap(),
+        r#"<svg viewBox="0 0 2 2"><linearGradient gradientUnits="x" /><path stroke-width="2" /></svg>"#
+    );
+}
+
+#[test]
+fn test_html_to_jsx_style_objects() {
+    let html =
+        r#"<p style="color: red; font-size: 12px; -webkit-transition: none; --gap: 2px;">x</p>"#;
+    assert_eq!(html_to_jsx(html).unwrap(), html);
+    let options = HtmlToJsxOptions {
+        style_objects: true,
+    };
+    assert_eq!(
+        html_to_jsx_with_options(html, &options).unwrap(),
+        r#"<p style={{ color: "red", fontSize: "12px", WebkitTransition: "none", "--gap": "2px" }}>x</p>"#
+    );
+}
+
+#[test]
+fn test_html_to_jsx_round_trip() {
+    let html = r#"<ul class="menu"><li><a href="/">Home</a></li><li><input type="checkbox" checked></li></ul>"#;
+    assert_eq!(
+        jsx_transformer(&html_to_jsx(html).unwrap()).unwrap(),
+        r#"`<ul class="menu"><li><a href="/">Home</a></li><li><input type="checkbox" checked/></li></ul>`"#
+    );
+}
+
+#[test]
+fn test_html_to_jsx_less_than_in_text() {
+    let jsx = html_to_jsx("<p>a < b {c}</p>").unwrap();
+    assert_eq!(jsx, r#"<p>a {"<"} b {"{"}c{"}"}</p>"#);
+    let JSXNode::Element { children, .. } = Parser::new(&jsx).parse().unwrap() else {
+        panic!("Expected Element");
+    };
+    assert_eq!(
+        children,
+        [
+            JSXNode::Text("a ".to_string()),
+            JSXNode::Expression(r#""<""#.to_string()),
+            JSXNode::Text(" b ".to_string()),
+            JSXNode::Expression(r#""{""#.to_string()),
+            JSXNode::Text("c".to_string()),
+            JSXNode::Expression(r#""}""#.to_string()),
+        ]
+    );
+}
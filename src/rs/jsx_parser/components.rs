@@ -0,0 +1,86 @@
+//! Components referenced by the JSX of a source, for bundlers checking that
+//! each one is imported ("missing import" and "unused import" diagnostics).
+
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::parser::{recovery_offset, Parser};
+use crate::jsx_parser::types::{ElementSpans, JSXAttribute};
+use crate::jsx_parser::visitor::{walk_node, JSXVisitor};
+use crate::jsx_transformer::tags_attrs::{classify_tag, TagType};
+
+/// An element rendered as a component, `<Card title="x"/>` or `<Ui.Button>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentRef {
+    /// Tag name as written, member expressions included.
+    pub name: String,
+    /// Byte span of the tag name in the opening tag.
+    pub span: (usize, usize),
+    /// Attribute names in source order, spreads as written (`...rest`).
+    pub props: Vec<String>,
+}
+
+impl ComponentRef {
+    /// The identifier that must be in scope: `Ui` for `<Ui.Button>`.
+    pub fn binding(&self) -> &str {
+        self.name.split('.').next().unwrap_or(&self.name)
+    }
+}
+
+/// Component references of a JavaScript source in document order, one per
+/// element, the JSX of embedded expressions included. Nodes that fail to
+/// parse are left out.
+pub fn components(source: &str) -> Vec<ComponentRef> {
+    let mut collector = Collector {
+        source,
+        base: 0,
+        elements: Vec::new(),
+        next: 0,
+        refs: Vec::new(),
+    };
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        let mut parser = Parser::new(&source[next..]);
+        match parser.parse_next_with_span() {
+            Some(Ok((node, (_, end)))) => {
+                collector.base = next;
+                collector.elements = parser.element_spans().to_vec();
+                collector.next = 0;
+                walk_node(&mut collector, &node);
+                i = next + end;
+            }
+            Some(Err(_)) => i = recovery_offset(source, next),
+            None => break,
+        }
+    }
+    collector.refs
+}
+
+// Collects the references of a parsed node, whose element spans are
+// relative to `base` and in the order elements are entered.
+struct Collector<'a> {
+    source: &'a str,
+    base: usize,
+    elements: Vec<ElementSpans>,
+    next: usize,
+    refs: Vec<ComponentRef>,
+}
+
+impl JSXVisitor for Collector<'_> {
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute]) {
+        let open = self
+            .elements
+            .get(self.next)
+            .map_or((0, 0), |spans| spans.open);
+        self.next += 1;
+        if classify_tag(tag) != TagType::Component {
+            return;
+        }
+        // The name follows `<` and any whitespace
+        let after = &self.source[self.base + open.0 + 1..];
+        let start = self.source.len() - after.trim_start().len();
+        self.refs.push(ComponentRef {
+            name: tag.to_string(),
+            span: (start, start + tag.len()),
+            props: attributes.iter().map(|a| a.name.clone()).collect(),
+        });
+    }
+}
@@ -0,0 +1,387 @@
+//! Import analysis for the components of a source: which components used in
+//! its JSX are neither imported nor declared in it, the usual cause of a
+//! `__jsxComponent(UndefinedName, ...)` failing at runtime, and the edits
+//! that import them.
+//!
+//! Imports and declarations are found by a simple lexer that skips comments,
+//! strings, template literals and JSX. It sees `import` statements and the
+//! names declared with `function`, `class`, `const`, `let` and `var`;
+//! parameters and destructured names are not seen.
+
+use crate::jsx_parser::components::{components, ComponentRef};
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::parser::Parser;
+
+/// An `import` statement of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    /// The module specifier, `./card.js`.
+    pub module: String,
+    /// Local names bound by the statement: the default import, the namespace
+    /// and the named imports (after `as`). Empty for `import "./x.css"` and
+    /// type-only imports.
+    pub bindings: Vec<String>,
+    /// Byte span of the statement, its `;` included.
+    pub span: (usize, usize),
+}
+
+/// A component used in JSX whose binding is neither imported nor declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingImport {
+    /// The binding to import, `Ui` for `<Ui.Button>`.
+    pub name: String,
+    /// Every reference to it, in document order.
+    pub references: Vec<ComponentRef>,
+}
+
+/// Replacement of the byte span `span` of a source by `text`; an insertion
+/// when the span is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: (usize, usize),
+    pub text: String,
+}
+
+/// The `import` statements of a JavaScript source, in document order.
+pub fn scan_imports(source: &str) -> Vec<Import> {
+    let mut lexer = Lexer::new(source);
+    let mut imports = Vec::new();
+    while let Some(token) = lexer.next() {
+        if token.text == "import" && !token.after_dot {
+            if let Some(import) = import_statement(&mut lexer, token.start) {
+                imports.push(import);
+            }
+        }
+    }
+    imports
+}
+
+/// Components of `source` whose binding is not in scope, given the imports
+/// of the module (from `scan_imports`, or from the caller when it knows them
+/// better), in the order of their first reference. Names declared in the
+/// source count as in scope.
+pub fn missing_imports(source: &str, imports: &[Import]) -> Vec<MissingImport> {
+    let declared = declarations(source);
+    let mut missing: Vec<MissingImport> = Vec::new();
+    for reference in components(source) {
+        let name = reference.binding();
+        if imports.iter().any(|i| i.bindings.iter().any(|b| b == name))
+            || declared.iter().any(|d| d == name)
+        {
+            continue;
+        }
+        match missing.iter_mut().find(|m| m.name == name) {
+            Some(entry) => entry.references.push(reference),
+            None => missing.push(MissingImport {
+                name: name.to_string(),
+                references: vec![reference],
+            }),
+        }
+    }
+    missing
+}
+
+/// Edits adding a named import for each missing component that `resolve`
+/// maps to a module specifier, one statement per module, after the last
+/// import of `source` (or at its start). Components it cannot resolve are
+/// left out.
+pub fn auto_import_edits(
+    source: &str,
+    missing: &[MissingImport],
+    resolve: impl Fn(&str) -> Option<String>,
+) -> Vec<TextEdit> {
+    let mut modules: Vec<(String, Vec<&str>)> = Vec::new();
+    for entry in missing {
+        let Some(module) = resolve(&entry.name) else {
+            continue;
+        };
+        match modules.iter_mut().find(|(m, _)| *m == module) {
+            Some((_, names)) => names.push(&entry.name),
+            None => modules.push((module, vec![&entry.name])),
+        }
+    }
+
+    let after_imports = scan_imports(source).last().map(|i| i.span.1);
+    modules
+        .into_iter()
+        .map(|(module, names)| {
+            let statement = format!("import {{ {} }} from \"{module}\";", names.join(", "));
+            match after_imports {
+                Some(end) => TextEdit {
+                    span: (end, end),
+                    text: format!("\n{statement}"),
+                },
+                None => TextEdit {
+                    span: (0, 0),
+                    text: format!("{statement}\n"),
+                },
+            }
+        })
+        .collect()
+}
+
+// The rest of an `import` statement starting at `start`; None for dynamic
+// imports, `import.meta` and anything malformed.
+fn import_statement(lexer: &mut Lexer, start: usize) -> Option<Import> {
+    let mut bindings = Vec::new();
+    let mut token = lexer.next()?;
+    if token.text == "type" {
+        // `import type { A } from "m"` binds no value
+        while let Some(next) = lexer.next() {
+            if next.text == "from" {
+                return lexer.next().and_then(|module| {
+                    let module = module.string?;
+                    Some(Import {
+                        module: module.to_string(),
+                        bindings,
+                        span: (start, lexer.end_of_statement()),
+                    })
+                });
+            }
+        }
+        return None;
+    }
+    if token.string.is_none() {
+        loop {
+            match token.text {
+                "from" => break,
+                "," => {}
+                "*" => {
+                    // `* as ns`
+                    lexer.next().filter(|t| t.text == "as")?;
+                    bindings.push(lexer.next()?.identifier()?.to_string());
+                }
+                "{" => loop {
+                    let name = lexer.next()?;
+                    match name.text {
+                        "}" => break,
+                        "," => continue,
+                        "type" if lexer.peek_is_identifier() => {
+                            // `{ type A }` binds no value
+                            lexer.next();
+                        }
+                        _ => {
+                            let mut local = name.text;
+                            if lexer.peek_text() == Some("as") {
+                                lexer.next();
+                                local = lexer.next()?.text;
+                            }
+                            bindings.push(local.to_string());
+                        }
+                    }
+                },
+                _ => bindings.push(token.identifier()?.to_string()),
+            }
+            token = lexer.next()?;
+        }
+        token = lexer.next()?;
+    }
+    let module = token.string?;
+    Some(Import {
+        module: module.to_string(),
+        bindings,
+        span: (start, lexer.end_of_statement()),
+    })
+}
+
+// Names declared with `function`, `class`, `const`, `let` or `var`.
+fn declarations(source: &str) -> Vec<String> {
+    let mut lexer = Lexer::new(source);
+    let mut names = Vec::new();
+    while let Some(token) = lexer.next() {
+        if matches!(token.text, "function" | "class" | "const" | "let" | "var") && !token.after_dot
+        {
+            let mut name = lexer.next();
+            // `function* gen`
+            if name.as_ref().is_some_and(|t| t.text == "*") {
+                name = lexer.next();
+            }
+            if let Some(name) = name.as_ref().and_then(Token::identifier) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    // Contents of a string literal
+    string: Option<&'a str>,
+    // Preceded by `.`, i.e. a property and not a keyword
+    after_dot: bool,
+}
+
+impl<'a> Token<'a> {
+    fn identifier(&self) -> Option<&'a str> {
+        let first = self.text.chars().next()?;
+        (self.string.is_none() && (first.is_alphabetic() || first == '_' || first == '$'))
+            .then_some(self.text)
+    }
+}
+
+// Tokens of JavaScript: identifiers, string literals and single punctuation
+// characters, skipping whitespace, comments, template literals and the JSX
+// found by the transform scanner.
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    // JSX spans not yet reached
+    jsx: std::vec::IntoIter<(usize, usize)>,
+    next_jsx: Option<(usize, usize)>,
+    previous_dot: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut jsx = jsx_spans(source).into_iter();
+        let next_jsx = jsx.next();
+        Self {
+            source,
+            pos: 0,
+            jsx,
+            next_jsx,
+            previous_dot: false,
+        }
+    }
+
+    fn peek_text(&self) -> Option<&'a str> {
+        let mut lexer = self.fork();
+        lexer.next().map(|t| t.text)
+    }
+
+    fn peek_is_identifier(&self) -> bool {
+        let mut lexer = self.fork();
+        lexer.next().is_some_and(|t| t.identifier().is_some())
+    }
+
+    fn fork(&self) -> Self {
+        Self {
+            source: self.source,
+            pos: self.pos,
+            jsx: self.jsx.clone(),
+            next_jsx: self.next_jsx,
+            previous_dot: self.previous_dot,
+        }
+    }
+
+    // End of the statement just lexed: past a following `;` on the same
+    // line, if any.
+    fn end_of_statement(&mut self) -> usize {
+        let rest = &self.source[self.pos..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        if line.trim_start().starts_with(';') {
+            self.pos += line.find(';').unwrap_or(0) + 1;
+        }
+        self.pos
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.skip_trivia();
+        let bytes = self.source.as_bytes();
+        let start = self.pos;
+        let first = *bytes.get(start)?;
+        let after_dot = std::mem::replace(&mut self.previous_dot, first == b'.');
+        let token = |text, string| Token {
+            text,
+            start,
+            string,
+            after_dot,
+        };
+
+        if first == b'"' || first == b'\'' {
+            let mut i = start + 1;
+            while i < bytes.len() && bytes[i] != first && bytes[i] != b'\n' {
+                i += if bytes[i] == b'\\' {
+                    1 + char_len(bytes, i + 1)
+                } else {
+                    1
+                };
+            }
+            let end = i.min(bytes.len());
+            self.pos = (end + 1).min(bytes.len());
+            return Some(token(
+                &self.source[start..self.pos],
+                Some(&self.source[start + 1..end]),
+            ));
+        }
+
+        let ch = self.source[start..].chars().next()?;
+        if ch.is_alphanumeric() || ch == '_' || ch == '$' {
+            let len = self.source[start..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(self.source.len() - start);
+            self.pos += len;
+        } else {
+            self.pos += ch.len_utf8();
+        }
+        Some(token(&self.source[start..self.pos], None))
+    }
+
+    fn skip_trivia(&mut self) {
+        let bytes = self.source.as_bytes();
+        loop {
+            while self.next_jsx.is_some_and(|(_, end)| end <= self.pos) {
+                self.next_jsx = self.jsx.next();
+            }
+            if let Some((start, end)) = self.next_jsx {
+                if start == self.pos {
+                    self.pos = end;
+                    self.previous_dot = false;
+                    continue;
+                }
+            }
+            let rest = &bytes[self.pos.min(bytes.len())..];
+            self.pos += match rest {
+                [c, ..] if c.is_ascii_whitespace() => 1,
+                [b'/', b'/', ..] => rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len()),
+                [b'/', b'*', ..] => rest
+                    .windows(2)
+                    .skip(2)
+                    .position(|w| w == b"*/")
+                    .map_or(rest.len(), |at| at + 4),
+                [b'`', ..] => {
+                    let mut i = 1;
+                    while i < rest.len() && rest[i] != b'`' {
+                        i += if rest[i] == b'\\' {
+                            1 + char_len(rest, i + 1)
+                        } else {
+                            1
+                        };
+                    }
+                    (i + 1).min(rest.len())
+                }
+                _ => return,
+            };
+        }
+    }
+}
+
+// Length of the UTF-8 character starting at byte `i`, 1 past the end.
+fn char_len(bytes: &[u8], i: usize) -> usize {
+    match bytes.get(i) {
+        Some(&b) if b >= 0xf0 => 4,
+        Some(&b) if b >= 0xe0 => 3,
+        Some(&b) if b >= 0xc0 => 2,
+        _ => 1,
+    }
+}
+
+// Spans of the JSX nodes of `source`; a `<` that does not start JSX is a
+// JavaScript operator and is lexed.
+fn jsx_spans(source: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        match Parser::new(&source[next..]).parse_next_with_span() {
+            Some(Ok((_, (_, end)))) => {
+                spans.push((next, next + end));
+                i = next + end;
+            }
+            Some(Err(_)) => i = next + 1,
+            None => break,
+        }
+    }
+    spans
+}
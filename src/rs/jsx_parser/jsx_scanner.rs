@@ -11,6 +11,11 @@
 //!
 //! Public API:
 //! - `find_next_jsx_start(src, from)` → Option<usize>
+//! - `find_next_jsx_operand(src, from)` → Option<JsxStart>, for the contents
+//!   of JSX expressions: a '<' following an operand (`count < 3`, `a<b`) is
+//!   a relational or shift operator there, never JSX.
+
+use crate::jsx_parser::lexer::is_identifier_start;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TokenCtx {
@@ -50,6 +55,81 @@ pub fn find_next_jsx_start(src: &str, from: usize) -> Option<usize> {
     Scanner::new(src, from).find_next_jsx_start()
 }
 
+/// A '<' that may begin JSX inside an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JsxStart {
+    pub at: usize,
+    /// Follows `}` or the `)` of an `if`, `while`, `for` or `with` head,
+    /// which may end a statement (`if (a) <p/>`, `{ f() } <p/>`) as well as
+    /// an operand (`{} < b`): JSX only if it parses as such.
+    pub ambiguous: bool,
+}
+
+/// Returns the next '<' that may begin JSX where an operand is expected,
+/// skipping the ones that follow an operand, or `None` if none found
+/// starting at `from`. Meant for expression contents, where a tag can only
+/// be an operand.
+pub fn find_next_jsx_operand(src: &str, from: usize) -> Option<JsxStart> {
+    let mut scanner = Scanner::new(src, from);
+    scanner.operands_only = true;
+    let at = scanner.find_next_jsx_start()?;
+    Some(JsxStart {
+        at,
+        ambiguous: scanner.ctx == TokenCtx::AfterOperand,
+    })
+}
+
+/// What `ExpressionScanner::next` finds in the contents of a JSX expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExpressionEvent {
+    /// A '<' that may begin JSX, as `find_next_jsx_operand` finds them.
+    Jsx(JsxStart),
+    /// The '}' ending the expression, at this offset.
+    End(usize),
+}
+
+/// Scanner of the contents of a JSX expression, following `{`, for the `}`
+/// ending it and the JSX nested in it. Braces, quotes and backticks are only
+/// counted in code: not in strings, comments, regex literals or template
+/// literal text. The caller skips each nested JSX node (see `resume`), as
+/// its text is not JavaScript.
+pub(crate) struct ExpressionScanner<'a> {
+    scanner: Scanner<'a>,
+}
+
+impl<'a> ExpressionScanner<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        let mut scanner = Scanner::new(src, 0);
+        scanner.operands_only = true;
+        scanner.track_braces = true;
+        Self { scanner }
+    }
+
+    /// The next nested JSX start or the end of the expression; None when
+    /// the input ends first.
+    pub(crate) fn next(&mut self) -> Option<ExpressionEvent> {
+        let at = self.scanner.find_next_jsx_start()?;
+        if std::mem::take(&mut self.scanner.closed) {
+            return Some(ExpressionEvent::End(at));
+        }
+        Some(ExpressionEvent::Jsx(JsxStart {
+            at,
+            ambiguous: self.scanner.ctx == TokenCtx::AfterOperand,
+        }))
+    }
+
+    /// Continues from `at`: past a nested JSX node, or past the `<` of one
+    /// that turned out to be a comparison.
+    pub(crate) fn resume(&mut self, at: usize) {
+        let scanner = &mut self.scanner;
+        scanner.i = at.min(scanner.len);
+        scanner.mode = Mode::Normal;
+        scanner.ctx = TokenCtx::BeforeOperand;
+        scanner.after_close = false;
+        scanner.after_head = false;
+    }
+}
+
 struct Scanner<'a> {
     bytes: &'a [u8],
     len: usize,
@@ -60,6 +140,21 @@ struct Scanner<'a> {
 
     // Template literal nesting stack (supports nested templates inside expressions)
     tpl_stack: Vec<TemplateState>,
+
+    // Skip '<' after an operand, except after a closing `}` or the `)` of a
+    // statement head (see `JsxStart`)
+    operands_only: bool,
+    after_close: bool,
+    // Last token was `if`, `while`, `for` or `with`
+    after_head: bool,
+    // Open parentheses, whether each follows such a keyword
+    parens: Vec<bool>,
+
+    // Stop at the `}` closing the code being scanned, setting `closed` (see
+    // `ExpressionScanner`); `braces` counts the ones open in between
+    track_braces: bool,
+    braces: usize,
+    closed: bool,
 }
 
 impl<'a> Scanner<'a> {
@@ -73,6 +168,13 @@ impl<'a> Scanner<'a> {
             mode: Mode::Normal,
             ctx: TokenCtx::BeforeOperand, // at start of file, regex is allowed
             tpl_stack: Vec::new(),
+            operands_only: false,
+            after_close: false,
+            after_head: false,
+            parens: Vec::new(),
+            track_braces: false,
+            braces: 0,
+            closed: false,
         }
     }
 
@@ -134,10 +236,11 @@ impl<'a> Scanner<'a> {
                         } else {
                             None
                         };
-                        if let Some(n) = next {
-                            if n.is_ascii_alphabetic()
-                                || n == b'_'
-                                || n == b'$'
+                        let operand = !self.operands_only
+                            || self.ctx == TokenCtx::BeforeOperand
+                            || self.after_close;
+                        if let Some(n) = next.filter(|_| operand) {
+                            if starts_identifier(&self.bytes[self.i + 1..])
                                 || n == b'/'
                                 || n == b'>'
                                 || n == b'!'
@@ -149,6 +252,18 @@ impl<'a> Scanner<'a> {
                         // Not plausible -> continue scanning as normal (e.g., relational/shift operator)
                     }
 
+                    if self.track_braces {
+                        match self.peek_byte() {
+                            Some(b'{') => self.braces += 1,
+                            Some(b'}') if self.braces == 0 => {
+                                self.closed = true;
+                                return Some(self.i);
+                            }
+                            Some(b'}') => self.braces -= 1,
+                            _ => {}
+                        }
+                    }
+
                     // Otherwise consume normally and update regex context heuristically
                     self.bump_normal_and_update_ctx();
                 }
@@ -185,6 +300,7 @@ impl<'a> Scanner<'a> {
                     }
                     self.mode = Mode::Normal;
                     self.ctx = TokenCtx::AfterOperand;
+                    self.after_close = false;
                 }
 
                 Mode::Regex {
@@ -217,6 +333,7 @@ impl<'a> Scanner<'a> {
                     }
                     self.mode = Mode::Normal;
                     self.ctx = TokenCtx::AfterOperand;
+                    self.after_close = false;
                 }
 
                 Mode::Template => {
@@ -247,6 +364,7 @@ impl<'a> Scanner<'a> {
                                             // Exiting outermost template
                                             self.mode = Mode::Normal;
                                             self.ctx = TokenCtx::AfterOperand;
+                                            self.after_close = false;
                                         }
                                         // No need to write back st if popped
                                         continue;
@@ -450,7 +568,10 @@ impl<'a> Scanner<'a> {
                         break;
                     }
                 }
-                self.ctx = classify_ident_ctx(&self.bytes[start..self.i]);
+                let word = &self.bytes[start..self.i];
+                self.ctx = classify_ident_ctx(word);
+                self.after_close = false;
+                self.after_head = matches!(word, b"if" | b"while" | b"for" | b"with");
                 return;
             }
 
@@ -475,11 +596,22 @@ impl<'a> Scanner<'a> {
                     }
                 }
                 self.ctx = TokenCtx::AfterOperand;
+                self.after_close = false;
                 return;
             }
 
             // Punctuation and operators
             self.i += 1;
+            let head = std::mem::take(&mut self.after_head);
+            self.after_close = match b {
+                b'(' => {
+                    self.parens.push(head);
+                    false
+                }
+                b')' => self.parens.pop().unwrap_or(false),
+                b'}' => true,
+                _ => false,
+            };
             self.ctx = match b {
                 b')' | b']' | b'}' | b'.' => TokenCtx::AfterOperand,
                 _ => TokenCtx::BeforeOperand,
@@ -526,6 +658,23 @@ fn is_whitespace(b: u8) -> bool {
     matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0B | 0x0C)
 }
 
+// Whether `bytes` start with a tag name, by the parser's identifier rules.
+#[inline]
+fn starts_identifier(bytes: &[u8]) -> bool {
+    let width = match bytes.first() {
+        Some(0..=0x7F) => 1,
+        Some(0xE0..=0xEF) => 3,
+        Some(0xF0..) => 4,
+        Some(_) => 2,
+        None => return false,
+    };
+    bytes
+        .get(..width)
+        .and_then(|c| std::str::from_utf8(c).ok())
+        .and_then(|c| c.chars().next())
+        .is_some_and(is_identifier_start)
+}
+
 // Minimal ASCII identifier classes; broaden if you need full unicode identifiers.
 #[inline]
 fn is_ident_start(b: u8) -> bool {
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::jsx_transformer::jsx_scanner::find_next_jsx_start;
+    use crate::jsx_parser::jsx_scanner::{find_next_jsx_operand, find_next_jsx_start, JsxStart};
 
     // Local helper to collect all candidate JSX start positions using the production scanner.
     fn collect_jsx_starts(src: &str) -> Vec<usize> {
@@ -152,4 +152,54 @@ const t = `X ${ `Y ${ /* c */ 1 }` } Z`;
         let at = find_next_jsx_start(src, 0).unwrap();
         assert_eq!(at, src.find("<x").unwrap());
     }
+
+    // (position, ambiguous) of every operand-position candidate.
+    fn collect_operand_starts(src: &str) -> Vec<(usize, bool)> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while let Some(JsxStart { at, ambiguous }) = find_next_jsx_operand(src, pos) {
+            out.push((at, ambiguous));
+            pos = at + 1;
+        }
+        out
+    }
+
+    #[test]
+    fn operand_scan_skips_comparisons() {
+        let src = "count < 3 && a<b && f(x) <y && list[0]<z && \"s\"<t && <Badge/>";
+        assert_eq!(
+            collect_operand_starts(src),
+            vec![(src.find("<Badge").unwrap(), false)]
+        );
+        // The plain scanner still reports them
+        assert_eq!(collect_jsx_starts(src).len(), 5);
+    }
+
+    #[test]
+    fn operand_scan_finds_operand_positions() {
+        let src = "ok ? <A/> : (<B/>) || items.map(i => <li/>) ?? typeof <C/>";
+        let starts: Vec<usize> = collect_operand_starts(src).iter().map(|s| s.0).collect();
+        assert_eq!(
+            starts,
+            vec![
+                src.find("<A").unwrap(),
+                src.find("<B").unwrap(),
+                src.find("<li").unwrap(),
+                src.find("<C").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn operand_scan_marks_statement_ends_ambiguous() {
+        let src = "() => { if (a) <p/>; while (f(b)) <q/>; { x() } <r/> }";
+        assert_eq!(
+            collect_operand_starts(src),
+            vec![
+                (src.find("<p").unwrap(), true),
+                (src.find("<q").unwrap(), true),
+                (src.find("<r").unwrap(), true),
+            ]
+        );
+    }
 }
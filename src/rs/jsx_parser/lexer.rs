@@ -0,0 +1,440 @@
+//! Tokenizer for the JSX of a JavaScript source, following the lexical rules
+//! of the parser, for syntax highlighters and editor grammars that need no
+//! AST.
+//!
+//! ```text
+//! <a href="/">Hi {name}</a>
+//! TagOpen TagName AttrName Equals AttrValue TagEnd Text ExprStart Expression
+//! ExprEnd TagClose TagName TagEnd
+//! ```
+//!
+//! JSX is found as the transform finds it; the JavaScript around it yields no
+//! tokens, and neither does whitespace inside tags.
+
+#[cfg(feature = "unicode-identifiers")]
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::jsx_parser::jsx_scanner::{
+    find_next_jsx_start, ExpressionEvent, ExpressionScanner, JsxStart,
+};
+use crate::jsx_parser::types::ParseError;
+
+const ERR_EXPECT_CLOSE_ANGLE: &str = "Expected >";
+const ERR_EXPECT_CLOSE_SLASH: &str = "Expected > after /";
+const ERR_FRAGMENT_CLOSE: &str = "Expected > for fragment closing tag";
+const ERR_MISMATCHED_TAG: &str = "Mismatched closing tag";
+const ERR_UNCLOSED_TAG: &str = "Unclosed tag";
+const ERR_EXPECT_IDENTIFIER: &str = "Expected identifier";
+const ERR_UNTERMINATED_STRING: &str = "Unterminated string literal";
+const ERR_EXPECT_STRING_OR_EXPR: &str = "Expected string or expression";
+const ERR_UNCLOSED_EXPRESSION: &str = "Unclosed expression";
+const ERR_UNMATCHED_CLOSE: &str = "Unmatched closing tag";
+
+/// Kind of a `JsxToken`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsxTokenKind {
+    /// `<` starting an opening tag.
+    TagOpen,
+    /// `</` starting a closing tag.
+    TagClose,
+    /// Tag name, e.g. `div`, `Card` or `my-icon`.
+    TagName,
+    /// `>` ending a tag.
+    TagEnd,
+    /// `/>` ending a self-closing tag.
+    SelfClose,
+    /// `<>` opening a fragment.
+    FragmentOpen,
+    /// `</>` closing a fragment.
+    FragmentClose,
+    AttrName,
+    /// `=` between an attribute name and its value.
+    Equals,
+    /// Quoted attribute value, quotes included.
+    AttrValue,
+    /// Spread attribute, e.g. `...props`.
+    Spread,
+    /// Text child, including the raw content of `<script>`.
+    Text,
+    /// `{` opening an expression child or attribute value.
+    ExprStart,
+    /// JavaScript code of an expression, around the JSX nested in it.
+    Expression,
+    /// `}` closing an expression.
+    ExprEnd,
+    /// A character the parser skips, e.g. the braces of `{...props}`.
+    Unknown,
+}
+
+/// A token with its byte span (start, end) in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsxToken {
+    pub kind: JsxTokenKind,
+    pub span: (usize, usize),
+}
+
+/// Tokens of a source, in order, with the errors that stopped the lexing of
+/// a JSX node. After an error, lexing resumes one character past it.
+#[derive(Debug, Default, PartialEq)]
+pub struct LexResult {
+    pub tokens: Vec<JsxToken>,
+    pub errors: Vec<ParseError>,
+}
+
+/// Tokenizes the JSX nodes of a JavaScript source.
+pub fn tokenize(source: &str) -> LexResult {
+    let mut result = LexResult::default();
+    let mut i = 0;
+    while let Some(start) = find_next_jsx_start(source, i) {
+        let mut lexer = Lexer::new(source, start, &mut result.tokens);
+        match lexer.node() {
+            Ok(()) => i = lexer.pos,
+            Err(message) => {
+                let position = lexer.pos;
+                result.errors.push(ParseError::new(position, message));
+                i = position + source[position..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    result
+}
+
+/// First character of tag and attribute names. ASCII letters, unless the
+/// `unicode-identifiers` feature is enabled.
+#[inline]
+pub(crate) fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$' || (!c.is_ascii() && is_xid_start(c))
+}
+
+/// Following characters of tag and attribute names: `-` and `.` allow
+/// `my-icon`, `aria-label` and `Foo.Bar`.
+#[inline]
+pub(crate) fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric()
+        || matches!(c, '_' | '$' | '-' | '.')
+        || (!c.is_ascii() && is_xid_continue(c))
+}
+
+// Without the feature, no non-ASCII character is part of a name
+#[cfg(not(feature = "unicode-identifiers"))]
+#[inline]
+fn is_xid_start(_: char) -> bool {
+    false
+}
+
+#[cfg(not(feature = "unicode-identifiers"))]
+#[inline]
+fn is_xid_continue(_: char) -> bool {
+    false
+}
+
+struct Lexer<'a, 't> {
+    src: &'a str,
+    pos: usize,
+    tokens: &'t mut Vec<JsxToken>,
+}
+
+impl<'a, 't> Lexer<'a, 't> {
+    fn new(src: &'a str, pos: usize, tokens: &'t mut Vec<JsxToken>) -> Self {
+        Self { src, pos, tokens }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    #[inline]
+    fn peek_n(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    #[inline]
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn push(&mut self, kind: JsxTokenKind, start: usize) {
+        self.tokens.push(JsxToken {
+            kind,
+            span: (start, self.pos),
+        });
+    }
+
+    // Consumes `n` characters as one token.
+    fn token(&mut self, kind: JsxTokenKind, n: usize) {
+        let start = self.pos;
+        for _ in 0..n {
+            self.bump();
+        }
+        self.push(kind, start);
+    }
+
+    // An element or fragment starting at `<`.
+    fn node(&mut self) -> Result<(), &'static str> {
+        match self.peek_n(1) {
+            Some('>') => {
+                self.token(JsxTokenKind::FragmentOpen, 2);
+                self.children(None)
+            }
+            Some(c) if is_identifier_start(c) => self.element(),
+            Some('/') => Err(ERR_UNMATCHED_CLOSE),
+            _ => Err(ERR_EXPECT_IDENTIFIER),
+        }
+    }
+
+    fn element(&mut self) -> Result<(), &'static str> {
+        self.token(JsxTokenKind::TagOpen, 1);
+        self.skip_whitespace();
+        let tag = self.identifier(JsxTokenKind::TagName)?;
+        self.skip_whitespace();
+        self.attributes()?;
+        self.skip_whitespace();
+
+        if self.peek() == Some('/') {
+            if self.peek_n(1) != Some('>') {
+                self.bump();
+                return Err(ERR_EXPECT_CLOSE_SLASH);
+            }
+            self.token(JsxTokenKind::SelfClose, 2);
+            return Ok(());
+        }
+        if self.peek() != Some('>') {
+            return Err(ERR_EXPECT_CLOSE_ANGLE);
+        }
+        self.token(JsxTokenKind::TagEnd, 1);
+        self.children(Some(tag))
+    }
+
+    fn identifier(&mut self, kind: JsxTokenKind) -> Result<&'a str, &'static str> {
+        let start = self.pos;
+        if !self.peek().is_some_and(is_identifier_start) {
+            return Err(ERR_EXPECT_IDENTIFIER);
+        }
+        self.bump();
+        while self.peek().is_some_and(is_identifier_char) {
+            self.bump();
+        }
+        self.push(kind, start);
+        Ok(&self.src[start..self.pos])
+    }
+
+    fn attributes(&mut self) -> Result<(), &'static str> {
+        while let Some(c) = self.peek() {
+            if c == '>' || c == '/' {
+                break;
+            }
+            self.skip_whitespace();
+
+            if self.src[self.pos..].starts_with("...") {
+                let start = self.pos;
+                self.pos += 3;
+                if self.peek().is_some_and(is_identifier_start) {
+                    while self.peek().is_some_and(is_identifier_char) {
+                        self.bump();
+                    }
+                } else {
+                    self.expression_content(false)?;
+                }
+                self.push(JsxTokenKind::Spread, start);
+                self.skip_whitespace();
+                continue;
+            }
+
+            if self.identifier(JsxTokenKind::AttrName).is_err() && self.peek().is_some() {
+                self.token(JsxTokenKind::Unknown, 1);
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('=') {
+                self.token(JsxTokenKind::Equals, 1);
+                self.skip_whitespace();
+                self.attribute_value()?;
+            }
+            self.skip_whitespace();
+        }
+        Ok(())
+    }
+
+    fn attribute_value(&mut self) -> Result<(), &'static str> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let start = self.pos;
+                self.bump();
+                match self.src[self.pos..].find(quote) {
+                    Some(end) => self.pos += end + 1,
+                    None => {
+                        self.pos = self.src.len();
+                        return Err(ERR_UNTERMINATED_STRING);
+                    }
+                }
+                self.push(JsxTokenKind::AttrValue, start);
+                Ok(())
+            }
+            Some('{') => self.expression(),
+            _ => Err(ERR_EXPECT_STRING_OR_EXPR),
+        }
+    }
+
+    // Children up to and including the closing tag; `None` for a fragment.
+    fn children(&mut self, parent: Option<&str>) -> Result<(), &'static str> {
+        if parent == Some("script") {
+            return self.script_text();
+        }
+        loop {
+            match self.peek() {
+                Some('<') if self.peek_n(1) == Some('/') => {
+                    let start = self.pos;
+                    self.pos += 2;
+                    self.skip_whitespace();
+                    let Some(parent) = parent else {
+                        if self.peek() != Some('>') {
+                            return Err(ERR_FRAGMENT_CLOSE);
+                        }
+                        self.bump();
+                        self.push(JsxTokenKind::FragmentClose, start);
+                        return Ok(());
+                    };
+                    self.tokens.push(JsxToken {
+                        kind: JsxTokenKind::TagClose,
+                        span: (start, start + 2),
+                    });
+                    let name_start = self.pos;
+                    if self.identifier(JsxTokenKind::TagName)? != parent {
+                        self.pos = name_start;
+                        return Err(ERR_MISMATCHED_TAG);
+                    }
+                    self.skip_whitespace();
+                    if self.peek() != Some('>') {
+                        return Err(ERR_EXPECT_CLOSE_ANGLE);
+                    }
+                    self.token(JsxTokenKind::TagEnd, 1);
+                    return Ok(());
+                }
+                Some('<') if self.peek_n(1) == Some('>') => {
+                    self.token(JsxTokenKind::FragmentOpen, 2);
+                    self.children(None)?;
+                }
+                Some('<') => self.element()?,
+                Some('{') => self.expression()?,
+                Some(_) => {
+                    let start = self.pos;
+                    let end = self.src[start..]
+                        .find(['<', '{'])
+                        .map_or(self.src.len(), |end| start + end);
+                    self.pos = end;
+                    self.push(JsxTokenKind::Text, start);
+                }
+                None => return Err(ERR_UNCLOSED_TAG),
+            }
+        }
+    }
+
+    // Raw text up to `</script>` (whitespace allowed around the name).
+    fn script_text(&mut self) -> Result<(), &'static str> {
+        let start = self.pos;
+        while self.pos < self.src.len() {
+            if self.src[self.pos..].starts_with("</") {
+                let text_end = self.pos;
+                let rest = self.src[self.pos + 2..].trim_start();
+                if let Some(after) = rest.strip_prefix("script") {
+                    let after_name = after.trim_start();
+                    if !after.starts_with(is_identifier_char) && after_name.starts_with('>') {
+                        if text_end > start {
+                            self.tokens.push(JsxToken {
+                                kind: JsxTokenKind::Text,
+                                span: (start, text_end),
+                            });
+                        }
+                        self.token(JsxTokenKind::TagClose, 2);
+                        self.skip_whitespace();
+                        self.token(JsxTokenKind::TagName, "script".len());
+                        self.skip_whitespace();
+                        self.token(JsxTokenKind::TagEnd, 1);
+                        return Ok(());
+                    }
+                }
+            }
+            self.bump();
+        }
+        self.push(JsxTokenKind::Text, start);
+        Err(ERR_UNCLOSED_TAG)
+    }
+
+    // `{`, the expression with the JSX nested in it, and `}`.
+    fn expression(&mut self) -> Result<(), &'static str> {
+        self.token(JsxTokenKind::ExprStart, 1);
+        let content_end = self.expression_content(true)? - 1;
+        self.tokens.push(JsxToken {
+            kind: JsxTokenKind::ExprEnd,
+            span: (content_end, content_end + 1),
+        });
+        Ok(())
+    }
+
+    // Skips to past the `}` matching an already consumed `{`; returns the
+    // position after it. The JSX nested in the expression is lexed to find
+    // where it ends, as the parser reads it, and its tokens and those of the
+    // code around it kept when `record` is set.
+    fn expression_content(&mut self, record: bool) -> Result<usize, &'static str> {
+        let content_start = self.pos;
+        let lexed = self.tokens.len();
+        let mut scanner = ExpressionScanner::new(&self.src[content_start..]);
+        let mut cursor = content_start;
+
+        while let Some(event) = scanner.next() {
+            let (next, ambiguous) = match event {
+                ExpressionEvent::End(end) => {
+                    let content_end = content_start + end;
+                    if content_end > cursor {
+                        self.tokens.push(JsxToken {
+                            kind: JsxTokenKind::Expression,
+                            span: (cursor, content_end),
+                        });
+                    }
+                    if !record {
+                        self.tokens.truncate(lexed);
+                    }
+                    self.pos = content_end + 1;
+                    return Ok(self.pos);
+                }
+                ExpressionEvent::Jsx(JsxStart { at, ambiguous }) => (content_start + at, ambiguous),
+            };
+            let before = self.tokens.len();
+            let mut nested = Lexer::new(self.src, next, &mut *self.tokens);
+            if next > cursor {
+                nested.tokens.push(JsxToken {
+                    kind: JsxTokenKind::Expression,
+                    span: (cursor, next),
+                });
+            }
+            match nested.node() {
+                Ok(()) => {
+                    cursor = nested.pos;
+                    scanner.resume(cursor - content_start);
+                }
+                // A comparison, as the parser reads it
+                Err(_) if ambiguous => {
+                    self.tokens.truncate(before);
+                    scanner.resume(next + 1 - content_start);
+                }
+                Err(e) => {
+                    self.pos = nested.pos;
+                    return Err(e);
+                }
+            }
+        }
+        if !record {
+            self.tokens.truncate(lexed);
+        }
+        self.pos = self.src.len();
+        Err(ERR_UNCLOSED_EXPRESSION)
+    }
+}
@@ -1,9 +1,29 @@
-pub mod types;
+pub mod components;
+pub mod imports;
+pub(crate) mod jsx_scanner;
+#[cfg(test)]
+mod jsx_scanner_tests;
+pub mod lexer;
 pub mod parser;
+pub mod position;
+pub mod printer;
+pub mod stats;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
+pub mod types;
 pub mod visitor;
 
+pub use components::{components, ComponentRef};
+pub use imports::{
+    auto_import_edits, missing_imports, scan_imports, Import, MissingImport, TextEdit,
+};
+pub use lexer::{tokenize, JsxToken, JsxTokenKind, LexResult};
+pub use parser::{recovery_offset, Parser};
+pub use position::{byte_offset, position, utf16_offset, LineIndex, Position, Span};
+pub use printer::reprint;
+pub use stats::{analyze, JsxStats};
 pub use types::{
-    JSXNode, JSXAttribute, JSXAttributeValue, ParseError, ParseResult, ParseResultWithSpan,
+    ElementSpans, EqIgnoreWhitespace, ExpressionPart, JSXAttribute, JSXAttributeValue, JSXNode,
+    ParseError, ParseResult, ParseResultWithSpan, SpannedNode,
 };
-pub use parser::Parser;
-pub use visitor::{JSXVisitor, walk_node, walk_nodes};
+pub use visitor::{walk_node, walk_nodes, JSXVisitor};
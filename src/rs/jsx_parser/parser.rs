@@ -1,22 +1,22 @@
 use std::iter::Peekable;
 use std::str::Chars;
 
+use crate::jsx_parser::jsx_scanner::{ExpressionEvent, ExpressionScanner, JsxStart};
+use crate::jsx_parser::lexer::{is_identifier_char, is_identifier_start};
 use crate::jsx_parser::types::{
-    JSXAttribute, JSXAttributeValue, JSXNode, ParseError, ParseResult, ParseResultWithSpan,
+    ElementSpans, ExpressionPart, JSXAttribute, JSXAttributeValue, JSXNode, ParseError,
+    ParseResult, ParseResultWithSpan,
 };
+use crate::jsx_parser::visitor::{walk_node, JSXVisitor};
 
 // Token characters
 const LEFT_ANGLE: char = '<';
 const RIGHT_ANGLE: char = '>';
 const FORWARD_SLASH: char = '/';
 const LEFT_BRACE: char = '{';
-const RIGHT_BRACE: char = '}';
 const EQUALS: char = '=';
 const DOUBLE_QUOTE: char = '"';
 const SINGLE_QUOTE: char = '\'';
-const UNDERSCORE: char = '_';
-const DOLLAR_SIGN: char = '$';
-const HYPHEN: char = '-';
 const DOT: char = '.';
 
 // Error messages
@@ -29,17 +29,57 @@ const ERR_EXPECT_IDENTIFIER: &str = "Expected identifier";
 const ERR_UNTERMINATED_STRING: &str = "Unterminated string literal";
 const ERR_EXPECT_STRING_OR_EXPR: &str = "Expected string or expression";
 const ERR_UNCLOSED_EXPRESSION: &str = "Unclosed expression";
+const ERR_TOO_DEEP: &str = "Nesting too deep";
+const ERR_UNMATCHED_CLOSE: &str = "Unmatched closing tag: {}";
+
+// Deepest nesting of nodes accepted, embedded expressions included, so that
+// the recursive parser and the walkers of its trees cannot overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+// Children of an element and the span of its closing tag
+type Children = (Vec<JSXNode>, Option<(usize, usize)>);
 
 pub struct Parser<'a> {
+    input: &'a str,
     chars: Peekable<Chars<'a>>,
+    // Byte offset of `input` in the source, which positions count from
+    base: usize,
     pos: usize,
+    // Exact position of an error raised inside an embedded expression; takes
+    // precedence over the start of the enclosing node when reporting.
+    error_at: Option<usize>,
+    // Start of every parsed element, in document order
+    element_starts: Vec<usize>,
+    // Span of every parsed node, in document order
+    node_spans: Vec<(usize, usize)>,
+    // Tag and children spans of every parsed element, in document order
+    element_spans: Vec<ElementSpans>,
+    // Nodes being parsed around the current one, those of enclosing parsers
+    // included
+    depth: usize,
+    // Visitor driven as nodes are parsed, in the order `walk_node` calls it,
+    // instead of children being collected (see `parse_next_into`)
+    sink: Option<&'a mut dyn JSXVisitor>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::new_at(input, 0)
+    }
+
+    // Parser whose positions start at `pos`, used for input slices nested in a larger source.
+    fn new_at(input: &'a str, pos: usize) -> Self {
         Self {
+            input,
             chars: input.chars().peekable(),
-            pos: 0,
+            base: pos,
+            pos,
+            error_at: None,
+            element_starts: Vec::new(),
+            node_spans: Vec::new(),
+            element_spans: Vec::new(),
+            depth: 0,
+            sink: None,
         }
     }
 
@@ -65,7 +105,52 @@ impl<'a> Parser<'a> {
         ParseResult { nodes, errors }
     }
 
+    /// Byte offsets of the `<` of every element parsed so far, in document
+    /// order (the order a visitor enters them), including elements nested in
+    /// embedded expressions.
+    pub fn element_starts(&self) -> &[usize] {
+        &self.element_starts
+    }
+
+    /// Byte spans (start, end) of every node parsed so far, in document order
+    /// (the order a visitor enters them), including nodes nested in embedded
+    /// expressions.
+    pub fn node_spans(&self) -> &[(usize, usize)] {
+        &self.node_spans
+    }
+
+    /// Spans of the opening tag, closing tag and children of every element
+    /// parsed so far, in the order of `element_starts`.
+    pub fn element_spans(&self) -> &[ElementSpans] {
+        &self.element_spans
+    }
+
+    // Parses a node with `parse`, recording its span before those of its
+    // descendants.
+    fn spanned(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JSXNode, String>,
+    ) -> Result<JSXNode, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(ERR_TOO_DEEP.to_string());
+        }
+        let index = self.node_spans.len();
+        self.node_spans.push((self.pos, self.pos));
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        let node = node?;
+        self.node_spans[index].1 = self.pos;
+        Ok(node)
+    }
+
     fn parse_element(&mut self) -> Result<JSXNode, String> {
+        let start = self.pos;
+        self.element_starts.push(start);
+        // Filled in once the element is parsed, before the spans of its
+        // descendants
+        let index = self.element_spans.len();
+        self.element_spans.push(ElementSpans::default());
         // Consume <
         self.bump();
         self.skip_whitespace();
@@ -83,6 +168,15 @@ impl<'a> Parser<'a> {
             self.bump();
             if self.peek() == Some(RIGHT_ANGLE) {
                 self.bump();
+                self.element_spans[index] = ElementSpans {
+                    open: (start, self.pos),
+                    close: None,
+                    children: (self.pos, self.pos),
+                };
+                if let Some(sink) = self.sink.as_deref_mut() {
+                    sink.enter_element(&tag, &attributes);
+                    sink.exit_element(&tag);
+                }
                 return Ok(JSXNode::Element {
                     tag,
                     attributes,
@@ -97,9 +191,21 @@ impl<'a> Parser<'a> {
             return Err(ERR_EXPECT_CLOSE_ANGLE.to_string());
         }
         self.bump();
+        let open = (start, self.pos);
+        if let Some(sink) = self.sink.as_deref_mut() {
+            sink.enter_element(&tag, &attributes);
+        }
 
         // Parse children
-        let children = self.parse_children(&tag)?;
+        let (children, close) = self.parse_children(&tag)?;
+        if let Some(sink) = self.sink.as_deref_mut() {
+            sink.exit_element(&tag);
+        }
+        self.element_spans[index] = ElementSpans {
+            open,
+            close,
+            children: (open.1, close.map_or(self.pos, |(start, _)| start)),
+        };
 
         Ok(JSXNode::Element {
             tag,
@@ -112,18 +218,28 @@ impl<'a> Parser<'a> {
         // Consume <>
         self.bump();
         self.bump();
+        if let Some(sink) = self.sink.as_deref_mut() {
+            sink.enter_fragment();
+        }
 
-        let children = self.parse_children("fragment")?;
+        let (children, _) = self.parse_children("fragment")?;
+        if let Some(sink) = self.sink.as_deref_mut() {
+            sink.exit_fragment();
+        }
 
         Ok(JSXNode::Fragment { children })
     }
 
-    fn parse_children(&mut self, parent_tag: &str) -> Result<Vec<JSXNode>, String> {
+    // Children up to the closing tag of `parent_tag`, and the span of the
+    // closing tag (None for a `<script>` left open).
+    fn parse_children(&mut self, parent_tag: &str) -> Result<Children, String> {
         let mut children = vec![];
 
         // Special handling for script tag - treat everything as text until closing tag
         if parent_tag == "script" {
             let mut content = String::new();
+            let start = self.pos;
+            let mut end = None;
 
             while let Some(c) = self.peek() {
                 if c == LEFT_ANGLE && self.peek_n(1) == Some(FORWARD_SLASH) {
@@ -139,6 +255,7 @@ impl<'a> Parser<'a> {
                             self.skip_whitespace();
                             if self.peek() == Some(RIGHT_ANGLE) {
                                 self.bump(); // >
+                                end = Some(peek_pos);
                                 break;
                             }
                         }
@@ -155,16 +272,19 @@ impl<'a> Parser<'a> {
                 }
             }
 
-            children.push(JSXNode::Text(content));
-            return Ok(children);
+            self.node_spans.push((start, end.unwrap_or(self.pos)));
+            self.push_child(&mut children, JSXNode::Text(content));
+            return Ok((children, end.map(|end| (end, self.pos))));
         }
 
+        let close_start;
         loop {
             match self.peek() {
                 Some(LEFT_ANGLE) => {
                     self.skip_whitespace();
                     if self.peek_n(1) == Some(FORWARD_SLASH) {
                         // End tag
+                        close_start = self.pos;
                         self.bump(); // <
                         self.bump(); // /
                         self.skip_whitespace();
@@ -195,17 +315,21 @@ impl<'a> Parser<'a> {
                         }
                     } else if self.peek_n(1) == Some(RIGHT_ANGLE) {
                         // Fragment
-                        children.push(self.parse_fragment()?);
+                        let child = self.spanned(Self::parse_fragment)?;
+                        self.push_child(&mut children, child);
                     } else {
                         // Element
-                        children.push(self.parse_element()?);
+                        let child = self.spanned(Self::parse_element)?;
+                        self.push_child(&mut children, child);
                     }
                 }
                 Some(LEFT_BRACE) => {
-                    children.push(self.parse_expression()?);
+                    let child = self.spanned(Self::parse_expression)?;
+                    self.push_child(&mut children, child);
                 }
                 Some(_) => {
-                    children.push(self.parse_text()?);
+                    let child = self.spanned(Self::parse_text)?;
+                    self.push_child(&mut children, child);
                 }
                 None => {
                     return Err(ERR_UNCLOSED_TAG.to_string().replace("{}", parent_tag));
@@ -213,7 +337,19 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(children)
+        Ok((children, Some((close_start, self.pos))))
+    }
+
+    // Adds a parsed child to `children`, or hands it to the sink: elements
+    // and fragments have been visited while parsed, while texts and
+    // expressions, JSX nested in them included, are walked whole. An `<`
+    // in an expression is only known to start JSX once it parses.
+    fn push_child(&mut self, children: &mut Vec<JSXNode>, child: JSXNode) {
+        match self.sink.as_deref_mut() {
+            Some(_) if matches!(child, JSXNode::Element { .. } | JSXNode::Fragment { .. }) => {}
+            Some(sink) => walk_node(sink, &child),
+            None => children.push(child),
+        }
     }
 
     fn parse_attributes(&mut self) -> Result<Vec<JSXAttribute>, String> {
@@ -234,13 +370,13 @@ impl<'a> Parser<'a> {
                 self.bump();
 
                 let name = match self.peek() {
-                    Some(c) if c.is_ascii_alphabetic() || c == UNDERSCORE || c == DOLLAR_SIGN => {
+                    Some(c) if is_identifier_start(c) => {
                         let ident = self.parse_identifier()?;
                         format!("...{ident}")
                     }
                     _ => {
                         // Accept any JS expression until the matching '}' for spread attributes like {...(expr)}
-                        let expr = self.parse_expression_content()?;
+                        let (expr, _) = self.parse_expression_content(false)?;
                         format!("...{expr}")
                     }
                 };
@@ -331,7 +467,7 @@ impl<'a> Parser<'a> {
             }
             Some(LEFT_BRACE) => {
                 self.bump();
-                let expr = self.parse_expression_content()?;
+                let (expr, _) = self.parse_expression_content(false)?;
                 Ok(JSXAttributeValue::Expression(expr))
             }
             _ => Err(ERR_EXPECT_STRING_OR_EXPR.to_string()),
@@ -341,38 +477,83 @@ impl<'a> Parser<'a> {
     fn parse_expression(&mut self) -> Result<JSXNode, String> {
         // Consume {
         self.bump();
-        let expr = self.parse_expression_content()?;
-        Ok(JSXNode::Expression(expr))
+        let (expr, parts) = self.parse_expression_content(true)?;
+        if parts.is_empty() {
+            Ok(JSXNode::Expression(expr))
+        } else {
+            Ok(JSXNode::EmbeddedExpression { raw: expr, parts })
+        }
     }
 
-    fn parse_expression_content(&mut self) -> Result<String, String> {
-        let mut content = String::new();
-        let mut brace_count = 1;
-
-        while let Some(c) = self.peek() {
-            match c {
-                LEFT_BRACE => {
-                    brace_count += 1;
-                    content.push(c);
+    // Reads the contents of an expression up to the `}` ending it, with the
+    // JavaScript chunks and parsed JSX subtrees they split into; no parts when
+    // there is no JSX. Candidate starts come from the comment/string-aware
+    // scanner, so `<` inside literals is never parsed, nor a `<` comparing an
+    // operand (`count < 3 && <Badge/>`). An ambiguous one, after `)` or `}`,
+    // is a comparison when it does not parse. The subtrees are skipped while
+    // looking for the end, as quotes, backticks and braces in their text are
+    // not JavaScript. Their spans are recorded when `record` is set, for
+    // children.
+    fn parse_expression_content(
+        &mut self,
+        record: bool,
+    ) -> Result<(String, Vec<ExpressionPart>), String> {
+        let start = self.pos;
+        let rest = &self.input[start - self.base..];
+        let mut scanner = ExpressionScanner::new(rest);
+        let mut parts = Vec::new();
+        let mut cursor = 0;
+
+        while let Some(event) = scanner.next() {
+            let (at, ambiguous) = match event {
+                ExpressionEvent::End(end) => {
+                    if !parts.is_empty() && end > cursor {
+                        parts.push(ExpressionPart::Code(rest[cursor..end].to_string()));
+                    }
+                    self.advance_to(start + end + 1);
+                    return Ok((rest[..end].to_string(), parts));
                 }
-                RIGHT_BRACE => {
-                    brace_count -= 1;
-                    if brace_count == 0 {
-                        self.bump();
-                        return Ok(content);
+                ExpressionEvent::Jsx(JsxStart { at, ambiguous }) => (at, ambiguous),
+            };
+            let mut nested = Parser::new_at(&rest[at..], start + at);
+            nested.depth = self.depth;
+            match nested.parse_next_with_span() {
+                Some(Ok((node, (node_start, node_end)))) => {
+                    if record {
+                        self.element_starts.append(&mut nested.element_starts);
+                        self.node_spans.append(&mut nested.node_spans);
+                        self.element_spans.append(&mut nested.element_spans);
                     }
-                    content.push(c);
+                    let start_rel = node_start - start;
+                    if start_rel > cursor {
+                        parts.push(ExpressionPart::Code(rest[cursor..start_rel].to_string()));
+                    }
+                    parts.push(ExpressionPart::Jsx {
+                        node,
+                        span: (node_start, node_end),
+                    });
+                    cursor = node_end - start;
+                    scanner.resume(cursor);
                 }
-                _ => {
-                    content.push(c);
+                Some(Err(e)) if !ambiguous => {
+                    self.error_at = Some(e.position);
+                    return Err(e.message);
                 }
+                _ => scanner.resume(at + 1),
             }
-            self.bump();
         }
 
+        self.advance_to(start + rest.len());
         Err(ERR_UNCLOSED_EXPRESSION.to_string())
     }
 
+    // Moves to byte `pos` of the source.
+    fn advance_to(&mut self, pos: usize) {
+        while self.pos < pos && self.peek().is_some() {
+            self.bump();
+        }
+    }
+
     fn parse_text(&mut self) -> Result<JSXNode, String> {
         let mut content = String::new();
 
@@ -391,7 +572,7 @@ impl<'a> Parser<'a> {
         let mut ident = String::new();
 
         if let Some(c) = self.peek() {
-            if !c.is_ascii_alphabetic() && c != UNDERSCORE && c != DOLLAR_SIGN {
+            if !is_identifier_start(c) {
                 return Err(ERR_EXPECT_IDENTIFIER.to_string());
             }
             ident.push(c);
@@ -399,8 +580,7 @@ impl<'a> Parser<'a> {
         }
 
         while let Some(c) = self.peek() {
-            if c.is_alphanumeric() || c == UNDERSCORE || c == DOLLAR_SIGN || c == HYPHEN || c == DOT
-            {
+            if is_identifier_char(c) {
                 ident.push(c);
                 self.bump();
             } else {
@@ -462,21 +642,20 @@ impl<'a> Parser<'a> {
                     // Fragment start: <>
                     if self.peek_n(1) == Some(RIGHT_ANGLE) {
                         let start = self.pos;
-                        match self.parse_fragment() {
+                        match self.spanned(Self::parse_fragment) {
                             Ok(node) => return Some(Ok(node)),
-                            Err(e) => return Some(Err(ParseError::new(start, e))),
+                            Err(e) => return Some(Err(self.make_error(start, e))),
                         }
                     // Element start: <[A-Za-z_$]
                     } else if self.is_valid_jsx_start_peek() {
                         let start = self.pos;
-                        match self.parse_element() {
+                        match self.spanned(Self::parse_element) {
                             Ok(node) => return Some(Ok(node)),
-                            Err(e) => return Some(Err(ParseError::new(start, e))),
+                            Err(e) => return Some(Err(self.make_error(start, e))),
                         }
                     } else {
                         // Invalid JSX start after '<'
-                        let pos = self.pos;
-                        return Some(Err(ParseError::new(pos, ERR_EXPECT_IDENTIFIER.to_string())));
+                        return Some(Err(self.invalid_start()));
                     }
                 }
                 _ => {
@@ -495,26 +674,25 @@ impl<'a> Parser<'a> {
                 Some(LEFT_ANGLE) => {
                     if self.peek_n(1) == Some(RIGHT_ANGLE) {
                         let start = self.pos;
-                        match self.parse_fragment() {
+                        match self.spanned(Self::parse_fragment) {
                             Ok(node) => {
                                 let end = self.pos;
                                 return Some(Ok((node, (start, end))));
                             }
-                            Err(e) => return Some(Err(ParseError::new(start, e))),
+                            Err(e) => return Some(Err(self.make_error(start, e))),
                         }
                     } else if self.is_valid_jsx_start_peek() {
                         let start = self.pos;
-                        match self.parse_element() {
+                        match self.spanned(Self::parse_element) {
                             Ok(node) => {
                                 let end = self.pos;
                                 return Some(Ok((node, (start, end))));
                             }
-                            Err(e) => return Some(Err(ParseError::new(start, e))),
+                            Err(e) => return Some(Err(self.make_error(start, e))),
                         }
                     } else {
                         // Invalid JSX start after '<'
-                        let pos = self.pos;
-                        return Some(Err(ParseError::new(pos, ERR_EXPECT_IDENTIFIER.to_string())));
+                        return Some(Err(self.invalid_start()));
                     }
                 }
                 _ => {
@@ -524,8 +702,104 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Like parse_next_with_span, driving `sink` as the next node is parsed
+    /// instead of building it: the visitor sees what `walk_node` would show
+    /// it for the node, in the same order, while only texts and expressions
+    /// are materialized. Returns the byte span of the node; on error the
+    /// sink has seen part of the node and should be discarded.
+    pub fn parse_next_into(
+        &mut self,
+        sink: &'a mut dyn JSXVisitor,
+    ) -> Option<Result<(usize, usize), ParseError>> {
+        self.sink = Some(sink);
+        let result = self.parse_next_with_span();
+        self.sink = None;
+        result.map(|result| result.map(|(_, span)| span))
+    }
+
+    // Builds a ParseError at the recorded nested position, or at `start` otherwise.
+    #[inline]
+    fn make_error(&mut self, start: usize, message: String) -> ParseError {
+        ParseError::new(self.error_at.take().unwrap_or(start), message)
+    }
+
     #[inline]
+    // Error for a `<` at the cursor starting neither an element nor a fragment;
+    // a stray closing tag, `</div>` in a partially edited file, is reported as
+    // such (and skipped by `recovery_offset`).
+    fn invalid_start(&mut self) -> ParseError {
+        let mut chars = self.chars.clone().skip(1);
+        let message = if chars.next() == Some(FORWARD_SLASH) {
+            let name: String = chars
+                .skip_while(|c| c.is_whitespace())
+                .take_while(|&c| c != RIGHT_ANGLE && !c.is_whitespace())
+                .collect();
+            ERR_UNMATCHED_CLOSE.replace("{}", &format!("</{name}>"))
+        } else {
+            ERR_EXPECT_IDENTIFIER.to_string()
+        };
+        ParseError::new(self.pos, message)
+    }
+
     fn is_valid_jsx_start_peek(&mut self) -> bool {
-        matches!(self.peek_n(1), Some(ch) if ch.is_ascii_alphabetic() || ch == UNDERSCORE || ch == DOLLAR_SIGN)
+        self.peek_n(1).is_some_and(is_identifier_start)
+    }
+}
+
+/// Where scanning resumes after the JSX at byte `start` of `source` failed to
+/// parse, so that one mistake yields one error: past the closing tag matching
+/// it, as the tags nested in it would fail again, or when it has none, at the
+/// next opening `<`, skipping its stray closing tags.
+pub fn recovery_offset(source: &str, start: usize) -> usize {
+    find_matching_close(source, start).unwrap_or_else(|| {
+        let from = (start + 1).min(source.len());
+        source[from..]
+            .match_indices(LEFT_ANGLE)
+            .map(|(at, _)| from + at)
+            .find(|&at| !source[at + 1..].starts_with(FORWARD_SLASH))
+            .unwrap_or(source.len())
+    })
+}
+
+// Offset just past the closing tag matching the element or fragment opened
+// at `start`, found by counting the tags of the same name without parsing.
+fn find_matching_close(source: &str, start: usize) -> Option<usize> {
+    let rest = source.get(start..)?.strip_prefix(LEFT_ANGLE)?;
+    let name = &rest[..rest.find(|c| !is_identifier_char(c)).unwrap_or(rest.len())];
+    // `<div` but not `<divider`; `<>` for fragments
+    let ends_name = |after: &str| match name {
+        "" => after.starts_with(RIGHT_ANGLE),
+        _ => !after.starts_with(is_identifier_char),
+    };
+    if !ends_name(&rest[name.len()..]) {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut i = start;
+    while let Some(at) = source[i..].find(LEFT_ANGLE) {
+        let lt = i + at;
+        i = lt + 1;
+        let closing = source[i..].starts_with(FORWARD_SLASH);
+        let name_start = i + usize::from(closing);
+        let Some(after) = source[name_start..]
+            .strip_prefix(name)
+            .filter(|after| ends_name(after))
+        else {
+            continue;
+        };
+        let gt = name_start + name.len() + after.find(RIGHT_ANGLE)?;
+        let self_closing = !closing && source[..gt].ends_with(FORWARD_SLASH);
+        if closing {
+            depth -= 1;
+        } else if !self_closing {
+            depth += 1;
+        }
+        // The first tag is the one at `start`
+        if depth == 0 {
+            return Some(gt + 1);
+        }
+        i = gt + 1;
     }
+    None
 }
@@ -0,0 +1,202 @@
+//! Conversions between byte offsets, as used by the parser and in spans, and
+//! the line and column positions of editors. Editors following LSP (VS Code)
+//! count columns in UTF-16 code units, which differ from characters for
+//! content outside the Basic Multilingual Plane (emoji).
+//!
+//! `position` and the other functions scan the source on each call; a
+//! `LineIndex` finds lines by binary search, for many lookups in one source.
+
+/// Position of a byte offset: 1-based line, and 1-based column counted in
+/// characters and in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub utf16_column: usize,
+}
+
+/// Position of byte `offset` of `source`; offsets past the end are clamped.
+/// A leading byte order mark takes no column, as in editors.
+pub fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..floor_char_boundary(source, offset)];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = &before[line_start..];
+    let line = match line_start {
+        0 => line.strip_prefix('\u{FEFF}').unwrap_or(line),
+        _ => line,
+    };
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: line.chars().count() + 1,
+        utf16_column: line.encode_utf16().count() + 1,
+    }
+}
+
+/// A byte range (start, end) of a source, end excluded. Spans elsewhere in
+/// the crate are `(usize, usize)` tuples, which convert to and from it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether byte `offset` is inside the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// The text of the span, None when it is not a range of char boundaries
+    /// of `source`.
+    pub fn text<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.get(self.start..self.end)
+    }
+}
+
+impl From<(usize, usize)> for Span {
+    fn from((start, end): (usize, usize)) -> Self {
+        Self { start, end }
+    }
+}
+
+impl From<Span> for (usize, usize) {
+    fn from(span: Span) -> Self {
+        (span.start, span.end)
+    }
+}
+
+/// Start offsets of the lines of a source, mapping byte offsets to positions
+/// and back without rescanning the source. Lines end at `\n`; the `\r` of a
+/// CRLF line ending belongs to its line.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Number of lines; an empty source, or one ending with `\n`, ends with
+    /// an empty line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Span of 1-based `line`, without its line ending.
+    pub fn line_span(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let end = match self.source[start..end].ends_with('\r') {
+            true => end - 1,
+            false => end,
+        };
+        Some(Span::new(start, end))
+    }
+
+    /// Text of 1-based `line`, without its line ending.
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        self.line_span(line)?.text(self.source)
+    }
+
+    /// Same as `position(source, offset)`.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = floor_char_boundary(self.source, offset);
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let prefix = &self.source[self.line_starts[line - 1]..offset];
+        let prefix = match line {
+            1 => prefix.strip_prefix('\u{FEFF}').unwrap_or(prefix),
+            _ => prefix,
+        };
+        Position {
+            line,
+            column: prefix.chars().count() + 1,
+            utf16_column: prefix.encode_utf16().count() + 1,
+        }
+    }
+
+    /// Byte offset of 1-based `line` and `column` in characters. Columns past
+    /// the end of the line map to its end; None when the line does not exist.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        self.offset_by(line, column, |_| 1)
+    }
+
+    /// Same as `offset`, with the column in UTF-16 code units as in LSP.
+    pub fn offset_utf16(&self, line: usize, utf16_column: usize) -> Option<usize> {
+        self.offset_by(line, utf16_column, char::len_utf16)
+    }
+
+    // Start of the character of the line holding unit `column - 1`, units
+    // being counted by `units`; a column inside a character maps to its start.
+    fn offset_by(&self, line: usize, column: usize, units: fn(char) -> usize) -> Option<usize> {
+        let span = self.line_span(line)?;
+        let mut text = &self.source[span.start..span.end];
+        let mut start = span.start;
+        if line == 1 && text.starts_with('\u{FEFF}') {
+            text = &text['\u{FEFF}'.len_utf8()..];
+            start += '\u{FEFF}'.len_utf8();
+        }
+        let mut passed = 0;
+        for (i, c) in text.char_indices() {
+            passed += units(c);
+            if passed >= column {
+                return Some(start + i);
+            }
+        }
+        Some(span.end)
+    }
+}
+
+/// Byte `offset` of `source` as an index in UTF-16 code units, the index of
+/// JS strings.
+pub fn utf16_offset(source: &str, offset: usize) -> usize {
+    source[..floor_char_boundary(source, offset)]
+        .encode_utf16()
+        .count()
+}
+
+/// Byte offset of the UTF-16 index `utf16_offset` of `source`. An index in
+/// the middle of a surrogate pair maps to the start of its character, one
+/// past the end to the end of `source`.
+pub fn byte_offset(source: &str, utf16_offset: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in source.char_indices() {
+        units += c.len_utf16();
+        if units > utf16_offset {
+            return i;
+        }
+    }
+    source.len()
+}
+
+// The greatest char boundary of `source` at or before `offset`.
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
@@ -0,0 +1,94 @@
+//! JSX source of a tree, for codemods that parse, edit and print JSX back.
+//!
+//! Printing is lossless for everything the parser keeps: parsing the output
+//! of `reprint` gives the tree back,
+//!
+//! ```text
+//! parse(reprint(parse(x))) == parse(x)
+//! ```
+//!
+//! except for the spans of the JSX of embedded expressions
+//! (`ExpressionPart::Jsx`), which locate it in the printed text rather than
+//! in `x`. Formatting the parser drops is not restored: whitespace inside
+//! tags is a single space, attributes without children self-close, and
+//! the `{` `}` around spreads are added back. The printed text is a fixed
+//! point: printing its tree gives the same text.
+
+use crate::jsx_parser::types::{JSXAttribute, JSXAttributeValue, JSXNode};
+
+/// JSX source of `node`. Text, expressions and attribute values are printed
+/// as the parser keeps them, i.e. as written.
+pub fn reprint(node: &JSXNode) -> String {
+    let mut out = String::new();
+    write_node(&mut out, node);
+    out
+}
+
+fn write_node(out: &mut String, node: &JSXNode) {
+    match node {
+        JSXNode::Element {
+            tag,
+            attributes,
+            children,
+        } => {
+            out.push('<');
+            out.push_str(tag);
+            for attribute in attributes {
+                out.push(' ');
+                write_attribute(out, attribute);
+            }
+            if children.is_empty() {
+                out.push_str(" />");
+                return;
+            }
+            out.push('>');
+            for child in children {
+                write_node(out, child);
+            }
+            out.push_str("</");
+            out.push_str(tag);
+            out.push('>');
+        }
+        JSXNode::Fragment { children } => {
+            out.push_str("<>");
+            for child in children {
+                write_node(out, child);
+            }
+            out.push_str("</>");
+        }
+        JSXNode::Text(text) => out.push_str(text),
+        JSXNode::Expression(code) | JSXNode::EmbeddedExpression { raw: code, .. } => {
+            out.push('{');
+            out.push_str(code);
+            out.push('}');
+        }
+    }
+}
+
+fn write_attribute(out: &mut String, attribute: &JSXAttribute) {
+    if attribute.is_spread() {
+        out.push('{');
+        out.push_str(&attribute.name);
+        out.push('}');
+        return;
+    }
+    out.push_str(&attribute.name);
+    match &attribute.value {
+        None => {}
+        Some(JSXAttributeValue::DoubleQuote(value)) => {
+            out.push_str("=\"");
+            out.push_str(value);
+            out.push('"');
+        }
+        Some(JSXAttributeValue::SingleQuote(value)) => {
+            out.push_str("='");
+            out.push_str(value);
+            out.push('\'');
+        }
+        Some(JSXAttributeValue::Expression(code)) => {
+            out.push_str("={");
+            out.push_str(code);
+            out.push('}');
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Size and shape of the JSX of a source, for build dashboards and for
+//! deciding when hoisting or minifying is worth it.
+
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::parser::{recovery_offset, Parser};
+use crate::jsx_parser::types::JSXAttribute;
+use crate::jsx_parser::visitor::{walk_node, JSXVisitor};
+use crate::jsx_transformer::tags_attrs::{classify_tag, TagType};
+
+/// Counts over the JSX nodes of a source, the JSX of embedded expressions
+/// included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsxStats {
+    /// Elements, components included.
+    pub elements: usize,
+    /// Elements rendered as components (`<Card>`, `<Ui.Button>`).
+    pub components: usize,
+    pub fragments: usize,
+    /// Expression children, `{value}` and `{items.map(i => <li/>)}`.
+    pub expressions: usize,
+    /// Deepest nesting of elements and fragments, 1 for a lone `<br/>`.
+    pub max_depth: usize,
+    /// Attributes of all elements, spreads included.
+    pub attr_count: usize,
+    /// Bytes of text children as written, whitespace included.
+    pub text_bytes: usize,
+}
+
+/// Statistics of the JSX nodes of a JavaScript source, found as the transform
+/// finds them, in a single pass over each. Nodes that fail to parse are left
+/// out.
+pub fn analyze(source: &str) -> JsxStats {
+    let mut counter = Counter::default();
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        match Parser::new(&source[next..]).parse_next_with_span() {
+            Some(Ok((node, (_, end)))) => {
+                walk_node(&mut counter, &node);
+                i = next + end;
+            }
+            Some(Err(_)) => i = recovery_offset(source, next),
+            None => break,
+        }
+    }
+    counter.stats
+}
+
+#[derive(Default)]
+struct Counter {
+    stats: JsxStats,
+    depth: usize,
+}
+
+impl Counter {
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+    }
+}
+
+impl JSXVisitor for Counter {
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute]) {
+        self.enter();
+        self.stats.elements += 1;
+        if classify_tag(tag) == TagType::Component {
+            self.stats.components += 1;
+        }
+        self.stats.attr_count += attributes.len();
+    }
+
+    fn exit_element(&mut self, _tag: &str) {
+        self.depth -= 1;
+    }
+
+    fn enter_fragment(&mut self) {
+        self.enter();
+        self.stats.fragments += 1;
+    }
+
+    fn exit_fragment(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn visit_text(&mut self, text: &str) {
+        self.stats.text_bytes += text.len();
+    }
+
+    fn visit_expression(&mut self, _expr: &str) {
+        self.stats.expressions += 1;
+    }
+
+    fn enter_embedded_expression(&mut self, _raw: &str) {
+        self.stats.expressions += 1;
+    }
+}
@@ -0,0 +1,138 @@
+//! proptest generators of JSX trees, for property tests of code that parses,
+//! edits or prints JSX (see `reprint`). Generated trees are those the parser
+//! can produce: text never follows text, and the JSX of embedded expressions
+//! is preceded by code that starts JSX. Text and quoted attribute values have
+//! no braces, which embedded expressions would count when looking for their
+//! end, and text no `<`.
+//!
+//! ```text
+//! proptest! {
+//!     #[test]
+//!     fn prints_back(node in jsx_parser::strategies::jsx_node()) { ... }
+//! }
+//! ```
+
+use proptest::prelude::*;
+
+use crate::jsx_parser::printer::reprint;
+use crate::jsx_parser::types::{ExpressionPart, JSXAttribute, JSXAttributeValue, JSXNode};
+
+/// Elements and fragments up to 4 levels deep.
+pub fn jsx_node() -> impl Strategy<Value = JSXNode> {
+    let leaf = (tag(), prop::collection::vec(attribute(), 0..4)).prop_map(|(tag, attributes)| {
+        JSXNode::Element {
+            tag,
+            attributes,
+            children: vec![],
+        }
+    });
+    leaf.prop_recursive(4, 32, 4, |node| {
+        let children = prop::collection::vec(child(node.clone()), 0..5)
+            .prop_map(merge_texts)
+            .boxed();
+        prop_oneof![
+            (
+                tag(),
+                prop::collection::vec(attribute(), 0..4),
+                children.clone()
+            )
+                .prop_map(|(tag, attributes, children)| JSXNode::Element {
+                    tag,
+                    attributes,
+                    children,
+                }),
+            children.prop_map(|children| JSXNode::Fragment { children }),
+        ]
+    })
+}
+
+/// Tag names of elements and components, `script` excluded as its children
+/// are raw text.
+pub fn tag() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z][a-z0-9]{0,6}",
+        "[a-z]{1,4}-[a-z]{1,4}",
+        "[A-Z][a-zA-Z0-9]{0,6}",
+        "[A-Z][a-z]{0,4}\\.[A-Z][a-z]{0,4}",
+    ]
+    .prop_filter("script children are raw text", |tag| tag != "script")
+}
+
+/// Attributes of every kind, spreads included.
+pub fn attribute() -> impl Strategy<Value = JSXAttribute> {
+    let name = prop_oneof!["[a-z][a-zA-Z]{0,8}", "(data|aria)-[a-z]{1,6}"];
+    prop_oneof![
+        name.clone()
+            .prop_map(|name| JSXAttribute { name, value: None }),
+        (name, attribute_value()).prop_map(|(name, value)| JSXAttribute {
+            name,
+            value: Some(value),
+        }),
+        "[a-z][a-zA-Z]{0,6}".prop_map(|spread| JSXAttribute {
+            name: format!("...{spread}"),
+            value: None,
+        }),
+    ]
+}
+
+fn attribute_value() -> impl Strategy<Value = JSXAttributeValue> {
+    prop_oneof![
+        "[^\"{}]{0,12}".prop_map(JSXAttributeValue::DoubleQuote),
+        "[^'{}]{0,12}".prop_map(JSXAttributeValue::SingleQuote),
+        code().prop_map(JSXAttributeValue::Expression),
+    ]
+}
+
+// A child of an element or fragment.
+fn child(node: impl Strategy<Value = JSXNode> + Clone) -> impl Strategy<Value = JSXNode> {
+    prop_oneof![
+        3 => "[^<{}]{1,16}".prop_map(JSXNode::Text),
+        1 => node.clone(),
+        1 => code().prop_map(JSXNode::Expression),
+        1 => embedded_expression(node),
+    ]
+}
+
+// `{cond && <b/>}` and the like, with `raw` printed from the parts. Spans
+// are left empty: they depend on where the expression ends up.
+fn embedded_expression(node: impl Strategy<Value = JSXNode>) -> impl Strategy<Value = JSXNode> {
+    let before = prop_oneof![
+        Just("cond && "),
+        Just("ok ? "),
+        Just("items.map((item) => "),
+        Just("(")
+    ];
+    (before, node, code()).prop_map(|(before, node, after)| {
+        let after = match before {
+            "ok ? " => format!(" : {after}"),
+            "items.map((item) => " | "(" => format!("){after}"),
+            _ => after,
+        };
+        let raw = format!("{before}{}{after}", reprint(&node));
+        let mut parts = vec![
+            ExpressionPart::Code(before.to_string()),
+            ExpressionPart::Jsx { node, span: (0, 0) },
+        ];
+        if !after.is_empty() {
+            parts.push(ExpressionPart::Code(after));
+        }
+        JSXNode::EmbeddedExpression { raw, parts }
+    })
+}
+
+// JavaScript without JSX, braces, strings or comments.
+fn code() -> impl Strategy<Value = String> {
+    "[a-z0-9 .+*()]{0,12}"
+}
+
+// Adjacent texts parse as one.
+fn merge_texts(children: Vec<JSXNode>) -> Vec<JSXNode> {
+    let mut merged: Vec<JSXNode> = Vec::with_capacity(children.len());
+    for child in children {
+        match (merged.last_mut(), child) {
+            (Some(JSXNode::Text(last)), JSXNode::Text(text)) => last.push_str(&text),
+            (_, child) => merged.push(child),
+        }
+    }
+    merged
+}
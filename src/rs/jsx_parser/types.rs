@@ -1,4 +1,7 @@
-#[derive(Debug, PartialEq)]
+use crate::jsx_transformer::tags_attrs::{classify_tag, TagType};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum JSXNode {
     Element {
         tag: String,
@@ -10,21 +13,227 @@ pub enum JSXNode {
     },
     Text(String),
     Expression(String),
+    /// Expression child that embeds JSX, e.g. `{items.map(i => <li>{i}</li>)}`.
+    /// `raw` keeps the source text between the braces; `parts` splits it into
+    /// verbatim JavaScript chunks and parsed JSX subtrees.
+    EmbeddedExpression {
+        raw: String,
+        parts: Vec<ExpressionPart>,
+    },
+}
+
+impl JSXNode {
+    /// Tag name of an element, None for other nodes.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            JSXNode::Element { tag, .. } => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// Attributes of an element, in source order; empty for other nodes.
+    pub fn attributes(&self) -> &[JSXAttribute] {
+        match self {
+            JSXNode::Element { attributes, .. } => attributes,
+            _ => &[],
+        }
+    }
+
+    /// The last attribute of an element named `name` as written (`className`,
+    /// not `class`), which wins when repeated. Spread attributes never match.
+    pub fn get_attr(&self, name: &str) -> Option<&JSXAttribute> {
+        self.attributes()
+            .iter()
+            .rev()
+            .find(|a| a.name == name && !a.is_spread())
+    }
+
+    /// Children of an element or fragment; empty for other nodes. The JSX of
+    /// embedded expressions is in their `parts`.
+    pub fn children(&self) -> &[JSXNode] {
+        match self {
+            JSXNode::Element { children, .. } | JSXNode::Fragment { children } => children,
+            _ => &[],
+        }
+    }
+
+    /// Children without the whitespace-only text between them, i.e. the
+    /// indentation of the source.
+    pub fn children_non_whitespace(&self) -> impl Iterator<Item = &JSXNode> {
+        self.children()
+            .iter()
+            .filter(|child| !child.is_whitespace())
+    }
+
+    /// Whether this is an element rendered as a component (`<Card>`,
+    /// `<Ui.Button>`), with the default classification of `classify_tag`.
+    pub fn is_component(&self) -> bool {
+        self.tag()
+            .is_some_and(|tag| classify_tag(tag) == TagType::Component)
+    }
+
+    /// Whether this is a text node made of whitespace only.
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self, JSXNode::Text(text) if text.trim().is_empty())
+    }
+
+    /// Structural equality ignoring formatting: whitespace-only text between
+    /// children is skipped, and text and JavaScript code compare equal when
+    /// they only differ in runs of whitespace. Tags and attributes must match.
+    pub fn eq_ignore_whitespace(&self, other: &JSXNode) -> bool {
+        match (self, other) {
+            (JSXNode::Text(a), JSXNode::Text(b))
+            | (JSXNode::Expression(a), JSXNode::Expression(b)) => a.eq_ignore_whitespace(b),
+            (
+                JSXNode::Element {
+                    tag: a_tag,
+                    attributes: a_attributes,
+                    ..
+                },
+                JSXNode::Element {
+                    tag: b_tag,
+                    attributes: b_attributes,
+                    ..
+                },
+            ) => {
+                a_tag == b_tag
+                    && a_attributes.len() == b_attributes.len()
+                    && a_attributes
+                        .iter()
+                        .zip(b_attributes)
+                        .all(|(a, b)| a.eq_ignore_whitespace(b))
+                    && children_eq_ignore_whitespace(self, other)
+            }
+            (JSXNode::Fragment { .. }, JSXNode::Fragment { .. }) => {
+                children_eq_ignore_whitespace(self, other)
+            }
+            (
+                JSXNode::EmbeddedExpression { parts: a, .. },
+                JSXNode::EmbeddedExpression { parts: b, .. },
+            ) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|pair| match pair {
+                        (ExpressionPart::Code(a), ExpressionPart::Code(b)) => {
+                            a.eq_ignore_whitespace(b)
+                        }
+                        (
+                            ExpressionPart::Jsx { node: a, .. },
+                            ExpressionPart::Jsx { node: b, .. },
+                        ) => a.eq_ignore_whitespace(b),
+                        _ => false,
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+fn children_eq_ignore_whitespace(a: &JSXNode, b: &JSXNode) -> bool {
+    let mut b_children = b.children_non_whitespace();
+    a.children_non_whitespace()
+        .all(|a| b_children.next().is_some_and(|b| a.eq_ignore_whitespace(b)))
+        && b_children.next().is_none()
+}
+
+/// Equality up to whitespace, used by `assert_jsx_eq!`: runs of whitespace
+/// compare equal to a single space, and leading and trailing whitespace is
+/// ignored. For nodes, see `JSXNode::eq_ignore_whitespace`.
+pub trait EqIgnoreWhitespace {
+    fn eq_ignore_whitespace(&self, other: &Self) -> bool;
 }
 
-#[derive(Debug, PartialEq)]
+impl EqIgnoreWhitespace for str {
+    fn eq_ignore_whitespace(&self, other: &str) -> bool {
+        self.split_whitespace().eq(other.split_whitespace())
+    }
+}
+
+impl EqIgnoreWhitespace for String {
+    fn eq_ignore_whitespace(&self, other: &String) -> bool {
+        self.as_str().eq_ignore_whitespace(other.as_str())
+    }
+}
+
+impl EqIgnoreWhitespace for JSXNode {
+    fn eq_ignore_whitespace(&self, other: &JSXNode) -> bool {
+        JSXNode::eq_ignore_whitespace(self, other)
+    }
+}
+
+impl EqIgnoreWhitespace for JSXAttribute {
+    fn eq_ignore_whitespace(&self, other: &JSXAttribute) -> bool {
+        self.name == other.name
+            && match (&self.value, &other.value) {
+                (
+                    Some(JSXAttributeValue::Expression(a)),
+                    Some(JSXAttributeValue::Expression(b)),
+                ) => a.eq_ignore_whitespace(b),
+                (a, b) => a == b,
+            }
+    }
+}
+
+impl<T: EqIgnoreWhitespace + ?Sized> EqIgnoreWhitespace for &T {
+    fn eq_ignore_whitespace(&self, other: &Self) -> bool {
+        (**self).eq_ignore_whitespace(*other)
+    }
+}
+
+/// Byte spans (start, end) of the parts of an element, recorded by the parser
+/// alongside the AST (see `Parser::element_spans`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ElementSpans {
+    /// The opening tag, `<div class="a">` or `<br/>`.
+    pub open: (usize, usize),
+    /// The closing tag, `</div>`; None for self-closing elements.
+    pub close: Option<(usize, usize)>,
+    /// Between the tags; empty for self-closing elements.
+    pub children: (usize, usize),
+}
+
+/// A segment of an embedded expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ExpressionPart {
+    /// JavaScript source kept as written.
+    Code(String),
+    /// A JSX subtree with its byte span (start, end) in the parser input.
+    Jsx { node: JSXNode, span: (usize, usize) },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct JSXAttribute {
     pub name: String,
     pub value: Option<JSXAttributeValue>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum JSXAttributeValue {
     DoubleQuote(String),
     SingleQuote(String),
     Expression(String),
 }
 
+impl JSXAttribute {
+    /// Whether this is a spread attribute, `{...props}`; its name holds the
+    /// spread expression.
+    pub fn is_spread(&self) -> bool {
+        self.name.starts_with("...")
+    }
+
+    /// The value of a quoted attribute, None for expressions and booleans.
+    pub fn string_value(&self) -> Option<&str> {
+        match &self.value {
+            Some(JSXAttributeValue::DoubleQuote(value) | JSXAttributeValue::SingleQuote(value)) => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Structured streaming parser error with position for recovery/aggregation.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseError {
@@ -42,10 +251,13 @@ impl ParseError {
     }
 }
 
+/// An AST node and its byte span (start, end).
+pub type SpannedNode = (JSXNode, (usize, usize));
+
 /// Parse result including the AST node and its byte span (start, end).
-pub type ParseResultWithSpan = Result<(JSXNode, (usize, usize)), ParseError>;
+pub type ParseResultWithSpan = Result<SpannedNode, ParseError>;
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ParseResult {
     pub nodes: Vec<JSXNode>,
     pub errors: Vec<ParseError>,
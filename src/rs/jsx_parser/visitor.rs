@@ -1,4 +1,4 @@
-use crate::jsx_parser::types::{JSXAttribute, JSXNode};
+use crate::jsx_parser::types::{ExpressionPart, JSXAttribute, JSXNode};
 
 // Visitor trait and traversal utilities for JSX AST.
 // This decouples traversal from operations performed on nodes (e.g., transformations).
@@ -20,13 +20,22 @@ pub trait JSXVisitor {
 
     // Called for an expression node
     fn visit_expression(&mut self, _expr: &str) {}
+
+    // Called before visiting the parts of an expression that embeds JSX
+    fn enter_embedded_expression(&mut self, _raw: &str) {}
+
+    // Called for a verbatim JavaScript chunk of an embedded expression
+    fn visit_expression_code(&mut self, _code: &str) {}
+
+    // Called after visiting the parts of an embedded expression
+    fn exit_embedded_expression(&mut self, _raw: &str) {}
 }
 
 /// Walk a single JSX node, invoking visitor hooks appropriately.
 /// The traversal order is:
-/// - enter_element/enter_fragment
-/// - children (depth-first)
-/// - exit_element/exit_fragment
+/// - enter_element/enter_fragment/enter_embedded_expression
+/// - children or expression parts (depth-first)
+/// - exit_element/exit_fragment/exit_embedded_expression
 pub fn walk_node<V: JSXVisitor + ?Sized>(visitor: &mut V, node: &JSXNode) {
     match node {
         JSXNode::Element {
@@ -49,6 +58,16 @@ pub fn walk_node<V: JSXVisitor + ?Sized>(visitor: &mut V, node: &JSXNode) {
         }
         JSXNode::Text(text) => visitor.visit_text(text),
         JSXNode::Expression(expr) => visitor.visit_expression(expr),
+        JSXNode::EmbeddedExpression { raw, parts } => {
+            visitor.enter_embedded_expression(raw);
+            for part in parts {
+                match part {
+                    ExpressionPart::Code(code) => visitor.visit_expression_code(code),
+                    ExpressionPart::Jsx { node, .. } => walk_node(visitor, node),
+                }
+            }
+            visitor.exit_embedded_expression(raw);
+        }
     }
 }
 
@@ -1,4 +1,9 @@
-use crate::jsx_parser::{JSXAttribute, JSXAttributeValue, JSXNode, Parser};
+use crate::extract::jsx_slices;
+use crate::jsx_parser::{
+    analyze, auto_import_edits, byte_offset, components, missing_imports, position,
+    recovery_offset, reprint, scan_imports, tokenize, utf16_offset, ExpressionPart, JSXAttribute,
+    JSXAttributeValue, JSXNode, JsxStats, JsxTokenKind, LineIndex, Parser, Span, TextEdit,
+};
 
 #[test]
 fn test_parse_simple_element() {
@@ -524,10 +529,31 @@ fn test_parse_list_map() {
             assert_eq!(tag, "ul");
             assert_eq!(children.len(), 1);
             match &children[0] {
-                JSXNode::Expression(expr) => {
-                    assert_eq!(expr, "items.map(item => <li>{item.name}</li>)");
+                JSXNode::EmbeddedExpression { raw, parts } => {
+                    assert_eq!(raw, "items.map(item => <li>{item.name}</li>)");
+                    assert_eq!(parts.len(), 3);
+                    assert_eq!(
+                        parts[0],
+                        ExpressionPart::Code("items.map(item => ".to_string())
+                    );
+                    match &parts[1] {
+                        ExpressionPart::Jsx { node, span } => {
+                            assert_eq!(
+                                *node,
+                                JSXNode::Element {
+                                    tag: "li".to_string(),
+                                    attributes: vec![],
+                                    children: vec![JSXNode::Expression("item.name".to_string())],
+                                }
+                            );
+                            // Span is relative to the parser input, not to the expression
+                            assert_eq!(*span, (23, 43));
+                        }
+                        _ => panic!("Expected Jsx part"),
+                    }
+                    assert_eq!(parts[2], ExpressionPart::Code(")".to_string()));
                 }
-                _ => panic!("Expected Expression"),
+                _ => panic!("Expected EmbeddedExpression"),
             }
         }
         _ => panic!("Expected Element"),
@@ -554,6 +580,42 @@ fn test_parse_template_string() {
     }
 }
 
+#[test]
+fn test_parse_backticks_in_strings_and_comments() {
+    let cases = [
+        ("<p>{'a`b'}</p>", "'a`b'"),
+        ("<p>{\"a`b\"}</p>", "\"a`b\""),
+        ("<p>{'a\\'`'}</p>", "'a\\'`'"),
+        ("<p>{x /* ` */}</p>", "x /* ` */"),
+        ("<p>{a // `\n}</p>", "a // `\n"),
+    ];
+    for (input, expr) in cases {
+        assert_eq!(
+            Parser::new(input).parse().unwrap(),
+            JSXNode::Element {
+                tag: "p".to_string(),
+                attributes: vec![],
+                children: vec![JSXNode::Expression(expr.to_string())],
+            },
+            "{input}"
+        );
+    }
+
+    match Parser::new("<p title={'a`b'} />").parse().unwrap() {
+        JSXNode::Element { attributes, .. } => {
+            assert_eq!(
+                attributes[0].value,
+                Some(JSXAttributeValue::Expression("'a`b'".to_string()))
+            );
+        }
+        _ => panic!("Expected Element"),
+    }
+    // Unclosed, but not for the backtick in the attribute value
+    let result = Parser::new("<p title={'a`b'}>").parse();
+    assert_eq!(result.errors.len(), 1);
+    assert_ne!(result.errors[0].message, "Unclosed expression");
+}
+
 #[test]
 fn test_fragment_with_nested_structure() {
     let input = "<><div>Content</div></>";
@@ -634,10 +696,19 @@ fn test_conditional_rendering() {
             assert_eq!(tag, "div");
             assert_eq!(children.len(), 1);
             match &children[0] {
-                JSXNode::Expression(expr) => {
-                    assert_eq!(expr, r#"loading ? <Spinner size="large" /> : <Content />"#);
+                JSXNode::EmbeddedExpression { raw, parts } => {
+                    assert_eq!(raw, r#"loading ? <Spinner size="large" /> : <Content />"#);
+                    let nested: Vec<_> = parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            ExpressionPart::Jsx { node, .. } => Some(node),
+                            ExpressionPart::Code(_) => None,
+                        })
+                        .collect();
+                    assert!(matches!(nested[0], JSXNode::Element { tag, .. } if tag == "Spinner"));
+                    assert!(matches!(nested[1], JSXNode::Element { tag, .. } if tag == "Content"));
                 }
-                _ => panic!("Expected Expression"),
+                _ => panic!("Expected EmbeddedExpression"),
             }
         }
         _ => panic!("Expected Element"),
@@ -691,13 +762,13 @@ fn test_list_mapping() {
             assert_eq!(tag, "div");
             assert_eq!(children.len(), 1);
             match &children[0] {
-                JSXNode::Expression(expr) => {
+                JSXNode::EmbeddedExpression { raw, .. } => {
                     assert_eq!(
-                        expr,
+                        raw,
                         "items.map((item, index) => <Item key={index} data={item} />)"
                     );
                 }
-                _ => panic!("Expected Expression"),
+                _ => panic!("Expected EmbeddedExpression"),
             }
         }
         _ => panic!("Expected Element"),
@@ -1004,11 +1075,11 @@ fn test_parse_complex_nested_structure() {
                     // Verify the conditional expression is present
                     let conditional = children
                         .iter()
-                        .find(|child| matches!(child, JSXNode::Expression(_)))
+                        .find(|child| matches!(child, JSXNode::EmbeddedExpression { .. }))
                         .expect("Expected to find conditional expression");
 
                     match conditional {
-                        JSXNode::Expression(expr) => {
+                        JSXNode::EmbeddedExpression { raw: expr, .. } => {
                             assert!(expr.contains("loading ?"));
                             assert!(expr.contains("<Spinner size=\"large\" />"));
                             assert!(expr.contains("<nav>"));
@@ -1045,14 +1116,14 @@ fn test_parse_conditional_expressions() {
             assert_eq!(meaningful_children.len(), 2);
 
             match &meaningful_children[0] {
-                JSXNode::Expression(expr) => {
+                JSXNode::EmbeddedExpression { raw: expr, .. } => {
                     assert_eq!(expr, "condition && <div>Conditional Content</div>");
                 }
                 _ => panic!("Expected condition expression"),
             }
 
             match &meaningful_children[1] {
-                JSXNode::Expression(expr) => {
+                JSXNode::EmbeddedExpression { raw: expr, .. } => {
                     assert_eq!(
                         expr,
                         "items.length === 0 ? <EmptyState /> : <List items={items} />"
@@ -1254,7 +1325,7 @@ fn test_parse_complex_layout_with_conditionals() {
             assert!(matches!(header_nodes[0], JSXNode::Element { tag, .. } if tag == "h1"));
             assert!(matches!(header_nodes[1], JSXNode::Element { tag, .. } if tag == "nav"));
 
-            if let JSXNode::Expression(expr) = header_nodes[2] {
+            if let JSXNode::EmbeddedExpression { raw: expr, .. } = header_nodes[2] {
                 assert!(expr.contains("user ?"));
                 assert!(expr.contains("styles.userMenu"));
                 assert!(expr.contains("styles.loginButton"));
@@ -1271,7 +1342,7 @@ fn test_parse_complex_layout_with_conditionals() {
             assert_eq!(tag, "main");
             assert_class_name(attributes, "styles.main");
 
-            if let JSXNode::Expression(expr) = &children[0] {
+            if let JSXNode::EmbeddedExpression { raw: expr, .. } = &children[0] {
                 assert!(expr.contains("loading ?"));
                 assert!(expr.contains("<ErrorMessage"));
             }
@@ -1324,32 +1395,12 @@ fn test_error_unclosed_attribute() {
     assert!(parser.parse().is_err());
 }
 
-// Helper to collect JSX slices from raw source using the streaming parser
+// Text of the JSX slices of a source
 fn collect_jsx_slices(source: &str) -> Vec<String> {
-    let mut out = Vec::new();
-    let mut i = 0;
-    while i < source.len() {
-        if let Some(rel) = source[i..].find('<') {
-            i += rel;
-        } else {
-            break;
-        }
-        let mut p = Parser::new(&source[i..]);
-        match p.parse_next_with_span() {
-            Some(Ok((_node, (start, end)))) => {
-                let start_abs = i + start;
-                let end_abs = i + end;
-                out.push(source[start_abs..end_abs].to_string());
-                i = end_abs;
-            }
-            Some(Err(_)) => {
-                // error recovery: advance one byte and try again
-                i += 1;
-            }
-            None => break,
-        }
-    }
-    out
+    jsx_slices(source)
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect()
 }
 
 // ---- Ported extractor tests (collection semantics) ----
@@ -1941,3 +1992,560 @@ fn collector_multiple_top_level_elements() {
         vec!["<div>One</div>".to_string(), "<span>Two</span>".to_string()]
     );
 }
+
+// (kind, text) of every token of the source
+fn lex(source: &str) -> Vec<(JsxTokenKind, &str)> {
+    let result = tokenize(source);
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    result
+        .tokens
+        .iter()
+        .map(|t| (t.kind, &source[t.span.0..t.span.1]))
+        .collect()
+}
+
+#[test]
+fn test_tokenize() {
+    use JsxTokenKind::*;
+
+    assert_eq!(
+        lex(r#"const a = <a href="/" {...rest}>Hi {name}</a>;"#),
+        vec![
+            (TagOpen, "<"),
+            (TagName, "a"),
+            (AttrName, "href"),
+            (Equals, "="),
+            (AttrValue, "\"/\""),
+            (Unknown, "{"),
+            (Spread, "...rest"),
+            (Unknown, "}"),
+            (TagEnd, ">"),
+            (Text, "Hi "),
+            (ExprStart, "{"),
+            (Expression, "name"),
+            (ExprEnd, "}"),
+            (TagClose, "</"),
+            (TagName, "a"),
+            (TagEnd, ">"),
+        ]
+    );
+
+    assert_eq!(
+        lex("<><ul>{items.map(i => <li key={i}>{i}</li>)}</ul><br /></>"),
+        vec![
+            (FragmentOpen, "<>"),
+            (TagOpen, "<"),
+            (TagName, "ul"),
+            (TagEnd, ">"),
+            (ExprStart, "{"),
+            (Expression, "items.map(i => "),
+            (TagOpen, "<"),
+            (TagName, "li"),
+            (AttrName, "key"),
+            (Equals, "="),
+            (ExprStart, "{"),
+            (Expression, "i"),
+            (ExprEnd, "}"),
+            (TagEnd, ">"),
+            (ExprStart, "{"),
+            (Expression, "i"),
+            (ExprEnd, "}"),
+            (TagClose, "</"),
+            (TagName, "li"),
+            (TagEnd, ">"),
+            (Expression, ")"),
+            (ExprEnd, "}"),
+            (TagClose, "</"),
+            (TagName, "ul"),
+            (TagEnd, ">"),
+            (TagOpen, "<"),
+            (TagName, "br"),
+            (SelfClose, "/>"),
+            (FragmentClose, "</>"),
+        ]
+    );
+
+    assert_eq!(
+        lex("<script>if (a < b) {}</script>"),
+        vec![
+            (TagOpen, "<"),
+            (TagName, "script"),
+            (TagEnd, ">"),
+            (Text, "if (a < b) {}"),
+            (TagClose, "</"),
+            (TagName, "script"),
+            (TagEnd, ">"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_comparisons() {
+    use JsxTokenKind::*;
+
+    assert_eq!(
+        lex("<p>{a<b && <i/>}{ {}<y }</p>"),
+        [
+            (TagOpen, "<"),
+            (TagName, "p"),
+            (TagEnd, ">"),
+            (ExprStart, "{"),
+            (Expression, "a<b && "),
+            (TagOpen, "<"),
+            (TagName, "i"),
+            (SelfClose, "/>"),
+            (ExprEnd, "}"),
+            (ExprStart, "{"),
+            (Expression, " {}<y "),
+            (ExprEnd, "}"),
+            (TagClose, "</"),
+            (TagName, "p"),
+            (TagEnd, ">"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_template_literals() {
+    use JsxTokenKind::*;
+
+    assert_eq!(
+        lex("<p>{css`a {`}</p>"),
+        [
+            (TagOpen, "<"),
+            (TagName, "p"),
+            (TagEnd, ">"),
+            (ExprStart, "{"),
+            (Expression, "css`a {`"),
+            (ExprEnd, "}"),
+            (TagClose, "</"),
+            (TagName, "p"),
+            (TagEnd, ">"),
+        ]
+    );
+}
+
+#[test]
+fn test_tokenize_errors() {
+    let source = "const a = <div></span>;\nconst b = <p>ok</p>;";
+    let result = tokenize(source);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].position, source.find("span").unwrap());
+    // Lexing resumes after the error
+    let last = result.tokens.last().unwrap();
+    assert_eq!(&source[last.span.0..], ">;");
+    assert!(Parser::new(source).parse().is_err());
+}
+
+#[test]
+fn test_node_spans() {
+    let source = "<div a={1}>x<script>if (a</b) {}</script>{<b/>}</div>";
+    let mut parser = Parser::new(source);
+    parser.parse_next_with_span().unwrap().unwrap();
+    let spans: Vec<&str> = parser
+        .node_spans()
+        .iter()
+        .map(|&(start, end)| &source[start..end])
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            source,
+            "x",
+            "<script>if (a</b) {}</script>",
+            "if (a</b) {}",
+            "{<b/>}",
+            "<b/>",
+        ]
+    );
+}
+
+#[test]
+fn test_utf16_positions() {
+    let source = "a = 1;\nconst \u{1F600}é = <b>";
+    let offset = source.find("<b>").unwrap();
+    let at = position(source, offset);
+    assert_eq!((at.line, at.column, at.utf16_column), (2, 12, 13));
+    assert_eq!(utf16_offset(source, offset), 19);
+    assert_eq!(byte_offset(source, 19), offset);
+
+    // Inside a surrogate pair and inside a character: their start
+    let emoji = source.find('\u{1F600}').unwrap();
+    assert_eq!(byte_offset(source, 14), emoji);
+    assert_eq!(position(source, emoji + 1).utf16_column, 7);
+    assert_eq!(utf16_offset(source, emoji + 1), 13);
+
+    assert_eq!(byte_offset(source, 1000), source.len());
+    assert_eq!(position(source, 1000).line, 2);
+    assert_eq!(position("", 0).column, 1);
+}
+
+#[test]
+fn test_line_index() {
+    let source = "\u{FEFF}a = 1;\r\nconst \u{1F600}é = <b>\n";
+    let index = LineIndex::new(source);
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.line(1), Some("\u{FEFF}a = 1;"));
+    assert_eq!(index.line(2), Some("const \u{1F600}é = <b>"));
+    assert_eq!(index.line(3), Some(""));
+    assert_eq!(index.line(0), None);
+    assert_eq!(index.line(4), None);
+
+    // Same positions as `position`, for every offset
+    for offset in 0..=source.len() + 1 {
+        assert_eq!(index.position(offset), position(source, offset), "{offset}");
+    }
+
+    let b = source.find("<b>").unwrap();
+    assert_eq!(index.offset(2, 12), Some(b));
+    assert_eq!(index.offset_utf16(2, 13), Some(b));
+    // Inside the surrogate pair: its start; past the end of a line: its end
+    let emoji = source.find('\u{1F600}').unwrap();
+    assert_eq!(index.offset_utf16(2, 8), Some(emoji));
+    assert_eq!(index.offset(1, 99), Some(source.find('\r').unwrap()));
+    assert_eq!(index.offset(1, 1), Some('\u{FEFF}'.len_utf8()));
+    assert_eq!(index.offset(9, 1), None);
+
+    let span = Span::from((b, b + 3));
+    assert_eq!((span.len(), span.text(source)), (3, Some("<b>")));
+    assert!(span.contains(b) && !span.contains(b + 3));
+    assert_eq!(<(usize, usize)>::from(span), (b, b + 3));
+}
+
+#[test]
+fn test_node_helpers() {
+    let node = Parser::new(
+        "<ul class=\"a\" class='b' {...rest} hidden>\n  <li>x</li>\n  <Card.Item />\n  text\n</ul>",
+    )
+    .parse()
+    .unwrap();
+    assert_eq!(node.tag(), Some("ul"));
+    assert_eq!(node.attributes().len(), 4);
+    assert_eq!(
+        node.get_attr("class").and_then(|a| a.string_value()),
+        Some("b")
+    );
+    assert!(node
+        .get_attr("hidden")
+        .is_some_and(|a| a.string_value().is_none()));
+    assert!(node.get_attr("...rest").is_none());
+    assert!(node.attributes()[2].is_spread());
+
+    let children: Vec<&JSXNode> = node.children_non_whitespace().collect();
+    assert_eq!(children.len(), 3);
+    assert_eq!(node.children().len(), 5);
+    assert!(!children[0].is_component() && children[1].is_component());
+    assert_eq!(children[2], &JSXNode::Text("\n  text\n".to_string()));
+    assert!(node.children()[0].is_whitespace());
+
+    // Clone, Eq and Hash: equal trees hash the same
+    use std::collections::HashSet;
+    let copy = node.clone();
+    let set: HashSet<&JSXNode> = [&node, &copy].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert!(JSXNode::Text("x".into()).children().is_empty());
+}
+
+#[test]
+fn test_eq_ignore_whitespace() {
+    let parse = |source: &str| Parser::new(source).parse().unwrap();
+    let compact = parse("<ul class=\"a\"><li>Hello world</li>{items.map(i => <b>{i}</b>)}</ul>");
+    let pretty = parse(
+        "<ul class=\"a\">\n  <li>\n    Hello   world\n  </li>\n  {items.map(i =>\n    <b>{ i }</b>)}\n</ul>",
+    );
+    assert_ne!(compact, pretty);
+    assert!(compact.eq_ignore_whitespace(&pretty));
+    crate::assert_jsx_eq!(compact, pretty);
+    crate::assert_jsx_eq!("a  b\n", " a b");
+
+    // Attribute values, tags and text still count
+    assert!(!compact.eq_ignore_whitespace(&parse("<ul class=\" a\"><li>Hello world</li></ul>")));
+    assert!(!parse("<p>ab</p>").eq_ignore_whitespace(&parse("<p>a b</p>")));
+    assert!(!parse("<p><b/></p>").eq_ignore_whitespace(&parse("<p><b/><b/></p>")));
+}
+
+#[test]
+#[should_panic(expected = "ignoring whitespace")]
+fn test_assert_jsx_eq_fails() {
+    crate::assert_jsx_eq!("<p>a</p>", "<p>b</p>");
+}
+
+#[test]
+fn test_recovery_offset() {
+    let end_of = |source: &str, part: &str| source.find(part).unwrap() + part.len();
+
+    // Past the matching closing tag, counting nested tags of the same name
+    let source = "<div><div><b></div></div>; next";
+    assert_eq!(recovery_offset(source, 0), end_of(source, "</div></div>"));
+    let source = "<ul><ul/><li></ul > <p/>";
+    assert_eq!(recovery_offset(source, 0), end_of(source, "</ul >"));
+    let source = "<><b></> x";
+    assert_eq!(recovery_offset(source, 0), end_of(source, "</>"));
+    let source = "<p a=\"x\" {...}/> <p>";
+    assert_eq!(recovery_offset(source, 0), end_of(source, "/>"));
+    // `<divider>` does not close `<div>`
+    let source = "<div><divider></divider></div>";
+    assert_eq!(recovery_offset(source, 0), source.len());
+
+    // Unclosed: the next opening tag, skipping closing ones
+    let source = "<p></b></i> <em>";
+    assert_eq!(recovery_offset(source, 0), source.find("<em>").unwrap());
+    assert_eq!(recovery_offset("<p></b>", 0), 7);
+    assert_eq!(recovery_offset("< 3", 0), 3);
+}
+
+#[test]
+fn test_stray_closing_tags() {
+    let source = "const a = <p>x</p>;\n</div></ Ui.Card>\nconst b = <b>ok</b>;";
+    let errors = crate::jsx_transformer::parse_jsx(source).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].position, source.find("</div>").unwrap());
+    assert_eq!(errors[0].message, "Unmatched closing tag: </div>");
+    let mut parser = Parser::new("x </ Ui.Card>");
+    assert_eq!(
+        parser.parse_next().unwrap().unwrap_err().message,
+        "Unmatched closing tag: </Ui.Card>"
+    );
+
+    let result = tokenize(source);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].message, "Unmatched closing tag");
+}
+
+#[test]
+fn test_element_spans() {
+    let source = "<ul class=\"a\">{items.map(i => <li key={i}>{i}</li>)}<br/></ul >";
+    let mut parser = Parser::new(source);
+    parser.parse_next_with_span().unwrap().unwrap();
+    let text = |span: (usize, usize)| &source[span.0..span.1];
+    let spans = parser.element_spans();
+    assert_eq!(spans.len(), 3);
+    assert_eq!(parser.element_starts().len(), 3);
+
+    assert_eq!(text(spans[0].open), "<ul class=\"a\">");
+    assert_eq!(spans[0].close.map(text), Some("</ul >"));
+    assert_eq!(
+        text(spans[0].children),
+        "{items.map(i => <li key={i}>{i}</li>)}<br/>"
+    );
+    assert_eq!(text(spans[1].open), "<li key={i}>");
+    assert_eq!(spans[1].close.map(text), Some("</li>"));
+    assert_eq!(text(spans[1].children), "{i}");
+    assert_eq!(text(spans[2].open), "<br/>");
+    assert_eq!(spans[2].close, None);
+    assert_eq!(spans[2].children, (spans[2].open.1, spans[2].open.1));
+
+    let source = "<script>a</b</script>";
+    let mut parser = Parser::new(source);
+    parser.parse_next_with_span().unwrap().unwrap();
+    let spans = parser.element_spans();
+    assert_eq!(
+        spans[0].close.map(|(s, e)| &source[s..e]),
+        Some("</script>")
+    );
+    assert_eq!(&source[spans[0].children.0..spans[0].children.1], "a</b");
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    let source = "<Über größe=\"1\"><ñandú/></Über>";
+    let result = Parser::new(source).parse_next();
+    if cfg!(feature = "unicode-identifiers") {
+        let Some(Ok(JSXNode::Element {
+            tag,
+            attributes,
+            children,
+        })) = result
+        else {
+            panic!("expected an element: {result:?}");
+        };
+        assert_eq!(tag, "Über");
+        assert_eq!(attributes[0].name, "größe");
+        assert!(matches!(&children[0], JSXNode::Element { tag, .. } if tag == "ñandú"));
+    } else {
+        assert!(matches!(result, Some(Err(_))));
+    }
+}
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize| "<a>".repeat(depth) + &"</a>".repeat(depth);
+    assert!(Parser::new(&nested(256)).parse_next().unwrap().is_ok());
+    let error = Parser::new(&nested(257)).parse_next().unwrap().unwrap_err();
+    assert_eq!(error.message, "Nesting too deep");
+
+    // Embedded expressions count toward the same depth
+    let expressions = "<a>{".repeat(200) + &"}</a>".repeat(200);
+    let error = Parser::new(&expressions).parse_next().unwrap().unwrap_err();
+    assert_eq!(error.message, "Nesting too deep");
+}
+
+// The node with the spans of embedded JSX cleared, which `reprint` moves.
+fn without_spans(node: &JSXNode) -> JSXNode {
+    let mut node = node.clone();
+    clear_spans(&mut node);
+    node
+}
+
+fn clear_spans(node: &mut JSXNode) {
+    match node {
+        JSXNode::Element { children, .. } | JSXNode::Fragment { children } => {
+            children.iter_mut().for_each(clear_spans)
+        }
+        JSXNode::EmbeddedExpression { parts, .. } => {
+            for part in parts {
+                if let ExpressionPart::Jsx { node, span } = part {
+                    *span = (0, 0);
+                    clear_spans(node);
+                }
+            }
+        }
+        JSXNode::Text(_) | JSXNode::Expression(_) => {}
+    }
+}
+
+#[test]
+fn test_reprint() {
+    let source = "<ul  class=\"a\" id='b' hidden {...rest} onClick={() => go(1)}>\n  \
+                  {items.map(i => <li key={i}>{i}</li>)}<br/><></>text &amp; more</ul>";
+    let node = Parser::new(source).parse().unwrap();
+    let printed = reprint(&node);
+    assert_eq!(
+        printed,
+        "<ul class=\"a\" id='b' hidden {...rest} onClick={() => go(1)}>\n  \
+         {items.map(i => <li key={i}>{i}</li>)}<br /><></>text &amp; more</ul>"
+    );
+    let reparsed = Parser::new(&printed).parse().unwrap();
+    assert_eq!(without_spans(&reparsed), without_spans(&node));
+    assert_eq!(reprint(&reparsed), printed);
+
+    // Raw script content, and spreads of expressions
+    for source in ["<script>if (a < b) {}</script>", "<p {...(a ?? b)} />"] {
+        let node = Parser::new(source).parse().unwrap();
+        assert_eq!(reprint(&node), source);
+    }
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_reprint_round_trip(node in crate::jsx_parser::strategies::jsx_node()) {
+        let printed = reprint(&node);
+        let reparsed = Parser::new(&printed).parse().unwrap();
+        proptest::prop_assert_eq!(without_spans(&reparsed), without_spans(&node));
+        proptest::prop_assert_eq!(reprint(&reparsed), printed);
+    }
+}
+
+#[test]
+fn test_analyze() {
+    let source = r#"
+        const a = <main class="page" {...rest}>
+            <Card title="x">hello</Card>
+            {items.map(i => <><li key={i}>{i}</li></>)}
+            <br/>
+        </main>;
+        const broken = <p>unclosed;
+        const b = <Icon.Star />;
+    "#;
+    let stats = analyze(source);
+    assert_eq!(
+        stats,
+        JsxStats {
+            elements: 5,
+            components: 2,
+            fragments: 1,
+            expressions: 2,
+            max_depth: 3,
+            attr_count: 4,
+            text_bytes: stats.text_bytes,
+        }
+    );
+    // "hello" and the indentation between the children of <main>
+    assert_eq!(
+        stats.text_bytes,
+        5 + 3 * "\n            ".len() + "\n        ".len()
+    );
+    assert_eq!(analyze("let x = 1 < 2;"), JsxStats::default());
+}
+
+#[test]
+fn test_components() {
+    let source = r#"const a = <Layout title="Home" {...rest}>
+    <p>{items.map(i => <Ui.Card key={i} />)}</p>
+    < Footer/><my-icon/>
+</Layout>;
+const broken = <Card>;
+const b = <Layout />;"#;
+    let refs = components(source);
+    let names: Vec<_> = refs.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["Layout", "Ui.Card", "Footer", "Layout"]);
+    for r in &refs {
+        assert_eq!(&source[r.span.0..r.span.1], r.name);
+    }
+    assert_eq!(refs[0].props, ["title", "...rest"]);
+    assert_eq!(refs[1].props, ["key"]);
+    assert_eq!(refs[1].binding(), "Ui");
+    assert!(refs[3].props.is_empty());
+}
+
+#[test]
+fn test_imports() {
+    let source = r#"import Layout, { Card as Tile, type Props } from "./layout.js";
+import * as Ui from './ui.js' // widgets
+import "./page.css";
+import type { Theme } from "./theme.js";
+const lazy = import("./lazy.js");
+
+function Footer() {
+    return <footer>Don't {"import"} me</footer>;
+}
+
+export const page = (
+    <Layout>
+        <Tile /><Ui.Button /><Card /><Theme /><Icon />
+        {list.map(i => <Card key={i} />)}
+        <Footer />
+    </Layout>
+);"#;
+    let imports = scan_imports(source);
+    let summary: Vec<_> = imports
+        .iter()
+        .map(|i| (i.module.as_str(), i.bindings.join(",")))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("./layout.js", "Layout,Tile".to_string()),
+            ("./ui.js", "Ui".to_string()),
+            ("./page.css", String::new()),
+            ("./theme.js", String::new()),
+        ]
+    );
+    assert!(source[imports[0].span.0..imports[0].span.1].ends_with("\"./layout.js\";"));
+    assert!(source[imports[1].span.0..imports[1].span.1].ends_with("'./ui.js'"));
+
+    let missing = missing_imports(source, &imports);
+    let names: Vec<_> = missing.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["Card", "Theme", "Icon"]);
+    assert_eq!(missing[0].references.len(), 2);
+
+    let edits = auto_import_edits(source, &missing, |name| match name {
+        "Card" | "Icon" => Some("./components.js".to_string()),
+        _ => None,
+    });
+    let end = imports[3].span.1;
+    assert_eq!(
+        edits,
+        [TextEdit {
+            span: (end, end),
+            text: "\nimport { Card, Icon } from \"./components.js\";".to_string(),
+        }]
+    );
+
+    // Without imports, at the start
+    let source = "export default () => <Card />;";
+    let missing = missing_imports(source, &scan_imports(source));
+    let edits = auto_import_edits(source, &missing, |_| Some("./card.js".to_string()));
+    assert_eq!(edits[0].span, (0, 0));
+    assert_eq!(edits[0].text, "import { Card } from \"./card.js\";\n");
+}
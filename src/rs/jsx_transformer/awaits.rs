@@ -0,0 +1,116 @@
+//! Detection of `await` in JSX expressions. A JSX root using `await` (or
+//! streaming an iterable, see `stream_methods`) emits its templates tagged with
+//! the async helper (`__jsxAsync`...``), which resolves interpolated promises,
+//! so the generated code also works outside async scopes.
+
+use std::borrow::Cow;
+
+use crate::jsx_parser::{ExpressionPart, JSXAttributeValue, JSXNode};
+
+/// True when `code` uses the `await` keyword outside string literals.
+pub(crate) fn contains_await(code: &str) -> bool {
+    let mut chars = code.char_indices().peekable();
+    let mut prev_ident = false;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' | '`' => {
+                // Skip the literal (template interpolations included)
+                while let Some((_, d)) = chars.next() {
+                    if d == '\\' {
+                        chars.next();
+                    } else if d == c {
+                        break;
+                    }
+                }
+                prev_ident = false;
+            }
+            c if is_ident_char(c) => {
+                if !prev_ident && code[i..].starts_with("await") {
+                    let after = code[i + 5..].chars().next();
+                    if !after.is_some_and(is_ident_char) {
+                        return true;
+                    }
+                }
+                prev_ident = true;
+            }
+            _ => prev_ident = false,
+        }
+    }
+    false
+}
+
+/// True when `pred` holds for any expression source in the tree: attribute
+/// values, children and the code chunks of embedded expressions.
+pub(crate) fn any_expression(node: &JSXNode, pred: &impl Fn(&str) -> bool) -> bool {
+    match node {
+        JSXNode::Element {
+            attributes,
+            children,
+            ..
+        } => attributes.iter().any(
+            |attr| matches!(&attr.value, Some(JSXAttributeValue::Expression(expr)) if pred(expr)),
+        ) || children.iter().any(|child| any_expression(child, pred)),
+        JSXNode::Fragment { children } => children.iter().any(|child| any_expression(child, pred)),
+        JSXNode::Text(_) => false,
+        JSXNode::Expression(expr) => pred(expr),
+        JSXNode::EmbeddedExpression { parts, .. } => parts.iter().any(|part| match part {
+            ExpressionPart::Code(code) => pred(code),
+            ExpressionPart::Jsx { node, .. } => any_expression(node, pred),
+        }),
+    }
+}
+
+/// Rewrites an interpolated expression that uses `await` into a promise the
+/// async template tag resolves: `await fetchTitle()` becomes `fetchTitle()`,
+/// anything else an async IIFE, `(async () => (expr))()`.
+pub(crate) fn async_interpolation(expr: &str) -> Cow<'_, str> {
+    if !contains_await(expr) {
+        return Cow::Borrowed(expr);
+    }
+    match await_operand(expr) {
+        Some(operand) => Cow::Borrowed(operand),
+        None => Cow::Owned(format!("(async () => ({}))()", expr.trim())),
+    }
+}
+
+// Operand of a leading `await` when it is a plain member/call chain without
+// further awaits, so dropping the keyword keeps the meaning.
+fn await_operand(expr: &str) -> Option<&str> {
+    let rest = expr.trim().strip_prefix("await")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let operand = rest.trim_start();
+    if operand.is_empty() || contains_await(operand) {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut chars = operand.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                c if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.checked_sub(1)?,
+            '?' if depth == 0 && chars.peek() == Some(&'.') => {}
+            c if depth == 0 && !(is_ident_char(c) || c == '.') => return None,
+            _ => {}
+        }
+    }
+    (depth == 0 && quote.is_none()).then_some(operand)
+}
+
+#[inline]
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
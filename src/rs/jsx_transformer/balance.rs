@@ -0,0 +1,149 @@
+//! Bracket balance of generated JavaScript, for `TransformOptions::verify_output`:
+//! a lightweight stand-in for parsing the output, catching code generation
+//! bugs (an unclosed template, a stray `)`) before a bundler reports them as
+//! a syntax error somewhere else.
+//!
+//! Strings, template literals (with their `${}` nesting), comments and regex
+//! literals are skipped. A `/` starts a regex where an operand is expected,
+//! after an operator, an opening bracket or a keyword such as `return`.
+
+/// The first imbalance of `code`: its byte offset and what is wrong there.
+pub(crate) fn imbalance(code: &str) -> Option<(usize, String)> {
+    // Open brackets, `$` standing for the `${` of a template
+    let mut open: Vec<(char, usize)> = Vec::new();
+    // Byte offsets of the template literals being read, innermost last
+    let mut templates: Vec<usize> = Vec::new();
+    let mut operand = false;
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        // Text of the innermost template, unless inside one of its `${}`
+        if templates.len() > open.iter().filter(|(b, _)| *b == '$').count() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '`' => {
+                    templates.pop();
+                    operand = true;
+                }
+                '$' if chars.peek().is_some_and(|&(_, n)| n == '{') => {
+                    chars.next();
+                    open.push(('$', i));
+                    operand = false;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                let mut closed = false;
+                while let Some((_, n)) = chars.next() {
+                    match n {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\n' => break,
+                        n if n == c => {
+                            closed = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    return Some((i, "unterminated string".to_string()));
+                }
+                operand = true;
+            }
+            '`' => templates.push(i),
+            '/' if chars.peek().is_some_and(|&(_, n)| n == '/') => {
+                while chars.next_if(|&(_, n)| n != '\n').is_some() {}
+            }
+            '/' if chars.peek().is_some_and(|&(_, n)| n == '*') => {
+                chars.next();
+                let rest = &code[i + 2..];
+                let Some(end) = rest.find("*/") else {
+                    return Some((i, "unterminated comment".to_string()));
+                };
+                while chars.next_if(|&(at, _)| at < i + 2 + end + 2).is_some() {}
+            }
+            '/' if !operand => {
+                // Regex literal, its character classes included
+                let mut class = false;
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => {
+                            chars.next();
+                        }
+                        Some((_, '[')) => class = true,
+                        Some((_, ']')) => class = false,
+                        Some((_, '/')) if !class => break,
+                        Some((_, '\n')) | None => {
+                            return Some((i, "unterminated regular expression".to_string()))
+                        }
+                        Some(_) => {}
+                    }
+                }
+                while chars.next_if(|&(_, n)| n.is_alphanumeric()).is_some() {}
+                operand = true;
+            }
+            '(' | '[' | '{' => {
+                open.push((c, i));
+                operand = false;
+            }
+            ')' | ']' | '}' => {
+                let expected = match open.pop() {
+                    Some(('(', _)) => ')',
+                    Some(('[', _)) => ']',
+                    Some(_) => '}',
+                    None => return Some((i, format!("unexpected `{c}`"))),
+                };
+                if c != expected {
+                    return Some((i, format!("expected `{expected}`, found `{c}`")));
+                }
+                operand = c != '}';
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let mut end = i + c.len_utf8();
+                while let Some((at, n)) =
+                    chars.next_if(|&(_, n)| n.is_alphanumeric() || n == '_' || n == '$')
+                {
+                    end = at + n.len_utf8();
+                }
+                operand = !matches!(
+                    &code[i..end],
+                    "return"
+                        | "typeof"
+                        | "instanceof"
+                        | "in"
+                        | "of"
+                        | "new"
+                        | "delete"
+                        | "void"
+                        | "throw"
+                        | "case"
+                        | "do"
+                        | "else"
+                        | "yield"
+                        | "await"
+                );
+            }
+            c if c.is_whitespace() => {}
+            _ => operand = false,
+        }
+    }
+
+    if let Some(&start) = templates.last() {
+        return Some((start, "unterminated template literal".to_string()));
+    }
+    open.last().map(|&(bracket, at)| {
+        let bracket = if bracket == '$' {
+            "${"
+        } else {
+            &code[at..at + 1]
+        };
+        (at, format!("unclosed `{bracket}`"))
+    })
+}
@@ -15,6 +15,11 @@ pub enum JSXError {
     ExtractionError(String),
     ParsingError(String),
     TransformError(String),
+    /// The progress callback asked to stop, after this many bytes.
+    Cancelled(usize),
+    /// A limit of the options was hit: `max_input_bytes`, `max_nodes` or
+    /// `timeout`.
+    LimitExceeded(String),
 }
 
 impl JSXError {
@@ -45,6 +50,8 @@ impl fmt::Display for JSXError {
             JSXError::ExtractionError(msg) => write!(f, "JSX extraction error: {msg}"),
             JSXError::ParsingError(msg) => write!(f, "JSX parsing error: {msg}"),
             JSXError::TransformError(msg) => write!(f, "JSX transform error: {msg}"),
+            JSXError::Cancelled(at) => write!(f, "JSX transform cancelled at byte {at}"),
+            JSXError::LimitExceeded(msg) => write!(f, "JSX transform limit exceeded: {msg}"),
         }
     }
 }
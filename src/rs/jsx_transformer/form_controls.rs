@@ -0,0 +1,97 @@
+//! Initial state of form controls from their React-style `value` and
+//! `defaultValue` props, which HTML ignores on `<textarea>` and `<select>`.
+//!
+//! - `<textarea value={text}/>` renders as `<textarea>${text}</textarea>`
+//! - `<select value="b">` marks its `<option value="b">` as `selected`; when
+//!   either value is an expression, each option compares them at runtime,
+//!   `<option value="b"${String((v)) === String("b") ? " selected" : ""}>`,
+//!   and `multiple` selects look the option up in the array value
+//! - `value` wins over `defaultValue`; both are removed from the control
+//!
+//! Options without a `value` attribute are not matched against their text.
+
+use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
+
+const VALUE: &str = "value";
+const DEFAULT_VALUE: &str = "defaultValue";
+
+/// Value of an enclosing `<select>`, applied to its options.
+pub(crate) struct SelectValue {
+    pub value: JSXAttributeValue,
+    pub multiple: bool,
+}
+
+/// Splits the initial value of a `<textarea>` or `<select>` off its
+/// attributes; None when it has neither prop.
+pub(crate) fn take_control_value(
+    attributes: &[JSXAttribute],
+) -> Option<(JSXAttributeValue, Vec<JSXAttribute>)> {
+    let value_of = |name: &str| {
+        attributes
+            .iter()
+            .find(|a| a.name == name)
+            .and_then(|a| a.value.clone())
+    };
+    let value = value_of(VALUE).or_else(|| value_of(DEFAULT_VALUE))?;
+    let rest = attributes
+        .iter()
+        .filter(|a| a.name != VALUE && a.name != DEFAULT_VALUE)
+        .cloned()
+        .collect();
+    Some((value, rest))
+}
+
+/// Attribute markup selecting an `<option>` of a `<select>`: ` selected`, an
+/// interpolation deciding it at runtime, or None when the option is not
+/// selected or has no `value`.
+pub(crate) fn selected_attribute(
+    select: &SelectValue,
+    attributes: &[JSXAttribute],
+) -> Option<String> {
+    if attributes.iter().any(|a| a.name == "selected") {
+        return None;
+    }
+    let option = attributes
+        .iter()
+        .find(|a| a.name == VALUE)
+        .and_then(|a| a.value.as_ref())?;
+    if let (Some(selected), Some(value)) = (static_value(&select.value), static_value(option)) {
+        return (selected == value).then(|| " selected".to_string());
+    }
+    let (selected, value) = (js_value(&select.value), js_value(option));
+    let test = if select.multiple {
+        format!("[].concat({selected}).map(String).includes(String({value}))")
+    } else {
+        format!("String({selected}) === String({value})")
+    };
+    Some(format!(r#"${{{test} ? " selected" : ""}}"#))
+}
+
+/// Text of a static value as HTML character data.
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;")
+}
+
+fn static_value(value: &JSXAttributeValue) -> Option<&str> {
+    match value {
+        JSXAttributeValue::DoubleQuote(s) | JSXAttributeValue::SingleQuote(s) => Some(s),
+        JSXAttributeValue::Expression(_) => None,
+    }
+}
+
+// JavaScript code of a value. JSX strings have no escapes, so backslashes and
+// line breaks are escaped for the JS literal.
+fn js_value(value: &JSXAttributeValue) -> String {
+    let literal = |s: &str, quote: char| {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        format!("{quote}{escaped}{quote}")
+    };
+    match value {
+        JSXAttributeValue::DoubleQuote(s) => literal(s, '"'),
+        JSXAttributeValue::SingleQuote(s) => literal(s, '\''),
+        JSXAttributeValue::Expression(expr) => format!("({expr})"),
+    }
+}
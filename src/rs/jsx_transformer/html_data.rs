@@ -0,0 +1,7 @@
+//! HTML elements and attributes of the living standard, generated by
+//! `build.rs` from `html_data.json`: void and obsolete elements, obsolete
+//! and boolean attributes, and the HTML names of JSX attribute names
+//! (`className` to `class`, `strokeWidth` to `stroke-width`). Update the
+//! dataset, not the generated code, when the standard changes.
+
+include!(concat!(env!("OUT_DIR"), "/html_data.rs"));
@@ -0,0 +1,158 @@
+//! Incremental re-transform for editors: after an edit, only the JSX nodes
+//! overlapping the changed range are parsed and transformed again; the
+//! output of the others is reused from the previous transform.
+//!
+//! The source is still scanned from the start, since an edit may open a
+//! string or a comment that changes where JSX starts. Reuse is disabled when
+//! the output of a node depends on its position in the file: with
+//! `hydration_markers` (ids count the markers before it) and
+//! `source_annotations` (line and column of each element), and when the
+//! source has a byte order mark or line endings to normalize.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use super::errors::JSXError;
+use super::options::TransformOptions;
+use super::output::TransformOutput;
+use super::pragma::apply_pragmas;
+use super::{find_next_jsx_start, normalize_source, transform_islands};
+
+/// Result of `jsx_transform_incremental` and `jsx_retransform`: the output,
+/// plus the transformed JSX nodes kept for the next `jsx_retransform`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IncrementalOutput {
+    pub output: TransformOutput,
+    /// Number of JSX nodes whose output was reused from the previous output.
+    pub reused: usize,
+    regions: Vec<Region>,
+    // Component helper and fragment component after pragmas: nodes are only
+    // reused when the pragmas of the file did not change.
+    pragmas: (String, Option<String>),
+}
+
+/// A transformed JSX node: its byte span in the source, its template and the
+/// data collected while transforming it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Region {
+    pub span: (usize, usize),
+    pub template: String,
+    pub output: TransformOutput,
+}
+
+/// Regions of a previous transform reusable at their (shifted) span, and the
+/// regions of the current one.
+#[derive(Debug, Default)]
+pub(crate) struct RegionCache {
+    reusable: Vec<Region>,
+    next: usize,
+    pub regions: Vec<Region>,
+    pub reused: usize,
+}
+
+impl RegionCache {
+    /// The reusable region starting at `start`, if any. Starts are queried
+    /// in increasing order.
+    pub fn take(&mut self, start: usize) -> Option<Region> {
+        while self
+            .reusable
+            .get(self.next)
+            .is_some_and(|r| r.span.0 < start)
+        {
+            self.next += 1;
+        }
+        let region = self.reusable.get(self.next).filter(|r| r.span.0 == start)?;
+        self.next += 1;
+        self.reused += 1;
+        Some(region.clone())
+    }
+}
+
+/// Same as `jsx_transform`, keeping what `jsx_retransform` needs to update the
+/// output after an edit.
+pub fn jsx_transform_incremental(
+    source: &str,
+    options: &TransformOptions,
+) -> Result<IncrementalOutput, JSXError> {
+    transform_with_cache(source, options, RegionCache::default())
+}
+
+/// Transforms `source`, the result of an edit of `previous_source` replacing
+/// the byte range `edit` of it, reusing the output of the JSX nodes of
+/// `previous` that the edit leaves untouched. `options` must be those of the
+/// previous transform. The result is the same as a full transform.
+pub fn jsx_retransform(
+    previous: &IncrementalOutput,
+    previous_source: &str,
+    source: &str,
+    edit: Range<usize>,
+    options: &TransformOptions,
+) -> Result<IncrementalOutput, JSXError> {
+    let mut cache = RegionCache::default();
+    if reusable(source, options) && pragmas(source, options) == previous.pragmas {
+        let inserted = (source.len() + edit.len()).saturating_sub(previous_source.len());
+        cache.reusable = previous
+            .regions
+            .iter()
+            .filter_map(|region| {
+                let (start, end) = region.span;
+                let span = if end <= edit.start {
+                    (start, end)
+                } else if start >= edit.end {
+                    (
+                        start - edit.end + edit.start + inserted,
+                        end - edit.end + edit.start + inserted,
+                    )
+                } else {
+                    return None;
+                };
+                // Checked rather than trusted, in case the range is off
+                (previous_source.get(start..end) == source.get(span.0..span.1)).then(|| Region {
+                    span,
+                    ..region.clone()
+                })
+            })
+            .collect();
+    }
+    transform_with_cache(source, options, cache)
+}
+
+fn transform_with_cache(
+    source: &str,
+    options: &TransformOptions,
+    mut cache: RegionCache,
+) -> Result<IncrementalOutput, JSXError> {
+    let output = if reusable(source, options) {
+        transform_islands(
+            source,
+            options,
+            &find_next_jsx_start,
+            None,
+            Some(&mut cache),
+        )?
+    } else {
+        transform_islands(source, options, &find_next_jsx_start, None, None)?
+    };
+    Ok(IncrementalOutput {
+        output,
+        reused: cache.reused,
+        regions: cache.regions,
+        pragmas: pragmas(source, options),
+    })
+}
+
+// Spans of reused nodes are those of the source as given: not reusable when
+// normalizing it moves them.
+fn reusable(source: &str, options: &TransformOptions) -> bool {
+    !options.hydration_markers
+        && options.source_annotations.is_none()
+        && matches!(normalize_source(source, options), Cow::Borrowed(s) if s.len() == source.len())
+}
+
+fn pragmas(source: &str, options: &TransformOptions) -> (String, Option<String>) {
+    let options = apply_pragmas(source, options);
+    (
+        options.component_helper.clone(),
+        options.fragment_component.clone(),
+    )
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::options::{
+    CspNonce, EventHandlerMode, ListMode, OutputTarget, QuoteStyle, SpreadOrder, TransformOptions,
+};
+
+/// `TransformOptions` as a plain JS object, e.g. the second argument of the
+/// WASM `jsx(input, options)` export. Fields are camelCased and all optional;
+/// the ones left out keep the defaults. Unknown fields are rejected.
+///
+/// ```js
+/// jsx(source, { minify: true, componentHelper: "h", filename: "app.jsx", sourceAnnotations: true });
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct JsTransformOptions {
+    pub list_methods: Option<Vec<String>>,
+    pub list_mode: Option<JsListMode>,
+    pub list_helper: Option<String>,
+    pub component_patterns: Option<Vec<String>>,
+    pub element_patterns: Option<Vec<String>>,
+    pub drop_attributes: Option<Vec<String>>,
+    pub keep_attributes: Option<Vec<String>>,
+    pub strip_props: Option<Vec<String>>,
+    pub collect_stripped_props: Option<bool>,
+    pub form_control_values: Option<bool>,
+    pub void_tags: Option<Vec<String>>,
+    pub close_custom_elements: Option<bool>,
+    pub normalize_attr_names: Option<bool>,
+    pub preserve_unknown_attr_case: Option<bool>,
+    pub preserve_data_aria_case: Option<bool>,
+    pub attr_name_map: Option<HashMap<String, String>>,
+    pub namespace_aware: Option<bool>,
+    pub event_handlers: Option<JsEventHandlerMode>,
+    pub action_helper: Option<String>,
+    pub quote_style: Option<JsQuoteStyle>,
+    pub spread_order: Option<JsSpreadOrder>,
+    pub hydration_markers: Option<bool>,
+    pub hydration_id_prefix: Option<String>,
+    /// Name of the transformed file, used by `sourceAnnotations`.
+    pub filename: Option<String>,
+    /// Annotate elements with `data-sxo-source` pointing into `filename`.
+    pub source_annotations: Option<bool>,
+    pub source_comments: Option<bool>,
+    pub fold_constants: Option<bool>,
+    pub coerce_literal_props: Option<bool>,
+    pub minify: Option<bool>,
+    pub pretty: Option<bool>,
+    pub component_helper: Option<String>,
+    pub fragment_component: Option<String>,
+    pub named_fragments: Option<bool>,
+    pub attribute_helper: Option<String>,
+    pub spread_helper: Option<String>,
+    pub spread_leading_space: Option<bool>,
+    pub expand_static_spreads: Option<bool>,
+    pub async_helper: Option<String>,
+    pub stream_methods: Option<Vec<String>>,
+    pub stream_functions: Option<Vec<String>>,
+    pub stream_helper: Option<String>,
+    pub output: Option<JsOutputTarget>,
+    pub scoped_css: Option<bool>,
+    pub merge_classes: Option<bool>,
+    /// Static nonce value added to inline scripts and styles.
+    pub csp_nonce: Option<String>,
+    /// JavaScript expression evaluated at render time for the nonce, e.g.
+    /// `"ctx.nonce"`. Takes precedence over `cspNonce`.
+    pub csp_nonce_expression: Option<String>,
+    pub script_hashes: Option<bool>,
+    pub raw_expression_marker: Option<String>,
+    /// Parse errors reported before giving up; 0 for no limit.
+    pub max_errors: Option<usize>,
+    /// Check the brackets of the generated code, see `TransformOptions`.
+    pub verify_output: Option<bool>,
+    /// Transform nodes while parsing them, see `TransformOptions`.
+    pub single_pass: Option<bool>,
+    /// Largest input in bytes; 0 for no limit.
+    pub max_input_bytes: Option<usize>,
+    pub max_nodes: Option<usize>,
+    /// Transform timeout in milliseconds, for native builds.
+    pub timeout_ms: Option<u64>,
+    pub strip_bom: Option<bool>,
+    pub normalize_line_endings: Option<bool>,
+}
+
+/// `ListMode` by name; the helper name is given by `listHelper`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsListMode {
+    Helper,
+    InlineJoin,
+    Disabled,
+}
+
+/// `EventHandlerMode` by name.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsEventHandlerMode {
+    Lowercase,
+    PreserveCamel,
+    Strip,
+    Hydrate,
+}
+
+/// `QuoteStyle` by name.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsQuoteStyle {
+    Double,
+    Single,
+    Preserve,
+}
+
+/// `SpreadOrder` by name.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsSpreadOrder {
+    Source,
+    ExplicitFirst,
+    SpreadFirst,
+}
+
+/// `OutputTarget` by name.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsOutputTarget {
+    Template,
+    Stream,
+}
+
+impl JsTransformOptions {
+    /// Applies the given fields over `base`.
+    pub fn apply(self, base: &TransformOptions) -> TransformOptions {
+        let mut options = base.clone();
+        set(&mut options.list_methods, self.list_methods);
+        match (self.list_mode, self.list_helper) {
+            (Some(JsListMode::InlineJoin), _) => options.list_mode = ListMode::InlineJoin,
+            (Some(JsListMode::Disabled), _) => options.list_mode = ListMode::Disabled,
+            (_, Some(helper)) => options.list_mode = ListMode::Helper(helper),
+            (Some(JsListMode::Helper), None) => options.list_mode = ListMode::default(),
+            (None, None) => {}
+        }
+        set(&mut options.component_patterns, self.component_patterns);
+        set(&mut options.element_patterns, self.element_patterns);
+        set(&mut options.drop_attributes, self.drop_attributes);
+        set(&mut options.keep_attributes, self.keep_attributes);
+        set(&mut options.strip_props, self.strip_props);
+        set(
+            &mut options.collect_stripped_props,
+            self.collect_stripped_props,
+        );
+        set(&mut options.form_control_values, self.form_control_values);
+        set(&mut options.void_tags, self.void_tags);
+        set(
+            &mut options.close_custom_elements,
+            self.close_custom_elements,
+        );
+        set(&mut options.normalize_attr_names, self.normalize_attr_names);
+        set(
+            &mut options.preserve_unknown_attr_case,
+            self.preserve_unknown_attr_case,
+        );
+        set(
+            &mut options.preserve_data_aria_case,
+            self.preserve_data_aria_case,
+        );
+        set(&mut options.attr_name_map, self.attr_name_map);
+        set(&mut options.namespace_aware, self.namespace_aware);
+        if let Some(mode) = self.event_handlers {
+            options.event_handlers = match mode {
+                JsEventHandlerMode::Lowercase => EventHandlerMode::Lowercase,
+                JsEventHandlerMode::PreserveCamel => EventHandlerMode::PreserveCamel,
+                JsEventHandlerMode::Strip => EventHandlerMode::Strip,
+                JsEventHandlerMode::Hydrate => EventHandlerMode::Hydrate,
+            };
+        }
+        set(&mut options.action_helper, self.action_helper);
+        if let Some(style) = self.quote_style {
+            options.quote_style = match style {
+                JsQuoteStyle::Double => QuoteStyle::Double,
+                JsQuoteStyle::Single => QuoteStyle::Single,
+                JsQuoteStyle::Preserve => QuoteStyle::Preserve,
+            };
+        }
+        if let Some(order) = self.spread_order {
+            options.spread_order = match order {
+                JsSpreadOrder::Source => SpreadOrder::Source,
+                JsSpreadOrder::ExplicitFirst => SpreadOrder::ExplicitFirst,
+                JsSpreadOrder::SpreadFirst => SpreadOrder::SpreadFirst,
+            };
+        }
+        set(&mut options.hydration_markers, self.hydration_markers);
+        set(&mut options.hydration_id_prefix, self.hydration_id_prefix);
+        match self.source_annotations {
+            Some(true) => {
+                options.source_annotations = self
+                    .filename
+                    .or(options.source_annotations)
+                    .or_else(|| Some("<anonymous>".to_string()));
+            }
+            Some(false) => options.source_annotations = None,
+            // A new file name for annotations already enabled
+            None if options.source_annotations.is_some() => {
+                if let Some(filename) = self.filename {
+                    options.source_annotations = Some(filename);
+                }
+            }
+            None => {}
+        }
+        set(&mut options.source_comments, self.source_comments);
+        set(&mut options.fold_constants, self.fold_constants);
+        set(&mut options.coerce_literal_props, self.coerce_literal_props);
+        set(&mut options.minify, self.minify);
+        set(&mut options.pretty, self.pretty);
+        set(&mut options.component_helper, self.component_helper);
+        if self.fragment_component.is_some() {
+            options.fragment_component = self.fragment_component;
+        }
+        set(&mut options.named_fragments, self.named_fragments);
+        if self.attribute_helper.is_some() {
+            options.attribute_helper = self.attribute_helper;
+        }
+        set(&mut options.async_helper, self.async_helper);
+        set(&mut options.spread_helper, self.spread_helper);
+        set(&mut options.spread_leading_space, self.spread_leading_space);
+        set(
+            &mut options.expand_static_spreads,
+            self.expand_static_spreads,
+        );
+        set(&mut options.stream_methods, self.stream_methods);
+        set(&mut options.stream_functions, self.stream_functions);
+        set(&mut options.stream_helper, self.stream_helper);
+        if let Some(output) = self.output {
+            options.output = match output {
+                JsOutputTarget::Template => OutputTarget::Template,
+                JsOutputTarget::Stream => OutputTarget::Stream,
+            };
+        }
+        set(&mut options.scoped_css, self.scoped_css);
+        set(&mut options.merge_classes, self.merge_classes);
+        if let Some(expr) = self.csp_nonce_expression {
+            options.csp_nonce = Some(CspNonce::Expression(expr));
+        } else if let Some(value) = self.csp_nonce {
+            options.csp_nonce = Some(CspNonce::Static(value));
+        }
+        set(&mut options.script_hashes, self.script_hashes);
+        set(
+            &mut options.raw_expression_marker,
+            self.raw_expression_marker,
+        );
+        set(&mut options.max_errors, self.max_errors);
+        set(&mut options.verify_output, self.verify_output);
+        set(&mut options.single_pass, self.single_pass);
+        set(&mut options.max_input_bytes, self.max_input_bytes);
+        set(&mut options.max_nodes, self.max_nodes);
+        if let Some(ms) = self.timeout_ms {
+            options.timeout = Some(Duration::from_millis(ms));
+        }
+        set(&mut options.strip_bom, self.strip_bom);
+        set(
+            &mut options.normalize_line_endings,
+            self.normalize_line_endings,
+        );
+        options
+    }
+}
+
+#[inline]
+fn set<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::errors::JSXError;
+use super::options::TransformOptions;
+use super::output::{HydrationManifest, TransformOutput};
+use super::source_map::source_map;
+use super::{jsx_transform, normalize_source, parse_errors, parse_jsx};
+use crate::jsx_parser::{position, utf16_offset, ExpressionPart, JSXAttributeValue, JSXNode};
+
+/// Result of the WASM `transform(input, options)` export, serialized to a
+/// plain JS object: `{ code, map, diagnostics, metadata }`. Failures are
+/// reported in `diagnostics`, with a null `code`, instead of being thrown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsTransformResult {
+    pub code: Option<String>,
+    /// Source map (version 3) of `code` as JSON. Its source is named after the
+    /// file of `sourceAnnotations`, `<anonymous>` otherwise. Null on failure
+    /// and when `TransformOptions::post_process` rewrote the code.
+    pub map: Option<String>,
+    pub diagnostics: Vec<JsDiagnostic>,
+    pub metadata: JsMetadata,
+}
+
+/// A problem that stopped the transform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDiagnostic {
+    /// Kind of problem: `parse-error`, `transform-error`, `extraction-error`,
+    /// `invalid-options`, `cancelled` or `limit-exceeded`.
+    pub code: &'static str,
+    pub message: String,
+    /// 1-based line and column (in characters), when the problem has a
+    /// location.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// 1-based column in UTF-16 code units, as counted by editors.
+    pub utf16_column: Option<usize>,
+    /// Start and end offsets in UTF-16 code units, as JS string indices.
+    pub span: Option<(usize, usize)>,
+}
+
+/// Error thrown by the WASM `jsx()` export, as a JS `Error` named `SxoError`
+/// carrying these fields. `code`, `line`, `column`, `utf16Column` and `span`
+/// are those of the first diagnostic; `message` is the full formatted error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsTransformError {
+    pub code: &'static str,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub utf16_column: Option<usize>,
+    pub span: Option<(usize, usize)>,
+    pub diagnostics: Vec<JsDiagnostic>,
+}
+
+impl JsTransformError {
+    /// The error for a failed transform of `source` with `options`.
+    pub fn new(source: &str, options: &TransformOptions, error: &JSXError) -> Self {
+        Self::from_diagnostics(error.to_string(), error_diagnostics(source, options, error))
+    }
+
+    pub fn from_diagnostics(message: String, diagnostics: Vec<JsDiagnostic>) -> Self {
+        let first = diagnostics.first();
+        Self {
+            code: first.map_or("transform-error", |d| d.code),
+            message,
+            line: first.and_then(|d| d.line),
+            column: first.and_then(|d| d.column),
+            utf16_column: first.and_then(|d| d.utf16_column),
+            span: first.and_then(|d| d.span),
+            diagnostics,
+        }
+    }
+}
+
+/// Data collected during the transform (see `TransformOutput`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsMetadata {
+    pub hydration: HydrationManifest,
+    pub components: BTreeMap<String, usize>,
+    pub web_components: BTreeMap<String, usize>,
+    pub styles: BTreeMap<String, String>,
+    pub stylesheet: String,
+    pub script_hashes: Vec<String>,
+    /// `TransformOptions::spread_order` the code was emitted with.
+    pub spread_order: &'static str,
+}
+
+impl From<TransformOutput> for JsMetadata {
+    fn from(output: TransformOutput) -> Self {
+        Self {
+            stylesheet: output.stylesheet(),
+            hydration: output.hydration,
+            components: output.usage.components,
+            web_components: output.usage.web_components,
+            styles: output.styles,
+            script_hashes: output.script_hashes,
+            spread_order: "",
+        }
+    }
+}
+
+impl JsTransformResult {
+    /// Transforms `source`, turning errors into diagnostics.
+    pub fn transform(source: &str, options: &TransformOptions) -> Self {
+        Self::from_result(source, options, jsx_transform(source, options))
+    }
+
+    /// The result of a transform of `source` with `options` already run.
+    pub fn from_result(
+        source: &str,
+        options: &TransformOptions,
+        result: Result<TransformOutput, JSXError>,
+    ) -> Self {
+        match result {
+            Ok(mut output) => Self {
+                map: options.post_process.is_none().then(|| {
+                    let file = options.source_annotations.as_deref();
+                    source_map(
+                        &normalize_source(source, options),
+                        &output.code,
+                        &output.islands,
+                        file.unwrap_or("<anonymous>"),
+                    )
+                }),
+                code: Some(std::mem::take(&mut output.code)),
+                metadata: JsMetadata {
+                    spread_order: options.spread_order.as_str(),
+                    ..output.into()
+                },
+                ..Self::default()
+            },
+            Err(e) => Self {
+                diagnostics: error_diagnostics(source, options, &e),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// A failed result with a single diagnostic without location.
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            diagnostics: vec![JsDiagnostic::new(code, message)],
+            ..Self::default()
+        }
+    }
+}
+
+impl JsDiagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            line: None,
+            column: None,
+            utf16_column: None,
+            span: None,
+        }
+    }
+
+    /// A diagnostic located at byte `offset` of `source`.
+    pub fn at(code: &'static str, message: impl Into<String>, source: &str, offset: usize) -> Self {
+        let location = position::position(source, offset);
+        let offset = utf16_offset(source, offset);
+        Self {
+            line: Some(location.line),
+            column: Some(location.column),
+            utf16_column: Some(location.utf16_column),
+            span: Some((offset, offset)),
+            ..Self::new(code, message)
+        }
+    }
+}
+
+/// Result of the `parse(input)` exports: the JSX nodes of the input, as
+/// plain JS objects tagged by `type`, or the parse errors in `diagnostics`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsParseResult {
+    pub nodes: Vec<JsRootNode>,
+    pub diagnostics: Vec<JsDiagnostic>,
+}
+
+/// A top-level JSX node with its start and end offsets in UTF-16 code units.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsRootNode {
+    pub span: (usize, usize),
+    #[serde(flatten)]
+    pub node: JsNode,
+}
+
+/// `JSXNode` as a JS object, e.g. `{ type: "text", value: "Hi" }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JsNode {
+    Element {
+        tag: String,
+        attributes: Vec<JsAttribute>,
+        children: Vec<JsNode>,
+    },
+    Fragment {
+        children: Vec<JsNode>,
+    },
+    Text {
+        value: String,
+    },
+    Expression {
+        code: String,
+    },
+    /// Expression embedding JSX; `parts` alternate code and JSX nodes.
+    EmbeddedExpression {
+        code: String,
+        parts: Vec<JsNode>,
+    },
+}
+
+/// An attribute; `expression` tells a `{…}` value from a quoted string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsAttribute {
+    pub name: String,
+    pub value: Option<String>,
+    pub expression: bool,
+}
+
+impl JsParseResult {
+    /// Parses the JSX nodes of `source` (see `parse_jsx`).
+    pub fn parse(source: &str) -> Self {
+        match parse_jsx(source) {
+            Ok(nodes) => Self {
+                nodes: nodes
+                    .into_iter()
+                    .map(|(node, (start, end))| JsRootNode {
+                        span: (utf16_offset(source, start), utf16_offset(source, end)),
+                        node: (&node).into(),
+                    })
+                    .collect(),
+                diagnostics: Vec::new(),
+            },
+            Err(errors) => Self {
+                nodes: Vec::new(),
+                diagnostics: errors
+                    .into_iter()
+                    .map(|e| JsDiagnostic::at("parse-error", e.message, source, e.position))
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl From<&JSXNode> for JsNode {
+    fn from(node: &JSXNode) -> Self {
+        let children = |children: &[JSXNode]| children.iter().map(JsNode::from).collect();
+        match node {
+            JSXNode::Element {
+                tag,
+                attributes,
+                children: nodes,
+            } => JsNode::Element {
+                tag: tag.clone(),
+                attributes: attributes
+                    .iter()
+                    .map(|a| JsAttribute {
+                        name: a.name.clone(),
+                        value: a.value.as_ref().map(|v| match v {
+                            JSXAttributeValue::DoubleQuote(v)
+                            | JSXAttributeValue::SingleQuote(v)
+                            | JSXAttributeValue::Expression(v) => v.clone(),
+                        }),
+                        expression: matches!(a.value, Some(JSXAttributeValue::Expression(_))),
+                    })
+                    .collect(),
+                children: children(nodes),
+            },
+            JSXNode::Fragment { children: nodes } => JsNode::Fragment {
+                children: children(nodes),
+            },
+            JSXNode::Text(value) => JsNode::Text {
+                value: value.clone(),
+            },
+            JSXNode::Expression(code) => JsNode::Expression { code: code.clone() },
+            JSXNode::EmbeddedExpression { raw, parts } => JsNode::EmbeddedExpression {
+                code: raw.clone(),
+                parts: parts
+                    .iter()
+                    .map(|part| match part {
+                        ExpressionPart::Code(code) => JsNode::Expression { code: code.clone() },
+                        ExpressionPart::Jsx { node, .. } => node.into(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+// Parse errors are reported one by one with their location; the formatted
+// message of the error is only used when none can be found.
+fn error_diagnostics(
+    source: &str,
+    options: &TransformOptions,
+    error: &JSXError,
+) -> Vec<JsDiagnostic> {
+    match error {
+        JSXError::ParsingError(message) => {
+            let diagnostics: Vec<JsDiagnostic> = parse_errors(source, options.max_errors)
+                .into_iter()
+                .map(|e| JsDiagnostic::at("parse-error", e.message, source, e.position))
+                .collect();
+            if diagnostics.is_empty() {
+                vec![JsDiagnostic::new("parse-error", message.as_str())]
+            } else {
+                diagnostics
+            }
+        }
+        JSXError::TransformError(message) => {
+            vec![JsDiagnostic::new("transform-error", message.as_str())]
+        }
+        JSXError::ExtractionError(message) => {
+            vec![JsDiagnostic::new("extraction-error", message.as_str())]
+        }
+        JSXError::Cancelled(_) => vec![JsDiagnostic::new("cancelled", error.to_string())],
+        JSXError::LimitExceeded(_) => {
+            vec![JsDiagnostic::new("limit-exceeded", error.to_string())]
+        }
+    }
+}
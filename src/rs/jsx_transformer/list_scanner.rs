@@ -0,0 +1,197 @@
+//! Incremental scanner over the JavaScript of a child expression that tracks
+//! which open call parentheses belong to list-producing methods.
+//!
+//! It is fed the verbatim code chunks of an expression in order; between chunks
+//! the caller can ask whether the current position (where an embedded JSX
+//! subtree sits) is inside the arguments of a list call such as `.map(...)`.
+//! Strings and template literals are skipped, and calls opened inside a
+//! template `${...}` never count, since their result is string-interpolated.
+//! Top-level calls producing iterables to stream (`items.values()` or flagged
+//! generator functions) are recorded as well.
+
+use super::options::TransformOptions;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Code,
+    Template,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Group {
+    // Call or grouping parentheses; `true` when opened by a list method call
+    Paren(bool),
+    Brace,
+    // `${ ... }` inside a template literal
+    TemplateExpr,
+}
+
+pub(crate) struct ListScanner<'a> {
+    options: &'a TransformOptions,
+    modes: Vec<Mode>,
+    groups: Vec<Group>,
+    quote: Option<char>,
+    escape: bool,
+    saw_stream_call: bool,
+    ends_with_list_call: bool,
+    // The last top-level list call is followed by a member access or call,
+    // e.g. `.length` or `.join(", ")`: its array is not the value
+    list_consumed: bool,
+}
+
+impl<'a> ListScanner<'a> {
+    pub(crate) fn new(options: &'a TransformOptions) -> Self {
+        Self {
+            options,
+            modes: vec![Mode::Code],
+            groups: Vec::new(),
+            quote: None,
+            escape: false,
+            saw_stream_call: false,
+            ends_with_list_call: false,
+            list_consumed: false,
+        }
+    }
+
+    /// Feeds the next chunk of JavaScript source.
+    pub(crate) fn feed(&mut self, code: &str) {
+        let mut iter = code.char_indices().peekable();
+
+        while let Some((idx, ch)) = iter.next() {
+            if !ch.is_whitespace() {
+                if self.ends_with_list_call
+                    && (matches!(ch, '.' | '[' | '(')
+                        || (ch == '?' && matches!(iter.peek(), Some((_, '.')))))
+                {
+                    self.list_consumed = true;
+                }
+                self.ends_with_list_call = false;
+            }
+            if self.escape {
+                self.escape = false;
+                continue;
+            }
+
+            if let Some(q) = self.quote {
+                match ch {
+                    '\\' => self.escape = true,
+                    c if c == q => self.quote = None,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match self.modes.last().copied().unwrap_or(Mode::Code) {
+                Mode::Template => match ch {
+                    '\\' => self.escape = true,
+                    '`' => {
+                        self.modes.pop();
+                    }
+                    '$' if matches!(iter.peek(), Some((_, '{'))) => {
+                        iter.next();
+                        self.groups.push(Group::TemplateExpr);
+                        self.modes.push(Mode::Code);
+                    }
+                    _ => {}
+                },
+                Mode::Code => match ch {
+                    '\'' | '"' => self.quote = Some(ch),
+                    '`' => self.modes.push(Mode::Template),
+                    '(' => {
+                        let top_level = self.modes.len() == 1;
+                        let before = &code[..idx];
+                        let is_list =
+                            top_level && is_method_callee(before, &self.options.list_methods);
+                        if is_list && self.groups.is_empty() {
+                            self.list_consumed = false;
+                        }
+                        self.saw_stream_call |= top_level && is_stream_callee(before, self.options);
+                        self.groups.push(Group::Paren(is_list));
+                    }
+                    ')' => {
+                        if let Some(Group::Paren(is_list)) = self.groups.last().copied() {
+                            self.groups.pop();
+                            self.ends_with_list_call = is_list && self.groups.is_empty();
+                        }
+                    }
+                    '{' => self.groups.push(Group::Brace),
+                    '}' => {
+                        if let Some(Group::TemplateExpr) = self.groups.pop() {
+                            self.modes.pop();
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    /// True when the current position is inside the arguments of a list call.
+    pub(crate) fn in_list_call(&self) -> bool {
+        self.groups.iter().any(|g| matches!(g, Group::Paren(true)))
+    }
+
+    /// True when any top-level call producing an iterable to stream was seen.
+    pub(crate) fn saw_stream_call(&self) -> bool {
+        self.saw_stream_call
+    }
+
+    /// True when the result of the last top-level list call is accessed or
+    /// called, `items.filter(f).length`, so that the value is not its array.
+    pub(crate) fn list_consumed(&self) -> bool {
+        self.list_consumed
+    }
+
+    /// True when the source so far ends with the closing paren of a top-level list call.
+    pub(crate) fn ends_with_list_call(&self) -> bool {
+        self.ends_with_list_call
+    }
+
+    /// Records an embedded JSX subtree at the current position.
+    pub(crate) fn skip_jsx(&mut self) {
+        self.ends_with_list_call = false;
+    }
+}
+
+// True when `before` ends with `.name` or `.name?.` where `name` is one of `methods`.
+fn is_method_callee(before: &str, methods: &[String]) -> bool {
+    match callee(before) {
+        Some((name, Some(_))) => methods.iter().any(|m| m == name),
+        _ => false,
+    }
+}
+
+// True for `.values()`-like iterator methods (except on `Object`/`Reflect`,
+// whose static methods return arrays) and calls of flagged generator functions.
+fn is_stream_callee(before: &str, options: &TransformOptions) -> bool {
+    match callee(before) {
+        Some((name, Some(receiver))) => {
+            !(receiver.ends_with("Object") || receiver.ends_with("Reflect"))
+                && options.stream_methods.iter().any(|m| m == name)
+        }
+        Some((name, None)) => options.stream_functions.iter().any(|f| f == name),
+        None => false,
+    }
+}
+
+// Callee name ending `before` (the text preceding a `(`, optionally ending in
+// `?.`), with the receiver source when it is a member access (`receiver.name`).
+fn callee(before: &str) -> Option<(&str, Option<&str>)> {
+    let s = before.trim_end();
+    let s = s.strip_suffix("?.").unwrap_or(s);
+    let name_start = s
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    let name = &s[name_start..];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let receiver = s[..name_start].trim_end().strip_suffix('.').map(|r| {
+        let r = r.strip_suffix('?').unwrap_or(r);
+        r.trim_end()
+    });
+    Some((name, receiver))
+}
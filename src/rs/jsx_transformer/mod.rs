@@ -1,59 +1,425 @@
+mod awaits;
+mod balance;
 mod errors;
-mod jsx_scanner;
-#[cfg(test)]
-mod jsx_scanner_tests;
+mod form_controls;
+mod html_data;
+mod incremental;
+mod integrity;
+mod js_options;
+mod js_result;
+mod list_scanner;
+mod options;
+mod output;
+mod pragma;
+mod prose_scanner;
+mod scoped_css;
+mod source_map;
 
 pub mod tags_attrs;
 mod transform;
+mod transformer;
 
 pub use errors::{JSXError, JSXErrorKind};
+pub use incremental::{jsx_retransform, jsx_transform_incremental, IncrementalOutput};
+pub use js_options::{
+    JsEventHandlerMode, JsListMode, JsOutputTarget, JsQuoteStyle, JsSpreadOrder, JsTransformOptions,
+};
+pub use js_result::{
+    JsAttribute, JsDiagnostic, JsMetadata, JsNode, JsParseResult, JsRootNode, JsTransformError,
+    JsTransformResult,
+};
+pub use options::{
+    CspNonce, EventHandlerMode, ExpressionSite, JsxStartContext, JsxStartFilter, ListMode,
+    OutputTarget, PostProcess, QuoteStyle, RewriteExpression, RewriteUrl, SpreadOrder,
+    TransformOptions, DEFAULT_ACTION_HELPER, DEFAULT_ASYNC_HELPER, DEFAULT_ATTRIBUTE_HELPER,
+    DEFAULT_COMPONENT_HELPER, DEFAULT_FRAGMENT_COMPONENT, DEFAULT_HYDRATION_ID_PREFIX,
+    DEFAULT_LIST_HELPER, DEFAULT_LIST_METHODS, DEFAULT_MAX_ERRORS, DEFAULT_RAW_EXPRESSION_MARKER,
+    DEFAULT_SPREAD_HELPER, DEFAULT_STREAM_HELPER, DEFAULT_STREAM_METHODS,
+};
+pub use output::{ComponentUsage, HydrationManifest, Island, StrippedProp, TransformOutput};
+pub use transformer::Transformer;
 
-use crate::jsx_parser::Parser;
-use jsx_scanner::find_next_jsx_start;
-use transform::transform_to_template;
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::jsx_parser::jsx_scanner::{find_next_jsx_operand, find_next_jsx_start};
+use crate::jsx_parser::{recovery_offset, JSXNode, LineIndex, ParseError, Parser, SpannedNode};
+use incremental::{Region, RegionCache};
+use pragma::apply_pragmas;
+use prose_scanner::find_next_island;
+use transform::{parse_to_template, render_static_html, transform_to_template, ParsedTemplate};
 
 // Common constants used across the transformer.
-const EMPTY_STRING: &str = "";
+const BOM: char = '\u{FEFF}';
 
 // Transforms JSX found in the provided source string into
 // a template-literal-based output using the runtime helpers.
 // - Streams through the input to find `<` and invokes the parser from that point
 // - Aggregates parser diagnostics (pretty-formatted) instead of failing fast
-// - Performs a final cleanup to remove empty interpolations produced by edge cases
+// - Renders nothing for empty expressions and JSX comments, `{}` and `{/* ... */}`
 pub fn jsx_transformer(source: &str) -> Result<String, JSXError> {
+    jsx_transformer_with_options(source, &TransformOptions::default())
+}
+
+// Same as `jsx_transformer`, with explicit transform options.
+pub fn jsx_transformer_with_options(
+    source: &str,
+    options: &TransformOptions,
+) -> Result<String, JSXError> {
+    jsx_transform(source, options).map(|output| output.code)
+}
+
+// Same as `jsx_transformer_with_options`, also returning the data collected
+// during the transform: the hydration manifest, component usage, scoped
+// styles and inline script hashes.
+pub fn jsx_transform(
+    source: &str,
+    options: &TransformOptions,
+) -> Result<TransformOutput, JSXError> {
+    transform_islands(source, options, &find_next_jsx_start, None, None)
+}
+
+// Same as `jsx_transformer`, only transforming the JSX starting in the given
+// byte ranges of `source`, e.g. the body of a `render()` method found by a JS
+// parser upstream; `<` anywhere else is left alone.
+// - Each range is scanned on its own, as code: a range starting inside a
+//   string or comment does not know it
+// - A node starting in a range is transformed whole, even past its end
+pub fn jsx_transformer_in_ranges(
+    source: &str,
+    ranges: &[Range<usize>],
+) -> Result<String, JSXError> {
+    jsx_transform_in_ranges(source, &TransformOptions::default(), ranges).map(|output| output.code)
+}
+
+// Same as `jsx_transformer_in_ranges`, with explicit transform options and
+// the data collected during the transform. Ranges are offsets of `source`
+// as given, before `strip_bom` and `normalize_line_endings` apply.
+pub fn jsx_transform_in_ranges(
+    source: &str,
+    options: &TransformOptions,
+    ranges: &[Range<usize>],
+) -> Result<TransformOutput, JSXError> {
+    let mut ranges: Vec<Range<usize>> = ranges
+        .iter()
+        .map(|range| {
+            normalized_offset(source, options, range.start)
+                ..normalized_offset(source, options, range.end)
+        })
+        .filter(|range| !range.is_empty())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    let find_start = |src: &str, from: usize| {
+        ranges
+            .iter()
+            .filter(|range| range.end > from)
+            .find_map(|range| {
+                let end = char_boundary(src, range.end, false);
+                let start = char_boundary(src, from.max(range.start), true);
+                (start < end)
+                    .then(|| find_next_jsx_start(&src[..end], start))
+                    .flatten()
+            })
+    };
+    transform_islands(source, options, &find_start, None, None)
+}
+
+// Same as `jsx_transform` for the code of an expression, e.g. a prop value,
+// where a `<` after an operand compares rather than starting JSX.
+pub(crate) fn jsx_transform_expression(
+    source: &str,
+    options: &TransformOptions,
+) -> Result<TransformOutput, JSXError> {
+    let find_start = |src: &str, from| find_next_jsx_operand(src, from).map(|start| start.at);
+    if options.post_process.is_some() {
+        // Post-processing is for the whole file
+        let options = TransformOptions {
+            post_process: None,
+            ..options.clone()
+        };
+        return transform_islands(source, &options, &find_start, None, None);
+    }
+    transform_islands(source, options, &find_start, None, None)
+}
+
+// Compiles an already parsed JSX node to its JavaScript expression, e.g. the
+// nodes of `parse_jsx`, so tools that parse once need not parse again.
+// - The result is what `jsx_transform` emits in place of the node
+// - Source annotations are skipped: the node carries no positions
+pub fn transform_node(node: &JSXNode, options: &TransformOptions) -> Result<String, JSXError> {
+    transform_to_template(node, options, &[], &mut TransformOutput::default())
+}
+
+// Default number of bytes between two calls of a progress callback.
+pub const DEFAULT_PROGRESS_INTERVAL: usize = 64 * 1024;
+
+// Same as `jsx_transform`, calling `progress(processed, total)` each time
+// another `interval` bytes of the input are processed, and once at the end.
+// Returning false stops the transform with `JSXError::Cancelled`.
+// - Progress is checked between top-level JSX nodes: a single node is never
+//   interrupted
+// - `processed` and `total` count bytes of the UTF-8 input
+pub fn jsx_transform_with_progress(
+    source: &str,
+    options: &TransformOptions,
+    interval: usize,
+    progress: &mut dyn FnMut(usize, usize) -> bool,
+) -> Result<TransformOutput, JSXError> {
+    let mut progress = Progress {
+        interval: interval.max(1),
+        next: interval.max(1),
+        callback: progress,
+    };
+    transform_islands(
+        source,
+        options,
+        &find_next_jsx_start,
+        Some(&mut progress),
+        None,
+    )
+}
+
+// Progress callback and the offset of its next call.
+struct Progress<'a> {
+    interval: usize,
+    next: usize,
+    callback: &'a mut dyn FnMut(usize, usize) -> bool,
+}
+
+impl Progress<'_> {
+    // Calls back once `processed` reaches the next report offset, or
+    // unconditionally when `done`.
+    fn report(&mut self, processed: usize, total: usize, done: bool) -> Result<(), JSXError> {
+        if !done && processed < self.next {
+            return Ok(());
+        }
+        self.next = processed.saturating_add(self.interval);
+        if (self.callback)(processed, total) {
+            Ok(())
+        } else {
+            Err(JSXError::Cancelled(processed))
+        }
+    }
+}
+
+// Running checks of `TransformOptions::max_nodes` and `timeout`.
+struct Limits {
+    max_nodes: usize,
+    nodes: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    deadline: Option<(std::time::Instant, std::time::Duration)>,
+}
+
+impl Limits {
+    // Fails right away for an input over `max_input_bytes`.
+    fn new(source: &str, options: &TransformOptions) -> Result<Self, JSXError> {
+        if options.max_input_bytes > 0 && source.len() > options.max_input_bytes {
+            return Err(JSXError::LimitExceeded(format!(
+                "the input is {} bytes, over max_input_bytes ({})",
+                source.len(),
+                options.max_input_bytes
+            )));
+        }
+        Ok(Self {
+            max_nodes: options.max_nodes,
+            nodes: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            deadline: options
+                .timeout
+                .map(|timeout| (std::time::Instant::now() + timeout, timeout)),
+        })
+    }
+
+    // Counts the `nodes` of the top-level node parsed at byte `at`.
+    fn check(&mut self, nodes: usize, at: usize) -> Result<(), JSXError> {
+        self.nodes += nodes;
+        if self.max_nodes > 0 && self.nodes > self.max_nodes {
+            return Err(JSXError::LimitExceeded(format!(
+                "more than max_nodes ({}) JSX nodes at byte {at}",
+                self.max_nodes
+            )));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((deadline, timeout)) = self.deadline {
+            if std::time::Instant::now() >= deadline {
+                return Err(JSXError::LimitExceeded(format!(
+                    "timeout of {} ms reached at byte {at}",
+                    timeout.as_millis()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Same as `jsx_transform`, treating the input as prose (Markdown-ish text)
+// with embedded JSX islands instead of JavaScript.
+// - Text outside the islands is left untouched, apostrophes and all
+// - Code spans, fenced code blocks, HTML comments and autolinks are skipped
+pub fn jsx_transform_prose(
+    source: &str,
+    options: &TransformOptions,
+) -> Result<TransformOutput, JSXError> {
+    transform_islands(source, options, &find_next_island, None, None)
+}
+
+// Transforms every JSX node starting at the offsets `find_start` yields,
+// copying the text between them as is. With a `cache`, nodes are reused from
+// it when possible and recorded in it.
+fn transform_islands(
+    source: &str,
+    options: &TransformOptions,
+    find_start: &dyn Fn(&str, usize) -> Option<usize>,
+    mut progress: Option<&mut Progress>,
+    mut cache: Option<&mut RegionCache>,
+) -> Result<TransformOutput, JSXError> {
+    let mut limits = Limits::new(source, options)?;
+    let source = &*normalize_source(source, options);
+    let options = &*apply_pragmas(source, options);
     let input = source;
+    let mut output = TransformOutput::default();
     let mut out = String::with_capacity(input.len() + 32);
     let mut cursor = 0;
     let mut i: usize = 0;
     let mut errors: Vec<String> = Vec::new();
 
-    // Streaming scan + error accumulation: on parse error advance one byte and continue
+    // Streaming scan + error accumulation: on parse error resync and continue
+    // with the next top-level JSX
     while i < input.len() {
-        if let Some(next) = find_next_jsx_start(input, i) {
+        if let Some(progress) = progress.as_deref_mut() {
+            progress.report(i, input.len(), false)?;
+        }
+        if let Some(next) = find_start(input, i) {
             i = next;
         } else {
             break;
         }
+        if let Some(accept) = options.jsx_start_filter {
+            if !accept(JsxStartContext::new(input, i)) {
+                i += 1;
+                continue;
+            }
+        }
+
+        if let Some(region) = cache.as_deref_mut().and_then(|cache| cache.take(i)) {
+            out.push_str(&input[cursor..i]);
+            let code_start = out.len();
+            out.push_str(&region.template);
+            output.islands.push(Island {
+                code: (code_start, out.len()),
+                source: region.span,
+            });
+            output.merge(region.output.clone());
+            cursor = region.span.1;
+            i = region.span.1;
+            if let Some(cache) = cache.as_deref_mut() {
+                cache.regions.push(region);
+            }
+            continue;
+        }
+
+        // Single pass, unless annotations need the element positions up
+        // front, regions are cached, or hydration ids count the markers of
+        // the whole output, which the output of a node does not see
+        if options.single_pass
+            && options.source_annotations.is_none()
+            && !options.hydration_markers
+            && cache.is_none()
+        {
+            let mut node_output = TransformOutput::default();
+            match parse_to_template(&input[i..], options, &mut node_output)? {
+                Some(Ok(ParsedTemplate::Code {
+                    code,
+                    span: (start, end),
+                    nodes,
+                })) => {
+                    let start_abs = i + start;
+                    let end_abs = i + end;
+                    limits.check(nodes, start_abs)?;
+                    verify_output(input, start_abs, &code, options)?;
+                    out.push_str(&input[cursor..start_abs]);
+                    let code_start = out.len();
+                    if options.source_comments {
+                        out.push_str(&source_comment(&input[start_abs..end_abs]));
+                    }
+                    out.push_str(&code);
+                    output.merge(node_output);
+                    output.islands.push(Island {
+                        code: (code_start, out.len()),
+                        source: (start_abs, end_abs),
+                    });
+                    cursor = end_abs;
+                    i = end_abs;
+                    reserve_rest(&mut out, input.len(), end_abs);
+                    continue;
+                }
+                // Transformed from its tree below
+                Some(Ok(ParsedTemplate::Async)) => {}
+                Some(Err(e)) => {
+                    if !push_parse_error(&mut errors, input, i + e.position, &e.message, options) {
+                        break;
+                    }
+                    i = recovery_offset(input, i);
+                    continue;
+                }
+                None => break,
+            }
+        }
 
         let mut p = Parser::new(&input[i..]);
         match p.parse_next_with_span() {
             Some(Ok((ast, (start, end)))) => {
                 let start_abs = i + start;
                 let end_abs = i + end;
+                limits.check(p.node_spans().len(), start_abs)?;
                 if start_abs > cursor {
                     out.push_str(&input[cursor..start_abs]);
                 }
-                let template = transform_to_template(&ast)?;
-                out.push('`');
-                out.push_str(&template);
-                out.push('`');
+                let locations = match &options.source_annotations {
+                    Some(_) => source_locations(input, i, p.element_starts()),
+                    None => Vec::new(),
+                };
+                let code_start = out.len();
+                let mut template = if options.source_comments {
+                    source_comment(&input[start_abs..end_abs])
+                } else {
+                    String::new()
+                };
+                match cache.as_deref_mut() {
+                    Some(cache) => {
+                        let mut region_output = TransformOutput::default();
+                        let code =
+                            transform_to_template(&ast, options, &locations, &mut region_output)?;
+                        verify_output(input, start_abs, &code, options)?;
+                        template.push_str(&code);
+                        out.push_str(&template);
+                        output.merge(region_output.clone());
+                        cache.regions.push(Region {
+                            span: (start_abs, end_abs),
+                            template,
+                            output: region_output,
+                        });
+                    }
+                    None => {
+                        let code = transform_to_template(&ast, options, &locations, &mut output)?;
+                        verify_output(input, start_abs, &code, options)?;
+                        out.push_str(&template);
+                        out.push_str(&code);
+                    }
+                }
+                output.islands.push(Island {
+                    code: (code_start, out.len()),
+                    source: (start_abs, end_abs),
+                });
                 cursor = end_abs;
                 i = end_abs;
+                reserve_rest(&mut out, input.len(), end_abs);
             }
             Some(Err(e)) => {
-                let pos_abs = i + e.position;
-                errors.push(format_diagnostic(input, pos_abs, &e.message));
-                i += 1; // recovery: advance and continue scanning
+                if !push_parse_error(&mut errors, input, i + e.position, &e.message, options) {
+                    break;
+                }
+                i = recovery_offset(input, i);
             }
             None => break,
         }
@@ -62,6 +428,9 @@ pub fn jsx_transformer(source: &str) -> Result<String, JSXError> {
     if cursor < input.len() {
         out.push_str(&input[cursor..]);
     }
+    if let Some(progress) = progress {
+        progress.report(input.len(), input.len(), true)?;
+    }
 
     if !errors.is_empty() {
         return Err(JSXError::with_kind(JSXErrorKind::ParsingError(
@@ -69,11 +438,216 @@ pub fn jsx_transformer(source: &str) -> Result<String, JSXError> {
         )));
     }
 
-    // Remove empty interpolation artifacts
-    if out.contains("${}") {
-        out = out.replace("${}", EMPTY_STRING);
+    if let Some(post_process) = options.post_process {
+        out = post_process(&out);
+        output.islands.clear();
+    }
+    output.code = out;
+    Ok(output)
+}
+
+// Reserves room in `out`, the output of the first `consumed` bytes of the
+// input, for the rest of it at the growth seen so far, so that
+// component-heavy files are not copied over and over. Saturating and capped,
+// as the product of two input sizes overflows a 32-bit `usize` (wasm32).
+fn reserve_rest(out: &mut String, total: usize, consumed: usize) {
+    const MAX_GROWTH: usize = 4;
+    let rest = total - consumed;
+    let growth = rest.saturating_mul(out.len().saturating_sub(consumed)) / consumed.max(1);
+    out.reserve(rest.saturating_add(growth.min(rest.saturating_mul(MAX_GROWTH))));
+}
+
+// JSX nodes of a JavaScript source, found and parsed as the transform does,
+// with their byte spans in `source`. Fails with every parse error, using the
+// same recovery as the transform (see `recovery_offset`).
+pub fn parse_jsx(source: &str) -> Result<Vec<SpannedNode>, Vec<ParseError>> {
+    parse_jsx_with_max_errors(source, 0)
+}
+
+// `parse_jsx` stopping at `max_errors` errors (0 for no limit), followed by
+// the summary the transform reports.
+fn parse_jsx_with_max_errors(
+    source: &str,
+    max_errors: usize,
+) -> Result<Vec<SpannedNode>, Vec<ParseError>> {
+    let mut nodes = Vec::new();
+    let mut errors = Vec::new();
+    let mut i = 0;
+    while let Some(next) = find_next_jsx_start(source, i) {
+        let mut p = Parser::new(&source[next..]);
+        match p.parse_next_with_span() {
+            Some(Ok((node, (start, end)))) => {
+                nodes.push((node, (next + start, next + end)));
+                i = next + end;
+            }
+            Some(Err(e)) => {
+                errors.push(ParseError::new(next + e.position, e.message));
+                if errors.len() == max_errors {
+                    errors.push(ParseError::new(
+                        next + e.position,
+                        too_many_errors(max_errors),
+                    ));
+                    break;
+                }
+                i = recovery_offset(source, next);
+            }
+            None => break,
+        }
+    }
+    if errors.is_empty() {
+        Ok(nodes)
+    } else {
+        Err(errors)
+    }
+}
+
+// Parser errors of the JSX nodes the transform visits, in source order and
+// with positions relative to `source`.
+pub(crate) fn parse_errors(source: &str, max_errors: usize) -> Vec<ParseError> {
+    parse_jsx_with_max_errors(source, max_errors)
+        .err()
+        .unwrap_or_default()
+}
+
+// Adds the diagnostic of a parse error at byte `at` of `input`, and the
+// summary once `max_errors` is reached: false then, to stop.
+fn push_parse_error(
+    errors: &mut Vec<String>,
+    input: &str,
+    at: usize,
+    message: &str,
+    options: &TransformOptions,
+) -> bool {
+    errors.push(format_diagnostic(input, at, message));
+    if errors.len() == options.max_errors {
+        errors.push(too_many_errors(options.max_errors));
+        return false;
+    }
+    true
+}
+
+fn too_many_errors(max_errors: usize) -> String {
+    format!("too many errors, stopped after {max_errors}; raise `max_errors` to report more")
+}
+
+// Renders JSX containing no expressions or components to a plain HTML
+// string, with no template literal or runtime helpers involved.
+// - The source holds only JSX roots, separated by whitespace
+// - Literal expressions that fold to text, e.g. `{"&copy;"}`, are allowed
+pub fn render_static(source: &str) -> Result<String, JSXError> {
+    let options = TransformOptions {
+        fold_constants: true,
+        ..TransformOptions::default()
+    };
+    let mut html = String::new();
+    let mut cursor = 0;
+    let mut p = Parser::new(source);
+    while let Some(result) = p.parse_next_with_span() {
+        let (ast, (start, end)) = result.map_err(|e| {
+            JSXError::with_kind(JSXErrorKind::ParsingError(format_diagnostic(
+                source, e.position, &e.message,
+            )))
+        })?;
+        if !source[cursor..start].trim().is_empty() {
+            return Err(JSXError::with_kind(JSXErrorKind::UnsupportedSyntax(
+                format!("`{}` is not JSX", source[cursor..start].trim()),
+            )));
+        }
+        html.push_str(&render_static_html(&ast, &options)?);
+        cursor = end;
+    }
+    if !source[cursor..].trim().is_empty() {
+        return Err(JSXError::with_kind(JSXErrorKind::UnsupportedSyntax(
+            format!("`{}` is not JSX", source[cursor..].trim()),
+        )));
+    }
+    Ok(html)
+}
+
+// The source as transformed: without its byte order mark and with LF line
+// endings, as the options ask.
+pub(crate) fn normalize_source<'a>(source: &'a str, options: &TransformOptions) -> Cow<'a, str> {
+    let source = match source.strip_prefix(BOM) {
+        Some(rest) if options.strip_bom => rest,
+        _ => source,
+    };
+    if options.normalize_line_endings && source.contains("\r\n") {
+        Cow::Owned(source.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(source)
+    }
+}
+
+// With `verify_output`, fails when the code generated for the JSX at byte
+// `start` of `source` has unbalanced brackets, pointing at that JSX.
+fn verify_output(
+    source: &str,
+    start: usize,
+    code: &str,
+    options: &TransformOptions,
+) -> Result<(), JSXError> {
+    if !options.verify_output {
+        return Ok(());
+    }
+    match balance::imbalance(code) {
+        Some((at, problem)) => Err(JSXError::TransformError(format_diagnostic(
+            source,
+            start,
+            &format!("generated code is not balanced: {problem} at byte {at} of `{code}`"),
+        ))),
+        None => Ok(()),
     }
-    Ok(out)
+}
+
+// Offset in the normalized source (see `normalize_source`) of byte `offset`
+// of `source`.
+fn normalized_offset(source: &str, options: &TransformOptions, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    let bom = if options.strip_bom && source.starts_with(BOM) {
+        BOM.len_utf8().min(offset)
+    } else {
+        0
+    };
+    let crlf = if options.normalize_line_endings {
+        let before = &source.as_bytes()[..offset];
+        before.windows(2).filter(|pair| pair == b"\r\n").count()
+    } else {
+        0
+    };
+    offset - bom - crlf
+}
+
+// `offset` moved to a character boundary of `source`, forward or backward.
+fn char_boundary(source: &str, offset: usize, forward: bool) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        if forward {
+            offset += 1;
+        } else {
+            offset -= 1;
+        }
+    }
+    offset
+}
+
+// `/* sxo: <jsx> */ ` for `source_comments`, `*/` being escaped so that the
+// comment cannot end early.
+fn source_comment(jsx: &str) -> String {
+    format!("/* sxo: {} */ ", jsx.replace("*/", "*\\/"))
+}
+
+// 1-based (line, column) of element starts given relative to `offset`.
+// Columns count characters.
+fn source_locations(source: &str, offset: usize, starts: &[usize]) -> Vec<(usize, usize)> {
+    starts
+        .iter()
+        .map(|&start| {
+            let before = &source[..offset + start];
+            let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line = before.matches('\n').count() + 1;
+            (line, before[line_start..].chars().count() + 1)
+        })
+        .collect()
 }
 
 // Pretty diagnostic formatter for parser errors with line/column and caret.
@@ -82,24 +656,20 @@ pub(crate) fn format_diagnostic(source: &str, pos: usize, message: &str) -> Stri
     let len = source.len();
     let pos = if pos > len { len } else { pos };
 
-    // Determine the line start and end around the error position
-    let before = &source[..pos];
-    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
-
-    let after = &source[pos..];
-    let line_end_rel = after.find('\n').unwrap_or(after.len());
-    let line_end = pos + line_end_rel;
-
-    let line_str = &source[line_start..line_end];
+    // The line around the error position, without the `\r` of a CRLF line
+    // ending, which would move the cursor
+    let index = LineIndex::new(source);
+    let line_no = index.position(pos).line;
+    let line = index.line_span(line_no).unwrap_or_default();
+    let line_str = &source[line.start..line.end];
 
     // Expand tabs for consistent caret alignment
     let expand = |s: &str| s.replace('\t', "    ");
-    let prefix = &source[line_start..pos];
+    let prefix = &source[line.start..pos];
     let prefix_expanded = expand(prefix);
     let line_expanded = expand(line_str);
 
-    // Calculate 1-based line and column
-    let line_no = before.chars().filter(|&c| c == '\n').count() + 1;
+    // Calculate 1-based column
     let col_no = prefix_expanded.chars().count() + 1;
 
     // Build output in a Rust-like diagnostic style with dynamic gutter width
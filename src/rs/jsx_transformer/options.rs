@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::tags_attrs::HTML_VOID_TAGS;
+
+// Array methods that produce a new array; calls to them are wrapped with
+// `__jsxList(...)` so arrays of templates are joined instead of comma-stringified.
+// Scalar reducers (`reduce`, `reduceRight`) and `forEach` are deliberately absent.
+pub const DEFAULT_LIST_METHODS: &[&str] = &[
+    // Core sequence builders
+    "map",
+    "flatMap",
+    "filter",
+    // Array-producing transforms
+    "slice",
+    "concat",
+    "flat",
+    // Modern immutable array-producing
+    "toReversed",
+    "toSorted",
+    "toSpliced",
+    "with",
+    // Mutating but array-returning
+    "reverse",
+    "sort",
+    "splice",
+    "fill",
+    "copyWithin",
+];
+
+// Iterator methods whose result is streamed with `stream_helper`.
+pub const DEFAULT_STREAM_METHODS: &[&str] = &["values", "keys", "entries"];
+
+// Runtime helper wrapping iterables (sync or async) of rendered chunks.
+pub const DEFAULT_STREAM_HELPER: &str = "__jsxStream";
+
+// Runtime helper used to join arrays of templates.
+pub const DEFAULT_LIST_HELPER: &str = "__jsxList";
+
+/// How expressions producing arrays of templates are emitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListMode {
+    /// Wrap with the named runtime helper: `${__jsxList(expr)}`.
+    Helper(String),
+    /// Join inline without a runtime helper: `expr.join("")` when the expression
+    /// ends in a list call, `[].concat((expr) ?? []).join("")` otherwise.
+    InlineJoin,
+    /// Leave list expressions unwrapped.
+    Disabled,
+}
+
+impl Default for ListMode {
+    fn default() -> Self {
+        ListMode::Helper(DEFAULT_LIST_HELPER.to_string())
+    }
+}
+
+impl ListMode {
+    // Wraps a list expression; `ends_with_list_call` is true for simple chains
+    // such as `items.filter(f).map(g)` where `.join("")` can be appended directly.
+    pub(crate) fn wrap(&self, expr: &str, ends_with_list_call: bool) -> String {
+        match self {
+            ListMode::Helper(name) => format!("{name}({expr})"),
+            ListMode::InlineJoin if ends_with_list_call => format!(r#"{expr}.join("")"#),
+            ListMode::InlineJoin => format!(r#"[].concat(({expr}) ?? []).join("")"#),
+            ListMode::Disabled => expr.to_string(),
+        }
+    }
+}
+
+// Runtime helper serializing spread attributes of elements.
+pub const DEFAULT_SPREAD_HELPER: &str = "__jsxSpread";
+
+// Runtime helper registering event handlers for `EventHandlerMode::Hydrate`.
+pub const DEFAULT_ACTION_HELPER: &str = "__jsxAction";
+
+// Runtime helper that renders components.
+pub const DEFAULT_COMPONENT_HELPER: &str = "__jsxComponent";
+
+// Runtime fragment component, for `fragment_component`.
+pub const DEFAULT_FRAGMENT_COMPONENT: &str = "__jsxFragment";
+
+// Runtime helper escaping expression values of element attributes, for
+// `attribute_helper`.
+pub const DEFAULT_ATTRIBUTE_HELPER: &str = "__jsxAttr";
+
+// Tag for templates of JSX roots that use `await`.
+pub const DEFAULT_ASYNC_HELPER: &str = "__jsxAsync";
+
+// Prefix of hydration marker ids (`sxo-0`, `sxo-1`, ...).
+pub const DEFAULT_HYDRATION_ID_PREFIX: &str = "sxo";
+
+// Parse errors reported before the transform gives up.
+pub const DEFAULT_MAX_ERRORS: usize = 50;
+
+// Leading comment of expressions left untransformed (`{/* @sxo-raw */ ...}`).
+pub const DEFAULT_RAW_EXPRESSION_MARKER: &str = "@sxo-raw";
+
+/// How `on*` event handler attributes on elements (`onClick={fn}`) are emitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventHandlerMode {
+    /// Lowercase the name like any other attribute: `onclick="${fn}"`.
+    #[default]
+    Lowercase,
+    /// Keep the name as written: `onClick="${fn}"`.
+    PreserveCamel,
+    /// Drop handlers entirely, e.g. for server-rendered output.
+    Strip,
+    /// Replace expression handlers with a single `data-action` marker per element
+    /// (`data-action="click:${__jsxAction(fn)}"`); the runtime helper, see
+    /// `TransformOptions::action_helper`, registers the function for the
+    /// client runtime and returns its id. String handlers stay inline, and
+    /// the actions of a `data-action` written on the element come first.
+    Hydrate,
+}
+
+/// Quotes around the values of emitted element attributes. A value containing
+/// the chosen quote is wrapped in the other one instead, or has it escaped
+/// when it contains both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `name="value"`, including interpolated values.
+    Double,
+    /// `name='value'`, including interpolated values.
+    Single,
+    /// The quotes written in the source; interpolated values use double quotes.
+    #[default]
+    Preserve,
+}
+
+/// Order of spread attributes (`{...props}`) relative to the explicit ones on
+/// elements. HTML keeps the first of duplicate attributes, so the attributes
+/// emitted first win when a spread repeats a name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpreadOrder {
+    /// As written: whichever comes first in the source wins.
+    #[default]
+    Source,
+    /// Explicit attributes before spreads, so explicit ones win.
+    ExplicitFirst,
+    /// Spreads before explicit attributes, so spread ones win.
+    SpreadFirst,
+}
+
+impl SpreadOrder {
+    /// Kebab-case name, reported as `spreadOrder` in the WASM transform
+    /// metadata so client-side merging can follow the same rule.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpreadOrder::Source => "source",
+            SpreadOrder::ExplicitFirst => "explicit-first",
+            SpreadOrder::SpreadFirst => "spread-first",
+        }
+    }
+}
+
+/// What each JSX root is compiled to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// A template literal producing the HTML string.
+    #[default]
+    Template,
+    /// A generator yielding static chunks and dynamic values in order,
+    /// `(function* () { yield `<p>`; yield (name); yield `</p>`; }).call(this)`,
+    /// for streamed responses. Yielded values may be strings, promises, arrays
+    /// or (async) iterables; `__jsxRenderStream` flattens them into strings.
+    Stream,
+}
+
+/// Rewrites a static asset URL, called with the element tag, the HTML
+/// attribute name and the value as written (see `TransformOptions::rewrite_url`).
+pub type RewriteUrl = fn(tag: &str, attr: &str, value: &str) -> String;
+
+/// Where an expression passed to `TransformOptions::rewrite_expression` appears.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpressionSite<'a> {
+    /// A child expression, `<p>{expr}</p>`.
+    Child,
+    /// An attribute of an element or a prop of a component, `<a href={expr}>`.
+    Attribute { tag: &'a str, name: &'a str },
+}
+
+/// Rewrites an expression before code generation (see
+/// `TransformOptions::rewrite_expression`).
+pub type RewriteExpression = fn(site: ExpressionSite<'_>, expr: &str) -> String;
+
+/// A `<` the scanner takes for the start of JSX, passed to
+/// `TransformOptions::jsx_start_filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsxStartContext<'a> {
+    /// The code being scanned: the source, or the code of a component prop.
+    pub source: &'a str,
+    /// Byte offset of the `<` in `source`.
+    pub at: usize,
+    /// The token before it, whitespace skipped: an identifier or keyword
+    /// (`useState`, `return`), else a single character (`(`, `=`); empty at
+    /// the start of `source`.
+    pub previous: &'a str,
+}
+
+impl<'a> JsxStartContext<'a> {
+    pub(crate) fn new(source: &'a str, at: usize) -> Self {
+        let before = source[..at].trim_end();
+        let word = before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '$');
+        let previous = match before.chars().next_back() {
+            _ if word.len() < before.len() => &before[word.len()..],
+            Some(c) => &before[before.len() - c.len_utf8()..],
+            None => "",
+        };
+        Self {
+            source,
+            at,
+            previous,
+        }
+    }
+}
+
+/// Decides whether a candidate start of JSX is one (see
+/// `TransformOptions::jsx_start_filter`).
+pub type JsxStartFilter = fn(JsxStartContext<'_>) -> bool;
+
+/// Rewrites the transformed code of a whole file (see
+/// `TransformOptions::post_process`).
+pub type PostProcess = fn(code: &str) -> String;
+
+/// Value of the `nonce` attribute added by `TransformOptions::csp_nonce`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CspNonce {
+    /// A fixed value: `nonce="abc123"`.
+    Static(String),
+    /// A JavaScript expression evaluated at render time: `nonce="${nonce}"`.
+    Expression(String),
+}
+
+/// Options controlling how JSX is transformed into template literals.
+#[derive(Debug, Clone)]
+pub struct TransformOptions {
+    /// Method names treated as producing an array of templates. An expression is
+    /// emitted as a list (see `list_mode`) when embedded JSX is returned from or
+    /// passed to one of these calls; a plain expression is never one.
+    pub list_methods: Vec<String>,
+    /// How detected list expressions are emitted.
+    pub list_mode: ListMode,
+    /// Tag name patterns always rendered through `__jsxComponent`, in addition
+    /// to names starting with an uppercase letter, `_` or `$` (e.g. `my-app-*`).
+    /// Patterns are exact names where `*` matches any run of characters.
+    pub component_patterns: Vec<String>,
+    /// Tag name patterns always rendered as plain markup, even when they look
+    /// like components (e.g. `Swiper`). Takes precedence over `component_patterns`.
+    pub element_patterns: Vec<String>,
+    /// Element attribute name patterns removed at compile time, matched as
+    /// written (e.g. `data-test-*`, `on*`). Spread attributes are kept.
+    pub drop_attributes: Vec<String>,
+    /// Element attribute name patterns kept even when they match
+    /// `drop_attributes` (e.g. `data-testid` next to `data-*`).
+    pub keep_attributes: Vec<String>,
+    /// Props removed from element output, matched by exact name, e.g. `ref`
+    /// and `key`, which only mean something to a client framework and would
+    /// otherwise render as `ref="${myRef}"`. Components still receive them.
+    pub strip_props: Vec<String>,
+    /// Record the props removed by `strip_props` in
+    /// `TransformOutput::stripped_props`.
+    pub collect_stripped_props: bool,
+    /// Render the React-style `value` and `defaultValue` of `<textarea>` as
+    /// its content and of `<select>` as the `selected` attribute of the
+    /// matching `<option>`, so server-rendered forms show their initial
+    /// state. Off by default, `value` rendering as a plain attribute.
+    pub form_control_values: bool,
+    /// Element names rendered self-closed with children dropped, matched
+    /// case-insensitively. Defaults to the HTML void elements.
+    pub void_tags: Vec<String>,
+    /// Emit an explicit closing tag for childless custom elements (`<my-icon/>`
+    /// becomes `<my-icon></my-icon>`), as HTML requires. When false they stay
+    /// self-closed, e.g. for XHTML output.
+    pub close_custom_elements: bool,
+    /// Normalize element attribute names with the built-in table (`className`
+    /// to `class`, `strokeWidth` to `stroke-width`, ...). When false, names are
+    /// emitted as written, apart from `attr_name_map`.
+    pub normalize_attr_names: bool,
+    /// Keep the written case of attribute names missing from the built-in
+    /// table instead of lowercasing them (e.g. SVG `fePointLight` attributes).
+    pub preserve_unknown_attr_case: bool,
+    /// Keep the written case of `data-*` and `aria-*` attribute names, e.g.
+    /// `data-testId` for test selectors, even when other unknown names are
+    /// lowercased. On by default; HTML parsers still match them ignoring case.
+    pub preserve_data_aria_case: bool,
+    /// User mapping from JSX attribute name to emitted name, applied to element
+    /// attributes before any other normalization.
+    pub attr_name_map: HashMap<String, String>,
+    /// Track `<svg>`/`<math>` subtrees: inside them childless elements self-close
+    /// (`<path/>`) and unknown attribute names keep their case.
+    pub namespace_aware: bool,
+    /// How element event handler attributes are emitted.
+    pub event_handlers: EventHandlerMode,
+    /// Runtime helper registering the handlers of `EventHandlerMode::Hydrate`.
+    pub action_helper: String,
+    /// Quotes around element attribute values.
+    pub quote_style: QuoteStyle,
+    /// Where spread attributes go among the explicit attributes of an element,
+    /// deciding which wins on duplicate names.
+    pub spread_order: SpreadOrder,
+    /// Surround outermost component renders (islands) with comment markers,
+    /// `<!--sxo:Counter:sxo-0-->...<!--/sxo:sxo-0-->`, and record the ids in
+    /// `TransformOutput::hydration`. Components nested in another component's
+    /// children or props are hydrated with their island and get no marker.
+    pub hydration_markers: bool,
+    /// Prefix of the marker ids, which are numbered in source order.
+    pub hydration_id_prefix: String,
+    /// Dev mode: when set to a file name, emitted elements get a
+    /// `data-sxo-source="<file>:<line>:<col>"` attribute pointing at their JSX
+    /// (1-based). Elements inside JSX passed as component props are skipped.
+    pub source_annotations: Option<String>,
+    /// Dev mode: emit the JSX of each root as a `/* sxo: <jsx> */` comment
+    /// right before its output, to match generated templates with their
+    /// source without a source map.
+    pub source_comments: bool,
+    /// Fold statically decidable children into the template: string and number
+    /// literals (`{"Hi"}`, `{42}`), template literals of such literals
+    /// (`` {`Hi ${"there"}`} ``) and conditionals on `true`/`false`
+    /// (`{false ? <A/> : <B/>}`). Off by default, these expressions being
+    /// interpolated as written.
+    pub fold_constants: bool,
+    /// Pass quoted component props holding a number as numbers, `count="42"`
+    /// as `{"count":42}`, for runtimes with typed props. Only numbers that
+    /// JavaScript prints back as written are coerced, so `"007"` and `"1e3"`
+    /// stay strings.
+    pub coerce_literal_props: bool,
+    /// Production output: drop whitespace-only children, collapse whitespace
+    /// runs in text (outside `pre`, `textarea`, `script` and `style`), unquote
+    /// static HTML attribute values where legal and emit void elements as `<br>`.
+    pub minify: bool,
+    /// Debug output: put each child element on its own line, indented by two
+    /// spaces per nesting level, so generated templates diff like the JSX.
+    /// Adds whitespace to the rendered HTML.
+    pub pretty: bool,
+    /// Runtime helper called to render components; set per file with a
+    /// `/** @jsx h */` pragma.
+    pub component_helper: String,
+    /// When set, fragments render as a component with this identifier,
+    /// `${__jsxComponent(Fragment, [], `...`)}`, instead of being flattened into
+    /// the parent, for runtimes that need an explicit fragment node. The
+    /// runtime helpers provide `DEFAULT_FRAGMENT_COMPONENT`. Set per file with a
+    /// `/* @jsxFrag Fragment */` pragma.
+    pub fragment_component: Option<String>,
+    /// Treat `<Fragment>` and `<React.Fragment>` with at most a `key` as
+    /// fragments: they behave like `<>`, except that a keyed
+    /// `<Fragment key={k}>` always renders through `fragment_component` (or
+    /// the default one) so the key reaches the runtime. Off by default, as
+    /// they are otherwise components like any other, e.g. a user component
+    /// named `Fragment`.
+    pub named_fragments: bool,
+    /// When set, expression values of element attributes are passed through
+    /// this runtime helper, `class="${__jsxAttr(cls)}"`, which escapes `&` and
+    /// quotes so a value containing `"` cannot end the attribute. The runtime
+    /// helpers provide `DEFAULT_ATTRIBUTE_HELPER`. Values are interpolated as
+    /// is otherwise.
+    pub attribute_helper: Option<String>,
+    /// Runtime helper serializing spread attributes of elements,
+    /// `<a {...props}>` to `<a${__jsxSpread(props)}>`. It returns the
+    /// attributes as HTML, each preceded by a space.
+    pub spread_helper: String,
+    /// Emit a space before spread attributes, `<a ${__jsxSpread(props)}>`, for
+    /// helpers returning attributes without a leading space.
+    pub spread_leading_space: bool,
+    /// Expand spreads of object literals whose keys and values are all static
+    /// into plain attributes at compile time, `<a {...{id: "x", hidden: true}}>`
+    /// to `<a id="x" hidden>`, following the helper: `true` gives a valueless
+    /// attribute, `false`, `null` and `undefined` no attribute. Other spreads
+    /// still go through `spread_helper`.
+    pub expand_static_spreads: bool,
+    /// Tag applied to every template of a JSX root whose expressions use
+    /// `await` (`` __jsxAsync`<h1>${fetchTitle()}</h1>` ``). The tag resolves
+    /// interpolated promises and returns a promise of the HTML, so no `await`
+    /// remains inside templates and list callbacks.
+    pub async_helper: String,
+    /// Method names producing (possibly async) iterables, e.g. `Map#values()`.
+    /// An expression calling one at top level is wrapped with `stream_helper`
+    /// instead of being joined as a list, and its JSX root renders through
+    /// `async_helper` so chunks can be consumed as they resolve.
+    pub stream_methods: Vec<String>,
+    /// Generator function names whose calls stream the same way, e.g. `rows`
+    /// for `{rows().map(r => <tr>{r}</tr>)}`.
+    pub stream_functions: Vec<String>,
+    /// Runtime helper wrapping streamed expressions.
+    pub stream_helper: String,
+    /// What each JSX root is compiled to.
+    pub output: OutputTarget,
+    /// Compile the `css` prop of elements to a generated class, collecting the
+    /// scoped rules in `TransformOutput::styles`. The CSS must be static.
+    /// Off by default, the `css` prop rendering as a plain attribute.
+    pub scoped_css: bool,
+    /// Render the `class` and `className` attributes of an element as one
+    /// `class` attribute, at the first of them, `class="a ${expr}"`, instead
+    /// of duplicates browsers resolve differently. Repeated static classes
+    /// appear once. Runtime spreads are not merged; those expanded by
+    /// `expand_static_spreads` are.
+    pub merge_classes: bool,
+    /// Add a `nonce` attribute to inline `<script>` and `<style>` elements (no
+    /// `src`) that do not set one, for strict Content-Security-Policy.
+    pub csp_nonce: Option<CspNonce>,
+    /// Record the SHA-256 hash of each static inline `<script>` body in
+    /// `TransformOutput::script_hashes`, for a CSP `script-src` header.
+    pub script_hashes: bool,
+    /// Expressions starting with a block comment holding this marker,
+    /// `{/* @sxo-raw */ code}`, are emitted as written: JSX found in them is
+    /// not transformed, e.g. for strings built with `<` that the scanner
+    /// takes for JSX. Applies to children and component props; empty to
+    /// disable.
+    pub raw_expression_marker: String,
+    /// Stop at this many parse errors, reporting the remaining ones as a
+    /// single summary; 0 reports them all.
+    pub max_errors: usize,
+    /// Check that the code generated for each JSX node balances its brackets,
+    /// strings and template literals, failing with a diagnostic at the node
+    /// otherwise: catches code generation bugs (including those of
+    /// `rewrite_expression` hooks) before the bundler's syntax error.
+    pub verify_output: bool,
+    /// Transform each JSX node as it is parsed, the parser calling the
+    /// transformer directly instead of building a tree first: same output,
+    /// fewer allocations on large builds. Nodes using `await` or streaming,
+    /// and every node when `source_annotations` or `hydration_markers` is set
+    /// or the transform is incremental, go through the tree.
+    pub single_pass: bool,
+    /// Fail with `JSXError::LimitExceeded` for inputs over this many bytes,
+    /// before parsing anything; 0 for no limit.
+    pub max_input_bytes: usize,
+    /// Fail with `JSXError::LimitExceeded` once the JSX nodes of the input
+    /// (elements, fragments, texts and expressions) outnumber this; 0 for no
+    /// limit.
+    pub max_nodes: usize,
+    /// Fail with `JSXError::LimitExceeded` when the transform runs longer,
+    /// checked between top-level JSX nodes. Native builds only: ignored on
+    /// wasm32, which has no clock.
+    pub timeout: Option<Duration>,
+    /// Drop a leading UTF-8 byte order mark instead of copying it to the
+    /// output; positions in diagnostics are then those of the source without
+    /// it.
+    pub strip_bom: bool,
+    /// Convert CRLF line endings to LF before transforming, so that the
+    /// output, script hashes and diagnostics only see `\n`.
+    pub normalize_line_endings: bool,
+    /// Build hook applied to static `src`, `href`, `srcset` and `poster` values
+    /// of elements, e.g. to prefix a CDN, add content hashes or resolve aliases.
+    /// Each URL of a `srcset` is passed on its own, without its descriptor.
+    /// Expression values are left alone.
+    pub rewrite_url: Option<RewriteUrl>,
+    /// Plugin hook called with the source of each child and attribute
+    /// expression, whose result is emitted in its place, e.g. to wrap values
+    /// with tracking, memoization or sanitizers. Expressions embedding JSX and
+    /// spread attributes are not passed to it.
+    pub rewrite_expression: Option<RewriteExpression>,
+    /// Veto over the `<` the built-in heuristic takes for JSX starts, for
+    /// hosts running a real JavaScript tokenizer: returning false leaves the
+    /// `<` as code, e.g. the type arguments of `useState<string>()`.
+    pub jsx_start_filter: Option<JsxStartFilter>,
+    /// Final hook called with the transformed code of the file, whose result
+    /// is returned instead, e.g. to add a banner or license header or to run
+    /// custom replacements. Not called for the JSX of component props, nor by
+    /// `transform_node`.
+    pub post_process: Option<PostProcess>,
+}
+
+impl Default for TransformOptions {
+    fn default() -> Self {
+        Self {
+            list_methods: DEFAULT_LIST_METHODS.iter().map(|m| m.to_string()).collect(),
+            list_mode: ListMode::default(),
+            component_patterns: Vec::new(),
+            element_patterns: Vec::new(),
+            drop_attributes: Vec::new(),
+            keep_attributes: Vec::new(),
+            strip_props: Vec::new(),
+            collect_stripped_props: false,
+            form_control_values: false,
+            void_tags: HTML_VOID_TAGS.iter().map(|t| t.to_string()).collect(),
+            close_custom_elements: true,
+            normalize_attr_names: true,
+            preserve_unknown_attr_case: false,
+            preserve_data_aria_case: true,
+            attr_name_map: HashMap::new(),
+            namespace_aware: true,
+            event_handlers: EventHandlerMode::default(),
+            action_helper: DEFAULT_ACTION_HELPER.to_string(),
+            quote_style: QuoteStyle::default(),
+            spread_order: SpreadOrder::default(),
+            hydration_markers: false,
+            hydration_id_prefix: DEFAULT_HYDRATION_ID_PREFIX.to_string(),
+            source_annotations: None,
+            source_comments: false,
+            fold_constants: false,
+            coerce_literal_props: false,
+            minify: false,
+            pretty: false,
+            component_helper: DEFAULT_COMPONENT_HELPER.to_string(),
+            fragment_component: None,
+            named_fragments: false,
+            attribute_helper: None,
+            spread_helper: DEFAULT_SPREAD_HELPER.to_string(),
+            spread_leading_space: false,
+            expand_static_spreads: false,
+            async_helper: DEFAULT_ASYNC_HELPER.to_string(),
+            stream_methods: DEFAULT_STREAM_METHODS
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            stream_functions: Vec::new(),
+            stream_helper: DEFAULT_STREAM_HELPER.to_string(),
+            output: OutputTarget::default(),
+            scoped_css: false,
+            merge_classes: false,
+            csp_nonce: None,
+            script_hashes: false,
+            raw_expression_marker: DEFAULT_RAW_EXPRESSION_MARKER.to_string(),
+            max_errors: DEFAULT_MAX_ERRORS,
+            verify_output: false,
+            single_pass: false,
+            max_input_bytes: 0,
+            max_nodes: 0,
+            timeout: None,
+            strip_bom: true,
+            normalize_line_endings: false,
+            rewrite_url: None,
+            rewrite_expression: None,
+            jsx_start_filter: None,
+            post_process: None,
+        }
+    }
+}
+
+// Whether `expr` starts with a block comment holding the raw expression
+// marker of the options.
+pub(crate) fn is_raw_expression(expr: &str, options: &TransformOptions) -> bool {
+    let marker = options.raw_expression_marker.as_str();
+    !marker.is_empty()
+        && expr
+            .trim_start()
+            .strip_prefix("/*")
+            .and_then(|rest| rest.split_once("*/"))
+            .is_some_and(|(comment, _)| comment.trim() == marker)
+}
+
+// Matches `name` against a pattern where `*` stands for any run of characters.
+pub(crate) fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut segments = pattern.split('*');
+    let first = segments.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    if !pattern.contains('*') {
+        return rest.is_empty();
+    }
+
+    let mut segments: Vec<&str> = segments.collect();
+    let last = segments.pop().unwrap_or_default();
+    for segment in segments {
+        match rest.find(segment) {
+            Some(at) => rest = &rest[at + segment.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
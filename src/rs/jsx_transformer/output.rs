@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use crate::jsx_parser::JSXAttribute;
+
+/// Component name to the hydration marker ids emitted for it, in source order.
+pub type HydrationManifest = BTreeMap<String, Vec<String>>;
+
+/// Components and web components referenced by a source file, with the number
+/// of JSX sites using each one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentUsage {
+    /// Identifiers passed to `__jsxComponent`, e.g. `Card` or `MyAppShell`.
+    pub components: BTreeMap<String, usize>,
+    /// Custom element tag names, e.g. `my-icon`.
+    pub web_components: BTreeMap<String, usize>,
+}
+
+impl ComponentUsage {
+    pub(crate) fn add_component(&mut self, identifier: &str) {
+        *self.components.entry(identifier.to_string()).or_default() += 1;
+    }
+
+    pub(crate) fn add_web_component(&mut self, tag: &str) {
+        *self.web_components.entry(tag.to_string()).or_default() += 1;
+    }
+
+    pub(crate) fn merge(&mut self, other: ComponentUsage) {
+        for (name, count) in other.components {
+            *self.components.entry(name).or_default() += count;
+        }
+        for (tag, count) in other.web_components {
+            *self.web_components.entry(tag).or_default() += count;
+        }
+    }
+}
+
+/// A prop removed from an element by `TransformOptions::strip_props`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrippedProp {
+    /// Tag of the element, e.g. `li`.
+    pub tag: String,
+    /// The prop as written, e.g. `key={item.id}`.
+    pub attribute: JSXAttribute,
+}
+
+/// A JSX node replaced by its template in the code of a transform.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Island {
+    /// Byte range of the template in `TransformOutput::code`.
+    pub code: (usize, usize),
+    /// Byte range of the JSX in the source, after `strip_bom` and
+    /// `normalize_line_endings`.
+    pub source: (usize, usize),
+}
+
+/// Result of `jsx_transform`: the transformed source plus data collected
+/// while transforming it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformOutput {
+    pub code: String,
+    /// Island markers emitted when `TransformOptions::hydration_markers` is set.
+    pub hydration: HydrationManifest,
+    /// Components referenced by the source, including JSX passed as props.
+    pub usage: ComponentUsage,
+    /// Scoped CSS of `css` props: generated class name to its rules.
+    pub styles: BTreeMap<String, String>,
+    /// CSP hash sources (`sha256-<base64>`) of static inline scripts, in source
+    /// order, when `TransformOptions::script_hashes` is set.
+    pub script_hashes: Vec<String>,
+    /// Props removed from elements, in source order, when
+    /// `TransformOptions::collect_stripped_props` is set.
+    pub stripped_props: Vec<StrippedProp>,
+    /// The JSX replaced in `code`, in order, code between them being copied
+    /// from the source; for source maps. Empty when
+    /// `TransformOptions::post_process` rewrote the code.
+    pub islands: Vec<Island>,
+}
+
+impl TransformOutput {
+    /// The collected scoped CSS as a single stylesheet.
+    pub fn stylesheet(&self) -> String {
+        self.styles.values().map(String::as_str).collect()
+    }
+
+    // Adds the data collected by a nested transform, e.g. of JSX passed as a
+    // prop; its code and hydration markers are left to the caller.
+    pub(crate) fn merge(&mut self, other: TransformOutput) {
+        self.usage.merge(other.usage);
+        self.styles.extend(other.styles);
+        for hash in other.script_hashes {
+            self.add_script_hash(hash);
+        }
+        self.stripped_props.extend(other.stripped_props);
+    }
+
+    pub(crate) fn add_script_hash(&mut self, hash: String) {
+        if !self.script_hashes.contains(&hash) {
+            self.script_hashes.push(hash);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Per-file JSX pragmas read from the leading comments of a source, following
+//! the classic JSX tooling conventions:
+//!
+//! - `/** @jsx h */` renders components with `h` instead of `__jsxComponent`
+//! - `/* @jsxFrag Fragment */` renders fragments as `h(Fragment, [], children)`
+
+use std::borrow::Cow;
+
+use super::options::TransformOptions;
+
+/// Returns `options` overridden by the pragmas found in the leading comments.
+pub(crate) fn apply_pragmas<'a>(
+    source: &str,
+    options: &'a TransformOptions,
+) -> Cow<'a, TransformOptions> {
+    let mut options = Cow::Borrowed(options);
+    for comment in leading_comments(source) {
+        for (name, value) in pragmas(comment) {
+            match name {
+                "jsx" => options.to_mut().component_helper = value.to_string(),
+                "jsxFrag" => options.to_mut().fragment_component = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    options
+}
+
+// Bodies of the comments before the first code token (a shebang line is skipped).
+fn leading_comments(source: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = source;
+    if rest.starts_with("#!") {
+        rest = rest.find('\n').map_or("", |end| &rest[end..]);
+    }
+    loop {
+        rest = rest.trim_start();
+        if let Some(line) = rest.strip_prefix("//") {
+            let end = line.find('\n').unwrap_or(line.len());
+            comments.push(&line[..end]);
+            rest = &line[end..];
+        } else if let Some(block) = rest.strip_prefix("/*") {
+            let Some(end) = block.find("*/") else {
+                break;
+            };
+            comments.push(&block[..end]);
+            rest = &block[end + 2..];
+        } else {
+            break;
+        }
+    }
+    comments
+}
+
+// `@name value` pairs in a comment body.
+fn pragmas(comment: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut words = comment.split_whitespace().peekable();
+    std::iter::from_fn(move || loop {
+        let name = words.next()?.strip_prefix('@');
+        if let Some(name) = name {
+            if let Some(value) = words.next_if(|w| !w.starts_with('@') && is_identifier_path(w)) {
+                return Some((name, value));
+            }
+        }
+    })
+}
+
+// An identifier or member path such as `h` or `React.Fragment`.
+fn is_identifier_path(word: &str) -> bool {
+    word.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    })
+}
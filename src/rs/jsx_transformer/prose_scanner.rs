@@ -0,0 +1,89 @@
+//! Prose scanner that finds JSX islands in Markdown-ish text.
+//!
+//! Unlike `jsx_scanner`, quotes and slashes carry no meaning here. Skipped
+//! regions are the ones a Markdown pass owns:
+//! - fenced code blocks (``` or ~~~) and inline code spans
+//! - HTML comments and backslash-escaped characters
+//! - autolinks such as `<https://example.com>` or `<me@example.com>`
+
+use crate::jsx_parser::lexer::is_identifier_start;
+
+/// Returns the index of the next '<' that starts a JSX element or fragment
+/// outside of the skipped regions, or `None` if none found starting at `from`.
+pub(crate) fn find_next_island(src: &str, from: usize) -> Option<usize> {
+    let bytes = src.as_bytes();
+    let mut i = from;
+    while i < bytes.len() {
+        if at_line_start(bytes, i) {
+            if let Some(end) = fenced_block_end(src, i) {
+                i = end;
+                continue;
+            }
+        }
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &src[i..i + run];
+                // An unmatched run of backticks is literal text
+                i += run + src[i + run..].find(fence).map_or(0, |end| end + run);
+            }
+            b'<' if src[i..].starts_with("<!--") => {
+                i = src[i..].find("-->").map_or(bytes.len(), |end| i + end + 3);
+            }
+            b'<' => {
+                let next = bytes.get(i + 1).copied();
+                if next == Some(b'>') {
+                    return Some(i);
+                }
+                if src[i + 1..].starts_with(is_identifier_start) && !is_autolink(&src[i + 1..]) {
+                    return Some(i);
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+// Whether only up to three spaces precede `i` on its line.
+fn at_line_start(bytes: &[u8], i: usize) -> bool {
+    let line_start = bytes[..i]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |p| p + 1);
+    i - line_start <= 3 && bytes[line_start..i].iter().all(|&b| b == b' ')
+}
+
+// End of the fenced code block opening at `i`: past its closing fence line,
+// or the end of input when the block is never closed.
+fn fenced_block_end(src: &str, i: usize) -> Option<usize> {
+    let marker = src.as_bytes()[i];
+    if marker != b'`' && marker != b'~' {
+        return None;
+    }
+    let run = src[i..].bytes().take_while(|&b| b == marker).count();
+    if run < 3 {
+        return None;
+    }
+    let mut line = src[i..].find('\n').map(|end| i + end + 1)?;
+    while line < src.len() {
+        let end = src[line..].find('\n').map_or(src.len(), |e| line + e);
+        let text = src[line..end].trim();
+        if text.len() >= run && text.bytes().all(|b| b == marker) {
+            return Some((end + 1).min(src.len()));
+        }
+        line = end + 1;
+    }
+    Some(src.len())
+}
+
+// `rest` follows a '<': an autolink runs to '>' without whitespace and holds
+// a scheme separator or an email '@'.
+fn is_autolink(rest: &str) -> bool {
+    let Some(end) = rest.find(|c: char| c == '>' || c == '<' || c.is_whitespace()) else {
+        return false;
+    };
+    rest[end..].starts_with('>') && rest[..end].contains([':', '@'])
+}
@@ -0,0 +1,246 @@
+//! Scoped CSS for the `css` prop of elements.
+//!
+//! `<p css={`color: red; & a { color: blue }`}>` renders as
+//! `<p class="sxo-<hash>">` and adds to `TransformOutput::styles`:
+//! `.sxo-<hash>{color: red;}.sxo-<hash> a{color: blue}`.
+//! - Top-level declarations apply to the element itself
+//! - In rules, `&` stands for the element; selectors without it are scoped
+//!   to its descendants
+//! - `@media`, `@supports`, `@container` and `@layer` blocks are scoped
+//!   recursively; other at-rules (`@keyframes`, `@font-face`) are kept as is
+//!
+//! The class name is a hash of the CSS, so identical styles share one rule.
+
+use super::errors::{JSXError, JSXErrorKind};
+use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
+
+const CSS_PROP: &str = "css";
+const CLASS_PREFIX: &str = "sxo-";
+
+// At-rules whose block holds style rules, scoped like the top level.
+const CONDITIONAL_AT_RULES: &[&str] = &["@media", "@supports", "@container", "@layer"];
+
+/// Attributes of an element with its `css` prop replaced by a generated class.
+pub(crate) struct ScopedStyle {
+    pub attributes: Vec<JSXAttribute>,
+    pub class: String,
+    pub css: String,
+}
+
+/// Scopes the `css` prop of an element, or returns None when it has none.
+/// The CSS must be static: a string or a template literal without `${`.
+pub(crate) fn scope_css_prop(
+    tag: &str,
+    attributes: &[JSXAttribute],
+) -> Result<Option<ScopedStyle>, JSXError> {
+    let Some(prop) = attributes.iter().find(|a| a.name == CSS_PROP) else {
+        return Ok(None);
+    };
+    let source = match &prop.value {
+        Some(JSXAttributeValue::DoubleQuote(css) | JSXAttributeValue::SingleQuote(css)) => {
+            Some(css.as_str())
+        }
+        Some(JSXAttributeValue::Expression(expr)) => static_literal(expr),
+        None => None,
+    };
+    let Some(source) = source else {
+        return Err(JSXError::with_kind(JSXErrorKind::InvalidAttribute(
+            format!(
+                "`css` on <{tag}> must be a string or a template literal without interpolations"
+            ),
+        )));
+    };
+
+    let class = format!("{CLASS_PREFIX}{:08x}", fnv1a(source));
+    let mut css = String::new();
+    scope_block(&strip_comments(source), &format!(".{class}"), &mut css);
+
+    let mut has_class = false;
+    let mut scoped = Vec::with_capacity(attributes.len());
+    for attribute in attributes.iter().filter(|a| a.name != CSS_PROP) {
+        if attribute.name != "class" && attribute.name != "className" {
+            scoped.push(attribute.clone());
+            continue;
+        }
+        has_class = true;
+        let value = match &attribute.value {
+            Some(JSXAttributeValue::DoubleQuote(v)) => {
+                JSXAttributeValue::DoubleQuote(format!("{v} {class}"))
+            }
+            Some(JSXAttributeValue::SingleQuote(v)) => {
+                JSXAttributeValue::SingleQuote(format!("{v} {class}"))
+            }
+            Some(JSXAttributeValue::Expression(expr)) => {
+                JSXAttributeValue::Expression(format!("`${{{expr}}} {class}`"))
+            }
+            None => JSXAttributeValue::DoubleQuote(class.clone()),
+        };
+        scoped.push(JSXAttribute {
+            name: attribute.name.clone(),
+            value: Some(value),
+        });
+    }
+    if !has_class {
+        scoped.push(JSXAttribute {
+            name: "class".to_string(),
+            value: Some(JSXAttributeValue::DoubleQuote(class.clone())),
+        });
+    }
+
+    Ok(Some(ScopedStyle {
+        attributes: scoped,
+        class,
+        css,
+    }))
+}
+
+// Contents of a quoted string or of a template literal without `${`.
+fn static_literal(expr: &str) -> Option<&str> {
+    let t = expr.trim();
+    let quote = t.chars().next()?;
+    if !matches!(quote, '"' | '\'' | '`') || t.len() < 2 || !t.ends_with(quote) {
+        return None;
+    }
+    let inner = &t[1..t.len() - 1];
+    if quote == '`' && inner.contains("${") {
+        return None;
+    }
+    Some(inner)
+}
+
+// 32-bit FNV-1a: stable across builds and platforms.
+fn fnv1a(s: &str) -> u32 {
+    s.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+// Byte offset of the first of `targets` in `css`, outside quoted strings and
+// `url(...)`, whose contents may hold any of them.
+fn find_unquoted(css: &str, targets: &[u8]) -> Option<usize> {
+    let bytes = css.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            // Unquoted URLs end at the first `)`; quoted ones are strings
+            b'(' if i >= 3
+                && bytes[i - 3..i].eq_ignore_ascii_case(b"url")
+                && !css[i + 1..].trim_start().starts_with(['"', '\'']) =>
+            {
+                i += bytes[i..].iter().position(|&b| b == b')')?;
+            }
+            b if targets.contains(&b) => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Byte offset of the `}` closing the block whose `{` precedes `s`.
+fn block_end(s: &str) -> usize {
+    let mut depth = 0;
+    let mut from = 0;
+    while let Some(at) = find_unquoted(&s[from..], b"{}") {
+        let i = from + at;
+        match s.as_bytes()[i] {
+            b'{' => depth += 1,
+            _ if depth == 0 => return i,
+            _ => depth -= 1,
+        }
+        from = i + 1;
+    }
+    s.len()
+}
+
+fn scope_block(css: &str, scope: &str, out: &mut String) {
+    let mut declarations = String::new();
+    let mut rules = String::new();
+    let mut rest = css;
+    while !rest.trim().is_empty() {
+        let Some(at) = find_unquoted(rest, b"{;") else {
+            push_declaration(&mut declarations, rest);
+            break;
+        };
+        let prelude = rest[..at].trim();
+        if rest.as_bytes()[at] == b';' {
+            if prelude.starts_with('@') {
+                rules.push_str(prelude);
+                rules.push(';');
+            } else {
+                push_declaration(&mut declarations, prelude);
+            }
+            rest = &rest[at + 1..];
+            continue;
+        }
+
+        let body_start = at + 1;
+        let body_end = body_start + block_end(&rest[body_start..]);
+        let body = rest[body_start..body_end].trim();
+        if CONDITIONAL_AT_RULES.iter().any(|r| prelude.starts_with(r)) {
+            rules.push_str(prelude);
+            rules.push('{');
+            scope_block(body, scope, &mut rules);
+            rules.push('}');
+        } else if prelude.starts_with('@') {
+            rules.push_str(prelude);
+            rules.push('{');
+            rules.push_str(body);
+            rules.push('}');
+        } else {
+            let selectors: Vec<String> = prelude
+                .split(',')
+                .map(|selector| scope_selector(selector.trim(), scope))
+                .collect();
+            rules.push_str(&selectors.join(","));
+            rules.push('{');
+            rules.push_str(body);
+            rules.push('}');
+        }
+        rest = rest.get(body_end + 1..).unwrap_or_default();
+    }
+
+    if !declarations.is_empty() {
+        out.push_str(scope);
+        out.push('{');
+        out.push_str(&declarations);
+        out.push('}');
+    }
+    out.push_str(&rules);
+}
+
+fn push_declaration(declarations: &mut String, declaration: &str) {
+    let declaration = declaration.trim();
+    if !declaration.is_empty() {
+        declarations.push_str(declaration);
+        declarations.push(';');
+    }
+}
+
+fn scope_selector(selector: &str, scope: &str) -> String {
+    if selector.contains('&') {
+        selector.replace('&', scope)
+    } else {
+        format!("{scope} {selector}")
+    }
+}
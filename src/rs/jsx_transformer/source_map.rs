@@ -0,0 +1,179 @@
+//! Source maps (version 3) of transformed code. Code copied from the source
+//! maps line for line; each template maps to the JSX it replaces, its first
+//! line to where the JSX starts and its following lines to the lines of the
+//! JSX, as far as it spans. Columns count UTF-16 code units, as in JavaScript.
+
+use super::output::Island;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Source map JSON of `code`, transformed from `source` (as normalized by
+/// `strip_bom` and `normalize_line_endings`) with the given islands, naming
+/// the source `file`.
+pub(crate) fn source_map(source: &str, code: &str, islands: &[Island], file: &str) -> String {
+    let mut map = Mappings::default();
+    let mut generated = Cursor::new(code);
+    let mut original = Cursor::new(source);
+
+    let mut from = (0, 0);
+    for island in islands {
+        copy(&mut map, &mut generated, &mut original, from, island.code.0);
+        let start = original.at();
+        let start_line = generated.line;
+        map.add(generated.at(), start);
+        original.advance_to(island.source.1);
+        for (i, _) in code[island.code.0..island.code.1].match_indices('\n') {
+            generated.advance_to(island.code.0 + i + 1);
+            let line = (start.0 + generated.line - start_line).min(original.line);
+            map.add(
+                generated.at(),
+                if line == start.0 { start } else { (line, 0) },
+            );
+        }
+        from = (island.code.1, island.source.1);
+    }
+    copy(&mut map, &mut generated, &mut original, from, code.len());
+
+    format!(
+        r#"{{"version":3,"sources":[{}],"sourcesContent":[{}],"names":[],"mappings":"{}"}}"#,
+        json_string(file),
+        json_string(source),
+        map.encoded
+    )
+}
+
+// Maps code copied from the source, from `from` (in the code and the source)
+// to `to` in the code: at its start and on each line.
+fn copy(
+    map: &mut Mappings,
+    generated: &mut Cursor<'_>,
+    original: &mut Cursor<'_>,
+    from: (usize, usize),
+    to: usize,
+) {
+    generated.advance_to(from.0);
+    original.advance_to(from.1);
+    if from.0 < to {
+        map.add(generated.at(), original.at());
+    }
+    for (i, _) in generated.text[from.0..to].match_indices('\n') {
+        generated.advance_to(from.0 + i + 1);
+        original.advance_to(from.1 + i + 1);
+        map.add(generated.at(), original.at());
+    }
+    generated.advance_to(to);
+    original.advance_to(from.1 + to - from.0);
+}
+
+// Position in a text, as 0-based line and UTF-16 column, moving forward only.
+struct Cursor<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn advance_to(&mut self, offset: usize) {
+        if offset <= self.offset {
+            return;
+        }
+        for c in self.text[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += c.len_utf16();
+            }
+        }
+        self.offset = offset;
+    }
+
+    fn at(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+}
+
+// `mappings` of a source map with a single source, built in generated order.
+#[derive(Default)]
+struct Mappings {
+    encoded: String,
+    line: usize,
+    column: usize,
+    previous: Option<(usize, usize)>,
+    source_line: i64,
+    source_column: i64,
+}
+
+impl Mappings {
+    fn add(&mut self, generated: (usize, usize), original: (usize, usize)) {
+        if self.previous == Some(generated) {
+            return;
+        }
+        if self.line < generated.0 {
+            for _ in self.line..generated.0 {
+                self.encoded.push(';');
+            }
+            self.line = generated.0;
+            self.column = 0;
+        } else if self.previous.is_some() {
+            self.encoded.push(',');
+        }
+        let (line, column) = (original.0 as i64, original.1 as i64);
+        vlq(&mut self.encoded, generated.1 as i64 - self.column as i64);
+        vlq(&mut self.encoded, 0);
+        vlq(&mut self.encoded, line - self.source_line);
+        vlq(&mut self.encoded, column - self.source_column);
+        self.column = generated.1;
+        self.source_line = line;
+        self.source_column = column;
+        self.previous = Some(generated);
+    }
+}
+
+// Base64 VLQ of a signed value, the sign in the lowest bit.
+fn vlq(out: &mut String, value: i64) {
+    let mut rest = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = rest & 0b11111;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
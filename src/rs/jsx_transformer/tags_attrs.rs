@@ -1,33 +1,127 @@
+use std::borrow::Cow;
+
+use super::awaits::async_interpolation;
 use super::errors::JSXError;
+use super::html_data::{
+    ATTRIBUTE_NAMES, BOOLEAN_ATTRIBUTES, HTML_TAGS, OBSOLETE_HTML_ATTRIBUTES, OBSOLETE_HTML_TAGS,
+};
+use super::options::{
+    is_raw_expression, matches_pattern, CspNonce, EventHandlerMode, ExpressionSite, QuoteStyle,
+    SpreadOrder, TransformOptions,
+};
+use super::output::TransformOutput;
+use super::transform::{fold_number, fold_quoted, is_empty_expression};
+use crate::jsx_parser::jsx_scanner::find_next_jsx_operand;
 use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
 
 const COMMA: &str = ",";
 const UNDERSCORE: char = '_';
 const DOLLAR_SIGN: char = '$';
 
-const VOID_TAGS: [&str; 23] = [
-    "area", "base", "br", "circle", "col", "ellipse", "embed", "hr", "image", "img", "input",
-    "line", "link", "meta", "param", "path", "polygon", "polyline", "rect", "source", "track",
-    "use", "wbr",
-];
+pub use super::html_data::HTML_VOID_TAGS;
+
+/// Whether a lowercase tag is an element of the HTML standard, `svg` or `math`.
+pub fn is_html_tag(tag: &str) -> bool {
+    HTML_TAGS.binary_search(&tag).is_ok()
+}
+
+/// Whether a lowercase tag is an obsolete HTML element, e.g. `center`.
+pub fn is_obsolete_tag(tag: &str) -> bool {
+    OBSOLETE_HTML_TAGS.binary_search(&tag).is_ok()
+}
+
+/// Whether a lowercase tag is an HTML void element, e.g. `br`.
+pub fn is_void(tag: &str) -> bool {
+    HTML_VOID_TAGS.binary_search(&tag).is_ok()
+}
+
+/// Whether an attribute, by its normalized HTML name, is a boolean attribute
+/// of HTML, present or absent rather than valued, e.g. `disabled`.
+pub fn is_boolean_attr(name: &str) -> bool {
+    BOOLEAN_ATTRIBUTES.binary_search(&name).is_ok()
+}
+
+/// The HTML name of a JSX attribute name the built-in table knows about,
+/// with the case HTML or SVG expects: `class` for `className`,
+/// `stroke-width` for `strokeWidth`, `viewBox` for `viewBox`.
+pub fn expected_case(attr: &str) -> Option<&'static str> {
+    ATTRIBUTE_NAMES
+        .binary_search_by_key(&attr, |(name, _)| name)
+        .ok()
+        .map(|i| ATTRIBUTE_NAMES[i].1)
+}
+
+/// Whether an attribute, by its normalized HTML name, is obsolete on an HTML
+/// element, e.g. `align` or `bgcolor` on `<td>`.
+pub fn is_obsolete_attribute(tag: &str, name: &str) -> bool {
+    OBSOLETE_HTML_ATTRIBUTES
+        .binary_search_by_key(&name, |(name, _)| name)
+        .is_ok_and(|i| {
+            let tags = OBSOLETE_HTML_ATTRIBUTES[i].1;
+            tags.is_empty() || tags.contains(&tag)
+        })
+}
+
+/// The HTML element closest to an unknown tag, e.g. `div` for `dvi`: one edit
+/// away for short tags, two for tags of six characters or more.
+pub fn closest_html_tag(tag: &str) -> Option<&'static str> {
+    HTML_TAGS
+        .iter()
+        .map(|known| (edit_distance(tag, known), *known))
+        .filter(|(distance, _)| *distance <= (tag.len() / 3).clamp(1, 2) && *distance < tag.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+// Levenshtein distance, counting a swap of adjacent characters as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum TagType {
+pub enum TagType {
     Component,
     WebComponent,
     Void,
     Element,
 }
 
+impl TagType {
+    /// Kebab-case name, as returned by the WASM `classifyTag` export.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagType::Component => "component",
+            TagType::WebComponent => "web-component",
+            TagType::Void => "void",
+            TagType::Element => "element",
+        }
+    }
+}
+
+/// Classifies a tag with the default rules: component names start with an
+/// uppercase letter, `_` or `$`; custom elements contain a hyphen; void
+/// elements are the HTML void elements.
 #[inline(always)]
-pub(crate) fn classify_tag(tag: &str) -> TagType {
-    // Component: starts with uppercase, '_' or '$'
-    if tag
-        .chars()
-        .next()
-        .map(|c| c.is_uppercase() || c == UNDERSCORE || c == DOLLAR_SIGN)
-        .unwrap_or(false)
-    {
+pub fn classify_tag(tag: &str) -> TagType {
+    if is_component_name(tag) {
         return TagType::Component;
     }
 
@@ -38,263 +132,714 @@ pub(crate) fn classify_tag(tag: &str) -> TagType {
 
     // Void element: binary search against sorted list (case-insensitive)
     let lower = tag.to_ascii_lowercase();
-    if VOID_TAGS.binary_search(&lower.as_str()).is_ok() {
+    if is_void(&lower) {
         return TagType::Void;
     }
 
     TagType::Element
 }
 
-// Unified attribute transformation helper
+/// Classification honoring the component allow/deny patterns and the void set
+/// from the options. Element patterns win over component patterns so a tag can
+/// always be forced back to plain markup.
 #[inline]
-pub(crate) fn transform_attribute(attr: &JSXAttribute, target: TagType) -> String {
-    match target {
-        TagType::Component => match &attr.value {
-            Some(JSXAttributeValue::Expression(expr)) => {
-                let transformed = super::jsx_transformer(expr).unwrap_or_else(|_| expr.to_string());
-                format!(r#"{{"{}":{}}}"#, &attr.name, transformed)
-            }
-            Some(JSXAttributeValue::DoubleQuote(value)) => {
-                format!(r#"{{"{}":"{}"}}"#, &attr.name, value)
-            }
-            Some(JSXAttributeValue::SingleQuote(value)) => {
-                format!(r#"{{"{}":'{}'}}"#, &attr.name, value)
+pub fn classify_tag_with(tag: &str, options: &TransformOptions) -> TagType {
+    let forced_element = options
+        .element_patterns
+        .iter()
+        .any(|p| matches_pattern(p, tag));
+    if !forced_element
+        && (is_component_name(tag)
+            || options
+                .component_patterns
+                .iter()
+                .any(|p| matches_pattern(p, tag)))
+    {
+        return TagType::Component;
+    }
+
+    if tag.contains('-') {
+        return TagType::WebComponent;
+    }
+    if options
+        .void_tags
+        .iter()
+        .any(|void| void.eq_ignore_ascii_case(tag))
+    {
+        return TagType::Void;
+    }
+    TagType::Element
+}
+
+// Component: starts with uppercase, '_' or '$'
+#[inline]
+fn is_component_name(tag: &str) -> bool {
+    tag.chars()
+        .next()
+        .map(|c| c.is_uppercase() || c == UNDERSCORE || c == DOLLAR_SIGN)
+        .unwrap_or(false)
+}
+
+// JavaScript reference for a component tag. Hyphenated names forced to be
+// components via `component_patterns` are not valid identifiers, so they are
+// mapped to PascalCase: `my-app-shell` -> `MyAppShell`.
+#[inline]
+pub(crate) fn component_identifier(tag: &str) -> Cow<'_, str> {
+    if !tag.contains('-') {
+        return Cow::Borrowed(tag);
+    }
+    tag.split('-')
+        .map(|segment| {
+            let mut chars = segment.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
             }
-            None => {
-                if attr.name.starts_with("...") {
-                    format!("{{{}}}", attr.name)
-                } else {
-                    format!(r#"{{"{}":true}}"#, attr.name)
-                }
+        })
+        .collect::<String>()
+        .into()
+}
+
+// Element whose attributes are being serialized, for rules that depend on
+// where an attribute appears.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ElementContext<'a> {
+    pub tag: &'a str,
+    // Inside <svg> or <math>, where attribute names are case-sensitive
+    pub foreign: bool,
+    // Minify: drop quotes around static values where HTML allows it
+    pub unquote: bool,
+}
+
+/// Serializes a single attribute for the given tag kind with the default
+/// options: a `{"name":value}` prop object for components, `name="value"`
+/// markup for elements. JSX in an expression value that does not transform
+/// is kept as written; `transform_attribute_with_options` reports it.
+pub fn transform_attribute(attr: &JSXAttribute, target: TagType) -> String {
+    let options = TransformOptions::default();
+    // Only expression values fail
+    transform_attribute_with_options(attr, target, &options).unwrap_or_else(|_| {
+        match (target, &attr.value) {
+            (TagType::Component, Some(JSXAttributeValue::Expression(expr))) => {
+                format!(r#"{{"{}":{expr}}}"#, attr.name)
             }
-        },
+            _ => transform_element_attribute(attr, &ElementContext::default(), &options),
+        }
+    })
+}
+
+/// Same as `transform_attribute` with the given options. Fails when JSX in an
+/// expression value does not transform.
+#[inline]
+pub fn transform_attribute_with_options(
+    attr: &JSXAttribute,
+    target: TagType,
+    options: &TransformOptions,
+) -> Result<String, JSXError> {
+    let mut output = TransformOutput::default();
+    match target {
+        TagType::Component => transform_component_attribute(attr, options, &mut output),
         // Elements (including web components and voids) share the same serialization
         _ => {
-            // Handle boolean and spread first (no normalized name for boolean to preserve legacy behavior)
-            if attr.value.is_none() {
-                if attr.name.starts_with("...") {
-                    return format!(
-                        "${{__jsxSpread({})}}",
-                        attr.name
-                            .replace("...", "")
-                            .trim_start_matches('(')
-                            .trim_end_matches(')')
-                    );
-                } else {
-                    return attr.name.to_string();
-                }
-            }
+            let nested = nested_jsx(attr, options, &mut output)?;
+            let attr = nested.as_ref().unwrap_or(attr);
+            Ok(transform_element_attribute(
+                attr,
+                &ElementContext::default(),
+                options,
+            ))
+        }
+    }
+}
 
-            let name = normalize_html_attr_name(&attr.name);
-            match &attr.value {
-                Some(JSXAttributeValue::Expression(expr)) => {
-                    format!(r#"{name}="${{{expr}}}""#)
-                }
-                Some(JSXAttributeValue::DoubleQuote(value)) => {
-                    format!(r#"{name}="{value}""#)
-                }
-                Some(JSXAttributeValue::SingleQuote(value)) => {
-                    format!("{name}='{value}'")
-                }
-                None => unreachable!("handled above"),
+// Component props become `{"name":value}` objects; JSX inside expression
+// values is transformed recursively and its components and styles recorded
+// in `output`.
+#[inline]
+fn transform_component_attribute(
+    attr: &JSXAttribute,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
+) -> Result<String, JSXError> {
+    Ok(match &attr.value {
+        None if attr.name.starts_with("...") => format!("{{{}}}", attr.name),
+        _ => format!(
+            r#"{{"{}":{}}}"#,
+            &attr.name,
+            component_prop_value(attr, options, output)?
+        ),
+    })
+}
+
+// JavaScript value of a component prop, spreads excluded.
+pub(crate) fn component_prop_value(
+    attr: &JSXAttribute,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
+) -> Result<String, JSXError> {
+    let coerce = |value: &str| {
+        options
+            .coerce_literal_props
+            .then(|| fold_number(value))
+            .flatten()
+    };
+    Ok(match &attr.value {
+        Some(JSXAttributeValue::Expression(expr)) if is_raw_expression(expr, options) => {
+            expr.to_string()
+        }
+        Some(JSXAttributeValue::Expression(expr)) => transform_nested(expr, options, output)?,
+        Some(JSXAttributeValue::DoubleQuote(value)) => {
+            coerce(value).unwrap_or_else(|| format!(r#""{value}""#))
+        }
+        Some(JSXAttributeValue::SingleQuote(value)) => {
+            coerce(value).unwrap_or_else(|| format!("'{value}'"))
+        }
+        None => "true".to_string(),
+    })
+}
+
+// Code of an expression value with the JSX in it transformed, its components
+// and styles recorded in `output`. The JSX belongs to the island of the
+// element or component, and its positions are relative to the value, so
+// neither is annotated.
+fn transform_nested(
+    expr: &str,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
+) -> Result<String, JSXError> {
+    let mut nested = if options.hydration_markers || options.source_annotations.is_some() {
+        let options = TransformOptions {
+            hydration_markers: false,
+            source_annotations: None,
+            ..options.clone()
+        };
+        super::jsx_transform_expression(expr, &options)?
+    } else {
+        super::jsx_transform_expression(expr, options)?
+    };
+    let code = std::mem::take(&mut nested.code);
+    output.merge(nested);
+    Ok(code)
+}
+
+// An element attribute whose expression value holds JSX, `title={cond ? <b/>
+// : "x"}`, with the JSX transformed as in embedded expressions; None for any
+// other attribute.
+fn nested_jsx(
+    attr: &JSXAttribute,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
+) -> Result<Option<JSXAttribute>, JSXError> {
+    let Some(JSXAttributeValue::Expression(expr)) = &attr.value else {
+        return Ok(None);
+    };
+    if is_raw_expression(expr, options) || find_next_jsx_operand(expr, 0).is_none() {
+        return Ok(None);
+    }
+    let code = transform_nested(expr, options, output)?;
+    Ok((code != *expr).then(|| JSXAttribute {
+        name: attr.name.clone(),
+        value: Some(JSXAttributeValue::Expression(code)),
+    }))
+}
+
+#[inline]
+fn transform_element_attribute(
+    attr: &JSXAttribute,
+    element: &ElementContext<'_>,
+    options: &TransformOptions,
+) -> String {
+    // Handle boolean and spread first (no normalized name for boolean to preserve legacy behavior)
+    if attr.value.is_none() {
+        if attr.name.starts_with("...") {
+            return format!(
+                "${{{}({})}}",
+                options.spread_helper,
+                attr.name
+                    .replace("...", "")
+                    .trim_start_matches('(')
+                    .trim_end_matches(')')
+            );
+        } else if let Some(mapped) = options.attr_name_map.get(&attr.name) {
+            return mapped.to_string();
+        } else {
+            return attr.name.to_string();
+        }
+    }
+
+    let name = normalize_attr_name_with(&attr.name, element.foreign, options);
+    let rewritten = attr
+        .value
+        .as_ref()
+        .and_then(|value| rewrite_url(&name, value, element, options));
+    match rewritten.as_ref().or(attr.value.as_ref()) {
+        Some(JSXAttributeValue::DoubleQuote(value) | JSXAttributeValue::SingleQuote(value))
+            if element.unquote && is_unquotable(value) =>
+        {
+            format!("{name}={value}")
+        }
+        Some(value) => element_attribute(&name, value, options),
+        None => unreachable!("handled above"),
+    }
+}
+
+// Static asset URL attributes passed through `TransformOptions::rewrite_url`.
+const REWRITTEN_URL_ATTRIBUTES: &[&str] = &["src", "href", "srcset", "poster"];
+
+// Applies `rewrite_url` to a static value of an asset URL attribute, keeping
+// its quotes; a quote of the same kind in the new URL is escaped.
+fn rewrite_url(
+    name: &str,
+    value: &JSXAttributeValue,
+    element: &ElementContext<'_>,
+    options: &TransformOptions,
+) -> Option<JSXAttributeValue> {
+    let rewrite = options.rewrite_url?;
+    if !REWRITTEN_URL_ATTRIBUTES.contains(&name) {
+        return None;
+    }
+    let rewrite = |url: &str| {
+        if name == "srcset" {
+            rewrite_srcset(url, |url| rewrite(element.tag, name, url))
+        } else {
+            rewrite(element.tag, name, url)
+        }
+    };
+    match value {
+        JSXAttributeValue::DoubleQuote(url) => Some(JSXAttributeValue::DoubleQuote(
+            rewrite(url).replace('"', "&quot;"),
+        )),
+        JSXAttributeValue::SingleQuote(url) => Some(JSXAttributeValue::SingleQuote(
+            rewrite(url).replace('\'', "&#39;"),
+        )),
+        JSXAttributeValue::Expression(_) => None,
+    }
+}
+
+// Rewrites each URL of the comma-separated candidates of a `srcset`, keeping
+// their width or density descriptors and the spacing as written.
+fn rewrite_srcset(srcset: &str, rewrite: impl Fn(&str) -> String) -> String {
+    srcset
+        .split(',')
+        .map(|candidate| {
+            let start = candidate.len() - candidate.trim_start().len();
+            let end = candidate[start..]
+                .find(char::is_whitespace)
+                .map_or(candidate.len(), |i| start + i);
+            if start == end {
+                return candidate.to_string();
             }
+            let url = &candidate[start..end];
+            format!(
+                "{}{}{}",
+                &candidate[..start],
+                rewrite(url),
+                &candidate[end..]
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// HTML unquoted attribute value syntax: non-empty, without whitespace or any
+// of `"'=<>` and backtick.
+#[inline]
+fn is_unquotable(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'))
+}
+
+#[inline]
+fn element_attribute(name: &str, value: &JSXAttributeValue, options: &TransformOptions) -> String {
+    let style = options.quote_style;
+    let (value, written) = match value {
+        JSXAttributeValue::Expression(expr) => {
+            let quote = expression_quote(style);
+            return format!("{name}={quote}{}{quote}", interpolation(expr, options));
+        }
+        JSXAttributeValue::DoubleQuote(value) => (value, '"'),
+        JSXAttributeValue::SingleQuote(value) => (value, '\''),
+    };
+    let quote = match style {
+        QuoteStyle::Double => '"',
+        QuoteStyle::Single => '\'',
+        QuoteStyle::Preserve => written,
+    };
+    let other = if quote == '"' { '\'' } else { '"' };
+    if !value.contains(quote) {
+        format!("{name}={quote}{value}{quote}")
+    } else if !value.contains(other) {
+        format!("{name}={other}{value}{other}")
+    } else {
+        let escaped = if quote == '"' { "&quot;" } else { "&#39;" };
+        format!("{name}={quote}{}{quote}", value.replace(quote, escaped))
+    }
+}
+
+// Quote of attribute values holding an interpolation.
+#[inline]
+fn expression_quote(style: QuoteStyle) -> char {
+    if style == QuoteStyle::Single {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+// Interpolation of an expression attribute value, `${expr}`; nothing for an
+// empty expression.
+fn interpolation(expr: &str, options: &TransformOptions) -> String {
+    if is_empty_expression(expr) {
+        return String::new();
+    }
+    // The helper escapes the awaited value, not its promise
+    let value = match &options.attribute_helper {
+        Some(helper) => async_interpolation(&format!("{helper}({expr})")).into_owned(),
+        None => async_interpolation(expr).into_owned(),
+    };
+    format!("${{{value}}}")
+}
+
+// Whether an attribute sets the class of an element.
+#[inline]
+fn is_class(attr: &JSXAttribute) -> bool {
+    attr.name == "class" || attr.name == "className"
+}
+
+// Single `class` attribute for `merge_classes`, from the static classes and
+// interpolations of the class attributes of an element.
+fn merged_class(values: &[String], options: &TransformOptions) -> String {
+    let quote = expression_quote(options.quote_style);
+    let escaped = if quote == '"' { "&quot;" } else { "&#39;" };
+    let mut classes: Vec<&str> = Vec::with_capacity(values.len());
+    for value in values {
+        if !classes.contains(&value.as_str()) {
+            classes.push(value);
         }
     }
+    let classes = classes
+        .iter()
+        .map(|class| {
+            if class.starts_with("${") {
+                Cow::Borrowed(*class)
+            } else {
+                Cow::Owned(class.replace(quote, escaped))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("class={quote}{classes}{quote}")
+}
+
+// Copy of an attribute with its expression value passed through
+// `TransformOptions::rewrite_expression`, or None when there is nothing to rewrite.
+fn rewrite_expression(
+    attr: &JSXAttribute,
+    tag: &str,
+    options: &TransformOptions,
+) -> Option<JSXAttribute> {
+    let rewrite = options.rewrite_expression?;
+    let Some(JSXAttributeValue::Expression(expr)) = &attr.value else {
+        return None;
+    };
+    let site = ExpressionSite::Attribute {
+        tag,
+        name: &attr.name,
+    };
+    Some(JSXAttribute {
+        name: attr.name.clone(),
+        value: Some(JSXAttributeValue::Expression(rewrite(site, expr))),
+    })
+}
+
+// The `nonce` attribute `TransformOptions::csp_nonce` adds to an inline
+// `<script>` or `<style>`, unless it already has one.
+pub(crate) fn nonce_attribute(
+    tag: &str,
+    attributes: &[JSXAttribute],
+    options: &TransformOptions,
+) -> Option<JSXAttribute> {
+    let nonce = options.csp_nonce.as_ref()?;
+    if !matches!(tag, "script" | "style")
+        || attributes
+            .iter()
+            .any(|a| a.name == "nonce" || a.name == "src")
+    {
+        return None;
+    }
+    let value = match nonce {
+        CspNonce::Static(value) => JSXAttributeValue::DoubleQuote(value.clone()),
+        CspNonce::Expression(expr) => JSXAttributeValue::Expression(expr.clone()),
+    };
+    Some(JSXAttribute {
+        name: "nonce".to_string(),
+        value: Some(value),
+    })
 }
 
 pub(crate) fn transform_component_attributes(
+    tag: &str,
     attributes: &[JSXAttribute],
+    options: &TransformOptions,
+    output: &mut TransformOutput,
 ) -> Result<String, JSXError> {
     let mut attr_parts = Vec::new();
     for attr in attributes.iter() {
-        attr_parts.push(transform_attribute(attr, TagType::Component));
+        let rewritten = rewrite_expression(attr, tag, options);
+        let attr = rewritten.as_ref().unwrap_or(attr);
+        attr_parts.push(transform_component_attribute(attr, options, output)?);
     }
     Ok(format!("[{}]", attr_parts.join(COMMA)))
 }
 
 pub(crate) fn transform_element_attributes(
     attributes: &[JSXAttribute],
+    element: &ElementContext<'_>,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
 ) -> Result<Vec<String>, JSXError> {
+    let expanded: Vec<JSXAttribute>;
+    let attributes = if options.expand_static_spreads {
+        expanded = attributes
+            .iter()
+            .flat_map(|attr| static_spread(attr).unwrap_or_else(|| vec![attr.clone()]))
+            .collect();
+        &expanded
+    } else {
+        attributes
+    };
     let mut attr_parts = Vec::new();
+    let mut actions = Vec::new();
+    // With `merge_classes`, the index of the merged class in `attr_parts` and
+    // its values
+    let merge_classes =
+        options.merge_classes && attributes.iter().filter(|a| is_class(a)).count() > 1;
+    let mut classes: Option<(usize, Vec<String>)> = None;
+    // Hydrated handlers are added to the actions of a `data-action` written
+    // on the element, in source order, rather than setting it twice
+    let hydrates = options.event_handlers == EventHandlerMode::Hydrate
+        && attributes.iter().any(|a| {
+            event_handler_name(&a.name).is_some()
+                && matches!(a.value, Some(JSXAttributeValue::Expression(_)))
+        });
     for attr in attributes {
-        attr_parts.push(transform_attribute(attr, TagType::Element));
+        if is_dropped(&attr.name, options) {
+            continue;
+        }
+        let rewritten = rewrite_expression(attr, element.tag, options);
+        let attr = rewritten.as_ref().unwrap_or(attr);
+        let nested = nested_jsx(attr, options, output)?;
+        let attr = nested.as_ref().unwrap_or(attr);
+        if merge_classes && is_class(attr) {
+            let (_, values) = classes.get_or_insert_with(|| {
+                attr_parts.push(String::new());
+                (attr_parts.len() - 1, Vec::new())
+            });
+            match &attr.value {
+                Some(JSXAttributeValue::Expression(expr)) if !is_empty_expression(expr) => {
+                    values.push(interpolation(expr, options))
+                }
+                Some(JSXAttributeValue::DoubleQuote(v) | JSXAttributeValue::SingleQuote(v)) => {
+                    values.extend(v.split_whitespace().map(str::to_string))
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if hydrates && attr.name == "data-action" {
+            match &attr.value {
+                Some(JSXAttributeValue::DoubleQuote(v) | JSXAttributeValue::SingleQuote(v)) => {
+                    actions.extend(v.split_whitespace().map(|a| a.replace('"', "&quot;")))
+                }
+                Some(JSXAttributeValue::Expression(expr)) if !is_empty_expression(expr) => {
+                    actions.push(interpolation(expr, options))
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(event) = event_handler_name(&attr.name) {
+            match (options.event_handlers, &attr.value) {
+                (EventHandlerMode::Strip, _) => continue,
+                (EventHandlerMode::PreserveCamel, Some(value)) => {
+                    attr_parts.push(element_attribute(&attr.name, value, options));
+                    continue;
+                }
+                (EventHandlerMode::Hydrate, Some(JSXAttributeValue::Expression(expr))) => {
+                    actions.push(format!("{event}:${{{}({expr})}}", options.action_helper));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        attr_parts.push(transform_element_attribute(attr, element, options));
+    }
+    if let Some((index, values)) = classes {
+        attr_parts[index] = merged_class(&values, options);
+    }
+    if !actions.is_empty() {
+        attr_parts.push(format!(r#"data-action="{}""#, actions.join(" ")));
+    }
+    // Stable sorts keep the source order within each group
+    let is_spread = |attr: &String| is_spread_part(attr, options);
+    match options.spread_order {
+        SpreadOrder::Source => {}
+        SpreadOrder::ExplicitFirst => attr_parts.sort_by_key(is_spread),
+        SpreadOrder::SpreadFirst => attr_parts.sort_by_key(|attr| !is_spread(attr)),
     }
     Ok(attr_parts)
 }
 
-// @see: https://github.com/denoland/deno_ast/blob/3aba071b59d71802398c2fbcd2d01c99a51553cf/src/transpiling/jsx_precompile.rs#L89
+// Whether an element attribute part is a spread, `${__jsxSpread(props)}`.
+pub(crate) fn is_spread_part(part: &str, options: &TransformOptions) -> bool {
+    part.strip_prefix("${")
+        .and_then(|rest| rest.strip_prefix(options.spread_helper.as_str()))
+        .is_some_and(|rest| rest.starts_with('('))
+}
+
+// Attributes of a spread of an object literal with static keys and values,
+// `{...{id: "x", hidden: true}}`, as `spread_helper` renders them; None for
+// any other spread or attribute. Strings with escapes are left to the runtime.
+fn static_spread(attr: &JSXAttribute) -> Option<Vec<JSXAttribute>> {
+    let mut expr = attr.name.strip_prefix("...")?.trim();
+    while let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        expr = inner.trim();
+    }
+    let body = expr.strip_prefix('{')?.strip_suffix('}')?;
+    let string = |t: &str| fold_quoted(t).filter(|_| !t.contains('\\'));
+
+    let mut entries = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ',') => {
+                entries.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !body[start..].trim().is_empty() {
+        entries.push(&body[start..]);
+    }
+
+    let mut attributes = Vec::new();
+    for entry in entries {
+        let (key, value) = entry.split_once(':')?;
+        let key = key.trim();
+        let name = if key.starts_with(['"', '\'']) {
+            string(key)?
+        } else if key.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            key.to_string()
+        } else {
+            return None;
+        };
+        let value = match value.trim() {
+            "true" => None,
+            "false" | "null" | "undefined" => continue,
+            value => Some(JSXAttributeValue::DoubleQuote(
+                fold_number(value).or_else(|| string(value))?,
+            )),
+        };
+        attributes.push(JSXAttribute { name, value });
+    }
+    Some(attributes)
+}
+
+// Whether an element attribute is removed by `drop_attributes` and not kept
+// by `keep_attributes`.
 #[inline]
-pub(crate) fn normalize_html_attr_name(name: &str) -> String {
-    match name {
-        // JSX specific
-        "htmlFor" => "for".to_string(),
-        "className" => "class".to_string(),
-        "dangerouslySetInnerHTML" => name.to_string(),
-
-        "panose1" => "panose-1".to_string(),
-        "xlinkActuate" => "xlink:actuate".to_string(),
-        "xlinkArcrole" => "xlink:arcrole".to_string(),
-
-        // xlink:href was removed from SVG and isn't needed
-        "xlinkHref" => "href".to_string(),
-        "xlink:href" => "href".to_string(),
-
-        "xlinkRole" => "xlink:role".to_string(),
-        "xlinkShow" => "xlink:show".to_string(),
-        "xlinkTitle" => "xlink:title".to_string(),
-        "xlinkType" => "xlink:type".to_string(),
-        "xmlBase" => "xml:base".to_string(),
-        "xmlLang" => "xml:lang".to_string(),
-        "xmlSpace" => "xml:space".to_string(),
-
-        // Attributes that are kebab-cased
-        "accentHeight"
-        | "acceptCharset"
-        | "alignmentBaseline"
-        | "arabicForm"
-        | "baselineShift"
-        | "capHeight"
-        | "clipPath"
-        | "clipRule"
-        | "colorInterpolation"
-        | "colorInterpolationFilters"
-        | "colorProfile"
-        | "colorRendering"
-        | "contentScriptType"
-        | "contentStyleType"
-        | "dominantBaseline"
-        | "enableBackground"
-        | "fillOpacity"
-        | "fillRule"
-        | "floodColor"
-        | "floodOpacity"
-        | "fontFamily"
-        | "fontSize"
-        | "fontSizeAdjust"
-        | "fontStretch"
-        | "fontStyle"
-        | "fontVariant"
-        | "fontWeight"
-        | "glyphName"
-        | "glyphOrientationHorizontal"
-        | "glyphOrientationVertical"
-        | "horizAdvX"
-        | "horizOriginX"
-        | "horizOriginY"
-        | "httpEquiv"
-        | "imageRendering"
-        | "letterSpacing"
-        | "lightingColor"
-        | "markerEnd"
-        | "markerMid"
-        | "markerStart"
-        | "overlinePosition"
-        | "overlineThickness"
-        | "paintOrder"
-        | "pointerEvents"
-        | "renderingIntent"
-        | "shapeRendering"
-        | "stopColor"
-        | "stopOpacity"
-        | "strikethroughPosition"
-        | "strikethroughThickness"
-        | "strokeDasharray"
-        | "strokeDashoffset"
-        | "strokeLinecap"
-        | "strokeLinejoin"
-        | "strokeMiterlimit"
-        | "strokeOpacity"
-        | "strokeWidth"
-        | "textAnchor"
-        | "textDecoration"
-        | "textRendering"
-        | "transformOrigin"
-        | "underlinePosition"
-        | "underlineThickness"
-        | "unicodeBidi"
-        | "unicodeRange"
-        | "unitsPerEm"
-        | "vAlphabetic"
-        | "vectorEffect"
-        | "vertAdvY"
-        | "vertOriginX"
-        | "vertOriginY"
-        | "vHanging"
-        | "vMathematical"
-        | "wordSpacing"
-        | "writingMode"
-        | "xHeight" => name
-            .chars()
-            .map(|ch| match ch {
-                'A'..='Z' => format!("-{}", ch.to_lowercase()),
-                _ => ch.to_string(),
-            })
-            .collect(),
-
-        // Attributes that are camelCased and should be kept as is.
-        "allowReorder"
-        | "attributeName"
-        | "attributeType"
-        | "baseFrequency"
-        | "baseProfile"
-        | "calcMode"
-        | "clipPathUnits"
-        | "diffuseConstant"
-        | "edgeMode"
-        | "filterUnits"
-        | "glyphRef"
-        | "gradientTransform"
-        | "gradientUnits"
-        | "kernelMatrix"
-        | "kernelUnitLength"
-        | "keyPoints"
-        | "keySplines"
-        | "keyTimes"
-        | "lengthAdjust"
-        | "limitingConeAngle"
-        | "markerHeight"
-        | "markerUnits"
-        | "markerWidth"
-        | "maskContentUnits"
-        | "maskUnits"
-        | "numOctaves"
-        | "pathLength"
-        | "patternContentUnits"
-        | "patternTransform"
-        | "patternUnits"
-        | "pointsAtX"
-        | "pointsAtY"
-        | "pointsAtZ"
-        | "preserveAlpha"
-        | "preserveAspectRatio"
-        | "primitiveUnits"
-        | "referrerPolicy"
-        | "refX"
-        | "refY"
-        | "repeatCount"
-        | "repeatDur"
-        | "requiredExtensions"
-        | "requiredFeatures"
-        | "specularConstant"
-        | "specularExponent"
-        | "spreadMethod"
-        | "startOffset"
-        | "stdDeviation"
-        | "stitchTiles"
-        | "surfaceScale"
-        | "systemLanguage"
-        | "tableValues"
-        | "targetX"
-        | "targetY"
-        | "textLength"
-        | "viewBox"
-        | "xChannelSelector"
-        | "yChannelSelector"
-        | "zoomAndPan" => name.to_string(),
+fn is_dropped(name: &str, options: &TransformOptions) -> bool {
+    !name.starts_with("...")
+        && options
+            .drop_attributes
+            .iter()
+            .any(|p| matches_pattern(p, name))
+        && !options
+            .keep_attributes
+            .iter()
+            .any(|p| matches_pattern(p, name))
+}
 
-        _ => {
-            // Devs expect attributes in the HTML document to be lowercased.
-            name.to_lowercase()
+// DOM event name of an `on*` handler attribute (`onClick` to `click`,
+// `onDoubleClick` to `dblclick`); `None` for other attributes.
+#[inline]
+fn event_handler_name(name: &str) -> Option<String> {
+    let rest = name.strip_prefix("on")?;
+    if !rest.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    match rest.to_lowercase().as_str() {
+        "doubleclick" => Some("dblclick".to_string()),
+        event => Some(event.to_string()),
+    }
+}
+
+/// Attribute name normalization honoring the options: the user mapping wins,
+/// then the built-in table unless normalization is disabled. Unknown names are
+/// lowercased unless `preserve_unknown_attr_case` is set, the element is in
+/// SVG/MathML (`foreign`), where names are case-sensitive, or the name is a
+/// `data-*` or `aria-*` one and `preserve_data_aria_case` is set. Names
+/// already in their normalized form are borrowed, not copied.
+#[inline]
+pub fn normalize_attr_name_with<'a>(
+    name: &'a str,
+    foreign: bool,
+    options: &'a TransformOptions,
+) -> Cow<'a, str> {
+    if let Some(mapped) = options.attr_name_map.get(name) {
+        return Cow::Borrowed(mapped);
+    }
+    if !options.normalize_attr_names {
+        return Cow::Borrowed(name);
+    }
+    match expected_case(name) {
+        Some(known) => Cow::Borrowed(known),
+        None if options.preserve_unknown_attr_case
+            || foreign
+            || (options.preserve_data_aria_case && is_data_or_aria(name)) =>
+        {
+            Cow::Borrowed(name)
         }
+        None => lowercase(name),
     }
 }
+
+/// Normalizes a JSX attribute name to its HTML form with the default rules:
+/// table lookups (`className` to `class`, `strokeWidth` to `stroke-width`),
+/// otherwise lowercased, `data-*` and `aria-*` names excepted. Names already
+/// in their normalized form are borrowed, not copied.
+#[inline]
+pub fn normalize_html_attr_name(name: &str) -> Cow<'_, str> {
+    // Devs expect attributes in the HTML document to be lowercased.
+    match expected_case(name) {
+        Some(known) => Cow::Borrowed(known),
+        None if is_data_or_aria(name) => Cow::Borrowed(name),
+        None => lowercase(name),
+    }
+}
+
+// A name in lowercase, borrowed when it has no uppercase letter.
+#[inline]
+fn lowercase(name: &str) -> Cow<'_, str> {
+    if name.chars().any(char::is_uppercase) {
+        Cow::Owned(name.to_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+#[inline]
+fn is_data_or_aria(name: &str) -> bool {
+    name.starts_with("data-") || name.starts_with("aria-")
+}
@@ -1,49 +1,631 @@
-use super::errors::JSXError;
+use std::borrow::Cow;
+
+use super::awaits::{any_expression, async_interpolation, contains_await};
+use super::errors::{JSXError, JSXErrorKind};
+use super::form_controls::{escape_text, selected_attribute, take_control_value, SelectValue};
+use super::integrity::csp_hash;
+use super::list_scanner::ListScanner;
+use super::options::{
+    is_raw_expression, ExpressionSite, OutputTarget, TransformOptions, DEFAULT_FRAGMENT_COMPONENT,
+};
+use super::output::{StrippedProp, TransformOutput};
+use super::scoped_css::scope_css_prop;
 use super::tags_attrs::{
-    classify_tag, transform_component_attributes, transform_element_attributes, TagType,
+    classify_tag_with, component_identifier, component_prop_value, is_spread_part, nonce_attribute,
+    transform_component_attributes, transform_element_attributes, ElementContext, TagType,
+};
+use crate::jsx_parser::{
+    walk_node, ExpressionPart, JSXAttribute, JSXAttributeValue, JSXNode, JSXVisitor, ParseError,
+    Parser,
 };
-use crate::jsx_parser::{walk_node, JSXAttribute, JSXNode, JSXVisitor};
 
-// Common constants used across the transformer.
-const OPENING_BRACKET: &str = "<";
+// Capacity of the builder of an element whose children were not measured
+// beforehand, when transforming in a single pass.
+const UNKNOWN_CHILDREN_SIZE: usize = 64;
 
-pub(crate) fn transform_to_template(ast: &JSXNode) -> Result<String, JSXError> {
-    let mut transformer = TemplateTransformer::new_root();
+pub(crate) fn transform_to_template(
+    ast: &JSXNode,
+    options: &TransformOptions,
+    locations: &[(usize, usize)],
+    output: &mut TransformOutput,
+) -> Result<String, JSXError> {
+    let mut transformer = TemplateTransformer::new_root(options, locations, output, ast);
+    transformer.is_async = any_expression(ast, &|code| is_async_code(code, options));
     walk_node(&mut transformer, ast);
-    transformer.finalize()
+    root_template(transformer)
+}
+
+// Transformed node parsed at the start of some source by `parse_to_template`.
+pub(crate) enum ParsedTemplate {
+    // Code of the node, its byte span and its number of nodes
+    Code {
+        code: String,
+        span: (usize, usize),
+        nodes: usize,
+    },
+    // The node uses `await` or streams an iterable, which tags every template
+    // from the root on: only known once parsed, it is transformed from its
+    // tree instead
+    Async,
+}
+
+// `transform_to_template` fused with the parsing of the next JSX node of
+// `source`: the parser drives the transformer as it goes, no tree is built
+// (see `TransformOptions::single_pass`). Parse errors are those of
+// `Parser::parse_next_with_span`; `output` is only complete for a `Code`.
+pub(crate) fn parse_to_template(
+    source: &str,
+    options: &TransformOptions,
+    output: &mut TransformOutput,
+) -> Result<Option<Result<ParsedTemplate, ParseError>>, JSXError> {
+    let mut probe = AsyncProbe {
+        transformer: TemplateTransformer::new(options, &[], output, Vec::new(), 0),
+        seen: false,
+    };
+    let mut parser = Parser::new(source);
+    let span = match parser.parse_next_into(&mut probe) {
+        Some(Ok(span)) => span,
+        Some(Err(e)) => return Ok(Some(Err(e))),
+        None => return Ok(None),
+    };
+    let nodes = parser.node_spans().len();
+    if probe.seen {
+        return Ok(Some(Ok(ParsedTemplate::Async)));
+    }
+    Ok(Some(Ok(ParsedTemplate::Code {
+        code: root_template(probe.transformer)?,
+        span,
+        nodes,
+    })))
+}
+
+// True when expression code makes its root async: it awaits or streams.
+fn is_async_code(code: &str, options: &TransformOptions) -> bool {
+    let mut scanner = ListScanner::new(options);
+    scanner.feed(code);
+    contains_await(code) || scanner.saw_stream_call()
+}
+
+// Code of the root template of a walked node.
+fn root_template(transformer: TemplateTransformer) -> Result<String, JSXError> {
+    let is_async = transformer.is_async;
+    let options = transformer.options;
+    let template = transformer.finalize()?;
+    Ok(match options.output {
+        OutputTarget::Template => {
+            template_literal(&template, is_async.then_some(&*options.async_helper))
+        }
+        // Awaited values are yielded as promises for the consumer to resolve
+        OutputTarget::Stream => stream_generator(&template),
+    })
+}
+
+// Transformer of a root whose tree is not known beforehand, watching the
+// expressions `any_expression` would check: once one is async the output is
+// to be thrown away, so the rest is not transformed.
+struct AsyncProbe<'a> {
+    transformer: TemplateTransformer<'a>,
+    seen: bool,
+}
+
+impl AsyncProbe<'_> {
+    fn check(&mut self, code: &str) -> bool {
+        self.seen = self.seen || is_async_code(code, self.transformer.options);
+        !self.seen
+    }
+}
+
+impl JSXVisitor for AsyncProbe<'_> {
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute]) {
+        let sync = attributes.iter().all(|attr| match &attr.value {
+            Some(JSXAttributeValue::Expression(expr)) => self.check(expr),
+            _ => true,
+        });
+        if sync && !self.seen {
+            self.transformer.enter_element(tag, attributes);
+        }
+    }
+
+    fn exit_element(&mut self, tag: &str) {
+        if !self.seen {
+            self.transformer.exit_element(tag);
+        }
+    }
+
+    fn enter_fragment(&mut self) {
+        if !self.seen {
+            self.transformer.enter_fragment();
+        }
+    }
+
+    fn exit_fragment(&mut self) {
+        if !self.seen {
+            self.transformer.exit_fragment();
+        }
+    }
+
+    fn visit_text(&mut self, text: &str) {
+        if !self.seen {
+            self.transformer.visit_text(text);
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &str) {
+        if self.check(expr) {
+            self.transformer.visit_expression(expr);
+        }
+    }
+
+    fn enter_embedded_expression(&mut self, raw: &str) {
+        if !self.seen {
+            self.transformer.enter_embedded_expression(raw);
+        }
+    }
+
+    fn visit_expression_code(&mut self, code: &str) {
+        if self.check(code) {
+            self.transformer.visit_expression_code(code);
+        }
+    }
+
+    fn exit_embedded_expression(&mut self, raw: &str) {
+        if !self.seen {
+            self.transformer.exit_embedded_expression(raw);
+        }
+    }
+}
+
+// Root template content as a generator yielding its static text and
+// interpolated values in order.
+fn stream_generator(content: &str) -> String {
+    let mut body = String::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    let bytes = content.as_bytes();
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                let Some(end) = interpolation_end(content, i + 2) else {
+                    break;
+                };
+                let text = &content[text_start..i];
+                push_yield(&mut body, &template_literal(text, None), !text.is_empty());
+                let expr = content[i + 2..end].trim();
+                push_yield(&mut body, &format!("({expr})"), !expr.is_empty());
+                text_start = end + 1;
+                i = end;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let rest = &content[text_start..];
+    push_yield(&mut body, &template_literal(rest, None), !rest.is_empty());
+    format!("(function* () {{{body} }}).call(this)")
+}
+
+#[inline]
+fn push_yield(body: &mut String, value: &str, when: bool) {
+    if when {
+        body.push_str(" yield ");
+        body.push_str(value);
+        body.push(';');
+    }
+}
+
+// Byte index of the `}` closing an interpolation whose expression starts at
+// `start`, skipping strings and nested template literals.
+fn interpolation_end(s: &str, start: usize) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'`' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'`' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    } else if bytes[i] == b'$' && bytes.get(i + 1) == Some(&b'{') {
+                        i = interpolation_end(s, i + 2)?;
+                    }
+                    i += 1;
+                }
+            }
+            b'{' => depth += 1,
+            b'}' if depth == 0 => return Some(i),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Renders a static tree straight to HTML: the template it compiles to is
+// cooked as JavaScript would, and any remaining interpolation is an error.
+pub(crate) fn render_static_html(
+    ast: &JSXNode,
+    options: &TransformOptions,
+) -> Result<String, JSXError> {
+    let mut output = TransformOutput::default();
+    let mut transformer = TemplateTransformer::new_root(options, &[], &mut output, ast);
+    walk_node(&mut transformer, ast);
+    let template = transformer.finalize()?;
+    cook_template(&template)
+}
+
+// Evaluates the escape sequences of template literal content.
+fn cook_template(content: &str) -> Result<String, JSXError> {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let Some((_, next)) = chars.next() else {
+                    break;
+                };
+                match next {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'v' => out.push('\u{b}'),
+                    '0' => out.push('\0'),
+                    '\n' => {}
+                    'x' | 'u' => {
+                        let code = cook_code_point(next, &mut chars).ok_or_else(|| {
+                            JSXError::with_kind(JSXErrorKind::UnsupportedSyntax(format!(
+                                "invalid escape sequence at {i}"
+                            )))
+                        })?;
+                        out.push(code);
+                    }
+                    other => out.push(other),
+                }
+            }
+            '$' if chars.peek().is_some_and(|&(_, c)| c == '{') => {
+                let Some(end) = interpolation_end(content, i + 2) else {
+                    return Err(JSXError::with_kind(JSXErrorKind::UnsupportedSyntax(
+                        format!("unterminated interpolation `{}`", &content[i..]),
+                    )));
+                };
+                if !content[i + 2..end].trim().is_empty() {
+                    return Err(JSXError::with_kind(JSXErrorKind::UnsupportedSyntax(
+                        format!("`{}` is not static", &content[i..=end]),
+                    )));
+                }
+                while chars.next_if(|&(j, _)| j <= end).is_some() {}
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+// The character of a `\xHH`, `\uHHHH` or `\u{H...}` escape, after its letter.
+fn cook_code_point(
+    kind: char,
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+) -> Option<char> {
+    let mut hex = String::new();
+    if kind == 'u' && chars.next_if(|&(_, c)| c == '{').is_some() {
+        while let Some((_, c)) = chars.next_if(|&(_, c)| c != '}') {
+            hex.push(c);
+        }
+        chars.next()?;
+    } else {
+        for _ in 0..if kind == 'x' { 2 } else { 4 } {
+            hex.push(chars.next()?.1);
+        }
+    }
+    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+}
+
+// Estimated length of the template of a node: its source text plus the
+// markup and helper calls each node adds, closing tags and
+// `${helper(Name, [{"prop":value}], `...`)}` wrappers. The estimate for the
+// children of each element is pushed to `hints`, in the order the visitor
+// enters elements, so their builders are sized once.
+fn size_hint(node: &JSXNode, options: &TransformOptions, hints: &mut Vec<usize>) -> usize {
+    match node {
+        JSXNode::Element {
+            tag,
+            attributes,
+            children,
+        } => {
+            let index = hints.len();
+            hints.push(0);
+            let attributes: usize = attributes
+                .iter()
+                .map(|attr| {
+                    let value = match &attr.value {
+                        Some(
+                            JSXAttributeValue::DoubleQuote(v)
+                            | JSXAttributeValue::SingleQuote(v)
+                            | JSXAttributeValue::Expression(v),
+                        ) => v.len(),
+                        None => 0,
+                    };
+                    attr.name.len() + value + 8
+                })
+                .sum();
+            let markup = match classify_tag_with(tag, options) {
+                TagType::Component => options.component_helper.len() + tag.len() + 16,
+                _ => 2 * tag.len() + 5,
+            };
+            let children: usize = children
+                .iter()
+                .map(|child| size_hint(child, options, hints))
+                .sum();
+            hints[index] = children;
+            markup + attributes + children
+        }
+        JSXNode::Fragment { children } => children
+            .iter()
+            .map(|child| size_hint(child, options, hints))
+            .sum(),
+        JSXNode::Text(text) => text.len(),
+        JSXNode::Expression(expr) => expr.len() + 3,
+        JSXNode::EmbeddedExpression { parts, .. } => {
+            let parts: usize = parts
+                .iter()
+                .map(|part| match part {
+                    ExpressionPart::Code(code) => code.len(),
+                    ExpressionPart::Jsx { node, .. } => size_hint(node, options, hints) + 2,
+                })
+                .sum();
+            parts + 3
+        }
+    }
+}
+
+// `content` as a template literal, tagged with `tag` when given.
+#[inline]
+fn template_literal(content: &str, tag: Option<&str>) -> String {
+    format!("{}`{content}`", tag.unwrap_or_default())
+}
+
+// Markup namespace of an element. SVG and MathML (foreign content) allow
+// self-closing tags and use case-sensitive attribute names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Namespace {
+    Html,
+    Svg,
+    MathMl,
 }
 
 // Internal stack frames used while visiting the AST.
-enum NodeFrame {
+enum NodeFrame<'a> {
     Element {
         tag: String,
         attrs_str: String,
-        is_void: bool,
+        tag_type: TagType,
+        namespace: Namespace,
         builder: TemplateBuilder,
     },
     Component {
         tag: String,
         attr_parts: String,
+        // Value of an explicit `children` prop, rendered instead of the JSX
+        // children
+        children_prop: Option<String>,
+        // Hydration marker id of an island
+        marker: Option<String>,
         builder: TemplateBuilder,
     },
     Fragment {
         builder: TemplateBuilder,
     },
+    // `<Fragment>` or `<Fragment key={k}>`; `key_parts` holds the props array
+    // when a key is given.
+    NamedFragment {
+        tag: String,
+        key_parts: Option<String>,
+        builder: TemplateBuilder,
+    },
+    // Expression embedding JSX: code chunks are kept verbatim and each nested
+    // subtree is appended as its own template literal. `is_list` is set once a
+    // subtree is found inside the callback of a list method call. `segments`
+    // keeps the unwrapped parts for constant folding.
+    Expression {
+        builder: TemplateBuilder,
+        segments: Vec<ExprSegment>,
+        scanner: ListScanner<'a>,
+        is_list: bool,
+    },
 }
 
-struct TemplateTransformer {
-    stack: Vec<NodeFrame>,
+enum ExprSegment {
+    Code(String),
+    Jsx(String),
+}
+
+struct TemplateTransformer<'a> {
+    stack: Vec<NodeFrame<'a>>,
     error: Option<JSXError>,
+    options: &'a TransformOptions,
+    // Side-channel data (hydration manifest, usage, styles); `code` is set by the caller
+    output: &'a mut TransformOutput,
+    // (line, column) of each element in visit order, for source annotations
+    locations: &'a [(usize, usize)],
+    // Estimated length of the children of each element in visit order, for
+    // the capacity of their builders (see `size_hint`)
+    size_hints: Vec<usize>,
+    elements_seen: usize,
+    // Embedded expressions entered inside one emitted as written (see
+    // `raw_expression_marker`), whose nodes are skipped
+    raw_depth: usize,
+    // The root uses `await`: templates are tagged with the async helper
+    is_async: bool,
+    // Value of each open `<select>`, innermost last
+    selects: Vec<Option<SelectValue>>,
 }
 
-impl TemplateTransformer {
-    fn new_root() -> Self {
+impl<'a> TemplateTransformer<'a> {
+    fn new_root(
+        options: &'a TransformOptions,
+        locations: &'a [(usize, usize)],
+        output: &'a mut TransformOutput,
+        root: &JSXNode,
+    ) -> Self {
+        let mut size_hints = Vec::new();
+        let capacity = size_hint(root, options, &mut size_hints);
+        Self::new(options, locations, output, size_hints, capacity)
+    }
+
+    // Transformer sizing the root builder with `capacity` and those of
+    // elements with `size_hints`, empty when the tree is not known.
+    fn new(
+        options: &'a TransformOptions,
+        locations: &'a [(usize, usize)],
+        output: &'a mut TransformOutput,
+        size_hints: Vec<usize>,
+        capacity: usize,
+    ) -> Self {
         // Root fragment frame to accumulate output even when the root is Text/Expression
         Self {
             stack: vec![NodeFrame::Fragment {
-                builder: TemplateBuilder::new(),
+                builder: TemplateBuilder::with_capacity(capacity),
             }],
+            size_hints,
             error: None,
+            options,
+            output,
+            locations,
+            elements_seen: 0,
+            raw_depth: 0,
+            is_async: false,
+            selects: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn is_stripped(&self, attr: &JSXAttribute) -> bool {
+        self.options.strip_props.contains(&attr.name)
+    }
+
+    // The attributes of an element without its stripped props, recording
+    // them when collected.
+    fn strip_props(&mut self, tag: &str, attributes: &[JSXAttribute]) -> Vec<JSXAttribute> {
+        let (stripped, kept): (Vec<_>, Vec<_>) = attributes
+            .iter()
+            .cloned()
+            .partition(|a| self.is_stripped(a));
+        if self.options.collect_stripped_props {
+            self.output
+                .stripped_props
+                .extend(stripped.into_iter().map(|attribute| StrippedProp {
+                    tag: tag.to_string(),
+                    attribute,
+                }));
+        }
+        kept
+    }
+
+    // Allocates a marker id for a component that starts an island, i.e. one
+    // not rendered inside another component.
+    fn hydration_marker(&mut self, tag: &str) -> Option<String> {
+        if !self.options.hydration_markers
+            || self
+                .stack
+                .iter()
+                .any(|frame| matches!(frame, NodeFrame::Component { .. }))
+        {
+            return None;
+        }
+        let count: usize = self.output.hydration.values().map(Vec::len).sum();
+        let id = format!("{}-{count}", self.options.hydration_id_prefix);
+        self.output
+            .hydration
+            .entry(tag.to_string())
+            .or_default()
+            .push(id.clone());
+        Some(id)
+    }
+
+    // Namespace inherited by children of the innermost open element.
+    // `<foreignObject>` switches its children back to HTML.
+    fn current_namespace(&self) -> Namespace {
+        self.stack
+            .iter()
+            .rev()
+            .find_map(|frame| match frame {
+                NodeFrame::Element { tag, namespace, .. } => {
+                    if *namespace == Namespace::Svg && tag == "foreignObject" {
+                        Some(Namespace::Html)
+                    } else {
+                        Some(*namespace)
+                    }
+                }
+                _ => None,
+            })
+            .unwrap_or(Namespace::Html)
+    }
+
+    #[inline]
+    fn element_namespace(&self, tag: &str) -> Namespace {
+        if !self.options.namespace_aware {
+            return Namespace::Html;
+        }
+        match tag {
+            "svg" => Namespace::Svg,
+            "math" => Namespace::MathMl,
+            _ => self.current_namespace(),
+        }
+    }
+
+    // True when inside an element with one of the given tag names.
+    fn inside(&self, tags: &[&str]) -> bool {
+        self.stack.iter().any(|frame| match frame {
+            NodeFrame::Element { tag, .. } => tags.contains(&tag.as_str()),
+            _ => false,
+        })
+    }
+
+    // Builder for the children of the element at `index` in visit order.
+    fn element_builder(&self, index: usize) -> TemplateBuilder {
+        TemplateBuilder::with_capacity(
+            self.size_hints
+                .get(index)
+                .copied()
+                .unwrap_or(UNKNOWN_CHILDREN_SIZE),
+        )
+    }
+
+    // `data-sxo-source` attribute of the element being entered, if enabled.
+    // The file name is escaped for the attribute and the template text, e.g.
+    // the backslashes of a Windows path.
+    fn source_annotation(&self, index: usize) -> Option<String> {
+        let file = self.options.source_annotations.as_deref()?;
+        let (line, column) = self.locations.get(index)?;
+        let file = file
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('\\', "\\\\")
+            .replace('`', "\\`")
+            .replace("${", "\\${");
+        Some(format!(r#" data-sxo-source="{file}:{line}:{column}""#))
+    }
+
+    // Marks the enclosing embedded expression as a list when a subtree starts
+    // inside a list method callback.
+    #[inline]
+    fn mark_list_context(&mut self) {
+        if let Some(NodeFrame::Expression {
+            scanner, is_list, ..
+        }) = self.stack.last_mut()
+        {
+            *is_list |= scanner.in_list_call();
+            scanner.skip_jsx();
         }
     }
 
@@ -53,6 +635,8 @@ impl TemplateTransformer {
             NodeFrame::Element { builder, .. } => builder,
             NodeFrame::Component { builder, .. } => builder,
             NodeFrame::Fragment { builder } => builder,
+            NodeFrame::NamedFragment { builder, .. } => builder,
+            NodeFrame::Expression { builder, .. } => builder,
         })
     }
 
@@ -63,79 +647,326 @@ impl TemplateTransformer {
                 NodeFrame::Element { builder, .. } => builder.append_child_tpl(s),
                 NodeFrame::Component { builder, .. } => builder.append_child_tpl(s),
                 NodeFrame::Fragment { builder } => builder.append_child_tpl(s),
+                NodeFrame::NamedFragment { builder, .. } => builder.append_child_tpl(s),
+                NodeFrame::Expression {
+                    builder, segments, ..
+                } => {
+                    let tag = self.is_async.then_some(self.options.async_helper.as_str());
+                    match builder.append_nested_tpl(s, tag) {
+                        Some(between) => {
+                            if matches!(segments.last(), Some(ExprSegment::Code(_))) {
+                                segments.pop();
+                            }
+                            if let Some(ExprSegment::Jsx(previous)) = segments.last_mut() {
+                                previous.push_str(&between);
+                                previous.push_str(s);
+                            }
+                        }
+                        None => segments.push(ExprSegment::Jsx(s.to_string())),
+                    }
+                }
             }
         }
     }
 
+    // Appends a rendered element or fragment; in pretty mode it goes on its own
+    // line, indented by nesting depth.
+    #[inline]
+    fn append_child_to_parent(&mut self, s: &str) {
+        if !self.options.pretty {
+            return self.append_to_parent(s);
+        }
+        let indent = self.indent();
+        match self.stack.last_mut() {
+            Some(
+                NodeFrame::Element { builder, .. }
+                | NodeFrame::Component { builder, .. }
+                | NodeFrame::Fragment { builder }
+                | NodeFrame::NamedFragment { builder, .. },
+            ) => builder.append_block(s, &indent),
+            _ => self.append_to_parent(s),
+        }
+    }
+
+    // Pretty mode indentation of a frame at the top of the stack after the
+    // root (and after popping it when rendering).
+    #[inline]
+    fn indent(&self) -> String {
+        "  ".repeat(self.stack.len().saturating_sub(1))
+    }
+
     fn finalize(mut self) -> Result<String, JSXError> {
         if let Some(err) = self.error.take() {
             return Err(err);
         }
         // At this point only the root fragment frame should remain
-        match self.stack.pop() {
-            Some(NodeFrame::Fragment { builder }) => Ok(builder.finalize()),
-            Some(NodeFrame::Element {
+        Ok(self
+            .stack
+            .pop()
+            .map(|frame| self.render_frame(frame))
+            .unwrap_or_default())
+    }
+
+    // `${helper(Identifier, [props], children)}`, `children` being the code
+    // of the third argument. Awaited props, `<A x={await y} />`, make the call
+    // a promise the async template tag resolves, as awaited children are.
+    fn render_component(
+        &self,
+        identifier: &str,
+        attr_parts: &str,
+        children: Option<String>,
+        awaits: bool,
+    ) -> String {
+        let helper = &self.options.component_helper;
+        let call = match children {
+            None => format!("{helper}({identifier}, {attr_parts})"),
+            Some(children) => format!("{helper}({identifier}, {attr_parts}, {children})"),
+        };
+        if awaits {
+            format!("${{(async () => ({call}))()}}")
+        } else {
+            format!("${{{call}}}")
+        }
+    }
+
+    // Template literal of rendered children, None when there are none.
+    fn children_argument(&self, children: String) -> Option<String> {
+        if children.is_empty() {
+            return None;
+        }
+        let tag = self.is_async.then_some(&*self.options.async_helper);
+        Some(template_literal(&children, tag))
+    }
+
+    // Hashes the body of a rendered inline `<script>` when it is static: no
+    // `src`, spread or interpolated attributes and no interpolated content.
+    // Bodies that do not cook, e.g. with an unterminated `${`, are skipped.
+    fn record_script_hash(&mut self, open: &str, rendered: &str) {
+        if open.contains(" src=") || open.contains("${") {
+            return;
+        }
+        let body = rendered
+            .strip_prefix(open)
+            .and_then(|rest| rest.strip_suffix("</script>"));
+        if let Some(Ok(body)) = body.map(cook_template) {
+            if !body.is_empty() {
+                self.output.add_script_hash(csp_hash(&body));
+            }
+        }
+    }
+
+    // Renders a completed frame into its template form.
+    fn render_frame(&self, frame: NodeFrame) -> String {
+        match frame {
+            NodeFrame::Fragment { builder } => builder.finalize(),
+            NodeFrame::Expression { builder, .. } => builder.finalize_code(),
+            NodeFrame::Element {
                 tag,
                 attrs_str,
-                is_void,
+                tag_type,
+                namespace,
                 builder,
-            }) => {
-                if is_void {
-                    Ok(format!("<{tag}{attrs_str}/>"))
+            } => {
+                let children = if self.options.pretty {
+                    builder.finalize_pretty(&self.indent())
                 } else {
-                    Ok(format!("<{tag}{attrs_str}>{}</{tag}>", builder.finalize()))
+                    builder.finalize()
+                };
+                let self_close = match tag_type {
+                    // Foreign content allows any childless element to self-close
+                    _ if namespace != Namespace::Html => children.is_empty(),
+                    TagType::Void => true,
+                    TagType::WebComponent => {
+                        children.is_empty() && !self.options.close_custom_elements
+                    }
+                    _ => false,
+                };
+                if self_close && tag_type == TagType::Void && self.options.minify {
+                    // HTML void elements need no self-closing slash
+                    format!("<{tag}{attrs_str}>")
+                } else if self_close {
+                    format!("<{tag}{attrs_str}/>")
+                } else {
+                    format!("<{tag}{attrs_str}>{children}</{tag}>")
                 }
             }
-            Some(NodeFrame::Component {
+            NodeFrame::NamedFragment {
+                key_parts, builder, ..
+            } => match (key_parts, &self.options.fragment_component) {
+                (None, None) => builder.finalize(),
+                (key_parts, fragment) => self.render_component(
+                    fragment.as_deref().unwrap_or(DEFAULT_FRAGMENT_COMPONENT),
+                    key_parts.as_deref().unwrap_or("[]"),
+                    self.children_argument(builder.finalize()),
+                    key_parts.as_deref().is_some_and(contains_await),
+                ),
+            },
+            NodeFrame::Component {
                 tag,
                 attr_parts,
+                children_prop,
+                marker,
                 builder,
-            }) => {
-                let children_str = builder.finalize();
-                if children_str.is_empty() {
-                    Ok(format!(r#"${{__jsxComponent({tag}, {attr_parts})}}"#))
-                } else {
-                    Ok(format!(
-                        r#"${{__jsxComponent({}, {}, `{}`)}}"#,
-                        tag,
-                        attr_parts,
-                        children_str.trim()
-                    ))
+            } => {
+                let identifier = component_identifier(&tag);
+                let awaits = contains_await(&attr_parts)
+                    || children_prop.as_deref().is_some_and(contains_await);
+                let children = children_prop.or_else(|| self.children_argument(builder.finalize()));
+                let rendered = self.render_component(&identifier, &attr_parts, children, awaits);
+                match marker {
+                    Some(id) => format!("<!--sxo:{tag}:{id}-->{rendered}<!--/sxo:{id}-->"),
+                    None => rendered,
                 }
             }
-            None => Ok(String::new()),
         }
     }
 }
 
-impl JSXVisitor for TemplateTransformer {
+impl JSXVisitor for TemplateTransformer<'_> {
     fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute]) {
         if self.error.is_some() {
             return;
         }
+        let index = self.elements_seen;
+        self.elements_seen += 1;
+        if self.raw_depth > 0 {
+            return;
+        }
+        self.mark_list_context();
 
-        match classify_tag(tag) {
-            TagType::Component => match transform_component_attributes(attributes) {
-                Ok(attr_parts) => {
-                    self.stack.push(NodeFrame::Component {
-                        tag: tag.to_string(),
-                        attr_parts,
-                        builder: TemplateBuilder::new(),
-                    });
+        if self.options.named_fragments && is_named_fragment(tag, attributes) {
+            let key_parts = if attributes.is_empty() {
+                None
+            } else {
+                match transform_component_attributes(tag, attributes, self.options, self.output) {
+                    Ok(parts) => Some(parts),
+                    Err(e) => {
+                        self.error = Some(e);
+                        return;
+                    }
                 }
-                Err(e) => {
-                    self.error = Some(e);
+            };
+            self.stack.push(NodeFrame::NamedFragment {
+                tag: tag.to_string(),
+                key_parts,
+                builder: self.element_builder(index),
+            });
+            return;
+        }
+
+        let tag_type = classify_tag_with(tag, self.options);
+        match tag_type {
+            TagType::Component => {
+                // `children` is the third argument of the helper, as in React;
+                // the attributes are only copied to take it out
+                let children_prop = attributes
+                    .iter()
+                    .rfind(|a| a.name == "children")
+                    .map(|a| component_prop_value(a, self.options, self.output))
+                    .transpose();
+                let children_prop = match children_prop {
+                    Ok(children_prop) => children_prop,
+                    Err(e) => {
+                        self.error = Some(e);
+                        return;
+                    }
+                };
+                let props = match children_prop {
+                    Some(_) => Cow::Owned(
+                        attributes
+                            .iter()
+                            .filter(|a| a.name != "children")
+                            .cloned()
+                            .collect(),
+                    ),
+                    None => Cow::Borrowed(attributes),
+                };
+                match transform_component_attributes(tag, &props, self.options, self.output) {
+                    Ok(attr_parts) => {
+                        self.output.usage.add_component(&component_identifier(tag));
+                        let marker = self.hydration_marker(tag);
+                        self.stack.push(NodeFrame::Component {
+                            tag: tag.to_string(),
+                            attr_parts,
+                            children_prop,
+                            marker,
+                            builder: self.element_builder(index),
+                        });
+                    }
+                    Err(e) => {
+                        self.error = Some(e);
+                    }
                 }
-            },
+            }
             _ => {
                 // Normal element or web component
-                match transform_element_attributes(attributes) {
+                if tag_type == TagType::WebComponent {
+                    self.output.usage.add_web_component(tag);
+                }
+                let namespace = self.element_namespace(tag);
+                let foreign = namespace != Namespace::Html;
+                // An unquoted value would swallow the `/` of a self-closed tag
+                let self_closes =
+                    tag_type == TagType::WebComponent && !self.options.close_custom_elements;
+                let mut attributes = Cow::Borrowed(attributes);
+                if attributes.iter().any(|a| self.is_stripped(a)) {
+                    attributes = Cow::Owned(self.strip_props(tag, &attributes));
+                }
+                let mut control_value = None;
+                if self.options.form_control_values
+                    && !foreign
+                    && matches!(tag, "textarea" | "select")
+                {
+                    if let Some((value, rest)) = take_control_value(&attributes) {
+                        control_value = Some(value);
+                        attributes = Cow::Owned(rest);
+                    }
+                }
+                let selected = match self.selects.last() {
+                    Some(Some(select)) if tag == "option" => {
+                        selected_attribute(select, &attributes)
+                    }
+                    _ => None,
+                };
+                if tag == "select" {
+                    let multiple = attributes.iter().any(|a| a.name == "multiple");
+                    self.selects.push(
+                        control_value
+                            .take()
+                            .map(|value| SelectValue { value, multiple }),
+                    );
+                }
+                if self.options.scoped_css {
+                    match scope_css_prop(tag, &attributes) {
+                        Ok(Some(style)) => {
+                            self.output.styles.entry(style.class).or_insert(style.css);
+                            attributes = Cow::Owned(style.attributes);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            self.error = Some(e);
+                            return;
+                        }
+                    }
+                }
+                if let Some(nonce) = nonce_attribute(tag, &attributes, self.options) {
+                    attributes.to_mut().push(nonce);
+                }
+                let element = ElementContext {
+                    tag,
+                    foreign,
+                    unquote: self.options.minify && !foreign && !self_closes,
+                };
+                match transform_element_attributes(&attributes, &element, self.options, self.output)
+                {
                     Ok(attrs) => {
                         let attrs_str = if !attrs.is_empty() {
                             attrs
                                 .iter()
                                 .map(|attr| {
-                                    if attr.starts_with("${__jsxSpread") {
+                                    if is_spread_part(attr, self.options)
+                                        && !self.options.spread_leading_space
+                                    {
                                         attr.to_string()
                                     } else {
                                         format!(" {attr}")
@@ -145,14 +976,30 @@ impl JSXVisitor for TemplateTransformer {
                         } else {
                             String::new()
                         };
+                        let attrs_str = attrs_str + selected.as_deref().unwrap_or_default();
+                        let attrs_str = match self.source_annotation(index) {
+                            Some(annotation) => attrs_str + &annotation,
+                            None => attrs_str,
+                        };
 
-                        let is_void = matches!(classify_tag(tag), TagType::Void);
                         self.stack.push(NodeFrame::Element {
                             tag: tag.to_string(),
                             attrs_str,
-                            is_void,
-                            builder: TemplateBuilder::new(),
+                            tag_type,
+                            namespace,
+                            builder: self.element_builder(index),
                         });
+                        // The value of a `<textarea>` is its content
+                        match control_value {
+                            Some(JSXAttributeValue::Expression(expr)) => {
+                                self.visit_expression(&expr)
+                            }
+                            Some(
+                                JSXAttributeValue::DoubleQuote(text)
+                                | JSXAttributeValue::SingleQuote(text),
+                            ) => self.visit_text(&escape_text(&text)),
+                            None => {}
+                        }
                     }
                     Err(e) => {
                         self.error = Some(e);
@@ -163,75 +1010,71 @@ impl JSXVisitor for TemplateTransformer {
     }
 
     fn exit_element(&mut self, tag: &str) {
-        if self.error.is_some() {
+        if self.error.is_some() || self.raw_depth > 0 {
             return;
         }
 
+        if tag == "select"
+            && self
+                .stack
+                .last()
+                .is_some_and(|f| matches!(f, NodeFrame::Element { .. }))
+        {
+            self.selects.pop();
+        }
+
         // Pop current frame and append its rendered output to the parent
-        let frame = self.stack.pop();
-        if let Some(frame) = frame {
-            match frame {
-                NodeFrame::Element {
-                    tag: frame_tag,
-                    attrs_str,
-                    is_void,
-                    builder,
-                } => {
-                    debug_assert_eq!(frame_tag, tag);
-                    let rendered = if is_void {
-                        format!("<{tag}{attrs_str}/>")
-                    } else {
-                        format!("<{tag}{attrs_str}>{}</{tag}>", builder.finalize())
-                    };
-                    self.append_to_parent(&rendered);
-                }
-                NodeFrame::Component {
-                    tag: frame_tag,
-                    attr_parts,
-                    builder,
-                } => {
-                    debug_assert_eq!(frame_tag, tag);
-                    let children_str = builder.finalize();
-                    let rendered = if children_str.is_empty() {
-                        format!(r#"${{__jsxComponent({tag}, {attr_parts})}}"#)
-                    } else {
-                        format!(
-                            r#"${{__jsxComponent({}, {}, `{}`)}}"#,
-                            tag,
-                            attr_parts,
-                            children_str.trim()
-                        )
-                    };
-                    self.append_to_parent(&rendered);
-                }
-                NodeFrame::Fragment { .. } => {
-                    // Not possible in exit_element
+        if let Some(frame) = self.stack.pop() {
+            debug_assert!(matches!(
+                &frame,
+                NodeFrame::Element { tag: t, .. }
+                    | NodeFrame::Component { tag: t, .. }
+                    | NodeFrame::NamedFragment { tag: t, .. } if t == tag
+            ));
+            let script = match &frame {
+                NodeFrame::Element { attrs_str, .. } if tag == "script" => {
+                    Some(format!("<script{attrs_str}>"))
                 }
+                _ => None,
+            };
+            let rendered = self.render_frame(frame);
+            if let Some(open) = script.filter(|_| self.options.script_hashes) {
+                self.record_script_hash(&open, &rendered);
             }
+            self.append_child_to_parent(&rendered);
         }
     }
 
     fn enter_fragment(&mut self) {
-        if self.error.is_some() {
+        if self.error.is_some() || self.raw_depth > 0 {
             return;
         }
+        self.mark_list_context();
         self.stack.push(NodeFrame::Fragment {
             builder: TemplateBuilder::new(),
         });
     }
 
     fn exit_fragment(&mut self) {
-        if self.error.is_some() {
+        if self.error.is_some() || self.raw_depth > 0 {
             return;
         }
         if let Some(NodeFrame::Fragment { builder }) = self.stack.pop() {
-            let rendered = builder.finalize();
-            self.append_to_parent(&rendered);
+            let rendered = match &self.options.fragment_component {
+                Some(fragment) => self.render_component(
+                    fragment,
+                    "[]",
+                    self.children_argument(builder.finalize()),
+                    false,
+                ),
+                None => builder.finalize(),
+            };
+            self.append_child_to_parent(&rendered);
         }
     }
 
     fn visit_text(&mut self, text: &str) {
-        if self.error.is_some() {
+        if self.error.is_some() || self.raw_depth > 0 {
             return;
         }
 
@@ -242,54 +1085,146 @@ impl JSXVisitor for TemplateTransformer {
         // <A></A>
         //     <B/>
         // which previously produced a single space text node.
-        if text.contains('\n') && text.trim().is_empty() {
-            let mut preserve = false;
-            for frame in &self.stack {
-                if let NodeFrame::Element { tag, .. } = frame {
-                    if tag == "pre" || tag == "textarea" {
-                        preserve = true;
-                        break;
-                    }
-                }
-            }
-            if !preserve {
+        if text.contains('\n') && text.trim().is_empty() && !self.inside(&["pre", "textarea"]) {
+            return;
+        }
+
+        // Minify: drop whitespace-only children and collapse whitespace runs,
+        // except where whitespace is significant
+        if self.options.minify && !self.inside(&["pre", "textarea", "script", "style"]) {
+            if text.trim().is_empty() {
                 return;
             }
+            let collapsed = collapse_whitespace(text);
+            if let Some(b) = self.current_builder_mut() {
+                b.push_text(&collapsed);
+            }
+            return;
         }
 
+        // Pretty mode lays out lines itself; source line breaks are dropped as in JSX
+        let text = if self.options.pretty && text.contains('\n') {
+            text.trim()
+        } else {
+            text
+        };
         if let Some(b) = self.current_builder_mut() {
             b.push_text(text);
         }
     }
 
     fn visit_expression(&mut self, expr: &str) {
-        if self.error.is_some() {
+        if self.error.is_some() || self.raw_depth > 0 {
             return;
         }
 
-        // Filter out JSX comments written as block comments inside expressions: {/* ... */}
-        if is_js_block_comment_only(expr) {
+        // Empty expressions and JSX comments, `{}` and `{/* ... */}`, render nothing
+        if is_empty_expression(expr) {
             return;
         }
+        let rewritten = self
+            .options
+            .rewrite_expression
+            .map(|rewrite| rewrite(ExpressionSite::Child, expr));
+        let expr = rewritten.as_deref().unwrap_or(expr);
 
+        // Without JSX, no callback returns templates: list calls are values,
+        // e.g. `name.slice(0, 3)`, and only stream calls are wrapped
+        let mut scanner = ListScanner::new(self.options);
+        scanner.feed(expr);
+        let list = scanner
+            .saw_stream_call()
+            .then(|| format!("{}({expr})", self.options.stream_helper));
+        // Awaited values become promises resolved by the async template tag
+        let list = match list {
+            Some(list) if contains_await(expr) => Some(async_interpolation(&list).into_owned()),
+            None if contains_await(expr) => Some(async_interpolation(expr).into_owned()),
+            list => list,
+        };
+        let fold = self.options.fold_constants;
         if let Some(b) = self.current_builder_mut() {
-            if expr.contains(OPENING_BRACKET) {
-                match super::jsx_transformer(expr) {
-                    Ok(nested) => {
-                        let nested_trim = nested.trim();
-                        if needs_list_wrapper(expr) {
-                            b.append_child_tpl(&format!("${{__jsxList({})}}", nested_trim));
-                        } else {
-                            b.append_child_tpl(&format!("${{{}}}", nested_trim));
-                        }
-                    }
-                    Err(e) => {
-                        self.error = Some(e);
-                    }
+            b.push_expr(expr, list.as_deref(), fold);
+        }
+    }
+
+    fn enter_embedded_expression(&mut self, raw: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.raw_depth > 0 {
+            self.raw_depth += 1;
+            return;
+        }
+        if is_raw_expression(raw, self.options) {
+            self.visit_expression(raw);
+            self.raw_depth = 1;
+            return;
+        }
+        self.stack.push(NodeFrame::Expression {
+            builder: TemplateBuilder::new(),
+            segments: Vec::new(),
+            scanner: ListScanner::new(self.options),
+            is_list: false,
+        });
+    }
+
+    fn visit_expression_code(&mut self, code: &str) {
+        if self.error.is_some() || self.raw_depth > 0 {
+            return;
+        }
+        if let Some(NodeFrame::Expression {
+            builder,
+            segments,
+            scanner,
+            ..
+        }) = self.stack.last_mut()
+        {
+            scanner.feed(code);
+            builder.push_text(code);
+            segments.push(ExprSegment::Code(code.to_string()));
+        }
+    }
+
+    fn exit_embedded_expression(&mut self, _raw: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.raw_depth > 0 {
+            self.raw_depth -= 1;
+            return;
+        }
+        if let Some(NodeFrame::Expression {
+            builder,
+            segments,
+            scanner,
+            is_list,
+        }) = self.stack.pop()
+        {
+            if self.options.fold_constants {
+                if let Some(branch) = fold_condition(&segments) {
+                    self.append_to_parent(branch);
+                    return;
                 }
-            } else {
-                b.push_expr(expr);
             }
+            let nested = builder.finalize_code();
+            let nested = if scanner.saw_stream_call() {
+                format!("{}({nested})", self.options.stream_helper)
+            } else if is_list && !scanner.list_consumed() {
+                self.options
+                    .list_mode
+                    .wrap(&nested, scanner.ends_with_list_call())
+            } else {
+                nested
+            };
+            let awaits = segments
+                .iter()
+                .any(|segment| matches!(segment, ExprSegment::Code(code) if contains_await(code)));
+            let rendered = if awaits {
+                format!("${{(async () => ({nested}))()}}")
+            } else {
+                format!("${{{nested}}}")
+            };
+            self.append_to_parent(&rendered);
         }
     }
 }
@@ -300,32 +1235,54 @@ struct TemplateBuilder {
     // Reserved for future: track if any array-ish expressions were encountered.
     #[allow(dead_code)]
     has_array: bool,
+    // Pretty mode: a child was placed on its own line
+    has_blocks: bool,
+    // End of the last nested template of an expression, while only
+    // whitespace follows it
+    nested_end: Option<usize>,
 }
 
 impl TemplateBuilder {
     fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
         Self {
-            out: String::new(),
+            out: String::with_capacity(capacity),
             has_array: false,
+            has_blocks: false,
+            nested_end: None,
         }
     }
 
     #[inline]
     fn push_text(&mut self, s: &str) {
+        if !s.trim().is_empty() {
+            self.nested_end = None;
+        }
         self.out.push_str(s);
     }
 
+    // `list` holds the already wrapped form when the expression is a list.
+    // With `fold`, statically known strings are inlined as text.
     #[inline]
-    fn push_expr(&mut self, expr: &str) {
+    fn push_expr(&mut self, expr: &str, list: Option<&str>, fold: bool) {
+        if fold {
+            if let Some(text) = fold_literal(expr) {
+                self.out.push_str(&text);
+                return;
+            }
+        }
         // Flatten the trivial case where the expression is a backtick string with a single interpolation: `${inner}`
         if let Some(inner) = extract_single_expr_from_backtick(expr) {
             self.out.push_str("${");
             self.out.push_str(inner);
             self.out.push('}');
-        } else if needs_list_wrapper(expr) {
-            self.out.push_str("${__jsxList(");
-            self.out.push_str(expr);
-            self.out.push_str(")}");
+        } else if let Some(list) = list {
+            self.out.push_str("${");
+            self.out.push_str(list);
+            self.out.push('}');
         } else {
             self.out.push_str("${");
             self.out.push_str(expr);
@@ -342,144 +1299,283 @@ impl TemplateBuilder {
         }
     }
 
+    // Appends a nested JSX subtree of an embedded expression as its own template
+    // literal; `tag` is the async helper when the root uses `await`. A root
+    // right after another one, `{cond && <dt/><dd/>}`, joins its template as
+    // a fragment child would, instead of tagging it; the whitespace between
+    // them is returned then, None otherwise.
+    #[inline]
+    fn append_nested_tpl(&mut self, tpl_like: &str, tag: Option<&str>) -> Option<String> {
+        let flat = flatten_trivial_nested_child(tpl_like);
+        let tpl = flat.as_deref().unwrap_or(tpl_like).trim();
+        let joined = self.nested_end.map(|end| {
+            // Whitespace with a line break is indentation
+            let between = self.out.split_off(end);
+            self.out.pop();
+            if between.contains('\n') {
+                String::new()
+            } else {
+                between
+            }
+        });
+        match &joined {
+            Some(between) => {
+                self.out.push_str(between);
+                self.out.push_str(tpl);
+                self.out.push('`');
+            }
+            None => self.out.push_str(&template_literal(tpl, tag)),
+        }
+        self.nested_end = Some(self.out.len());
+        joined
+    }
+
+    // Pretty mode: appends a child on its own line at the given indentation.
+    #[inline]
+    fn append_block(&mut self, tpl_like: &str, indent: &str) {
+        self.out.truncate(self.out.trim_end().len());
+        self.out.push('\n');
+        self.out.push_str(indent);
+        self.append_child_tpl(tpl_like);
+        self.has_blocks = true;
+    }
+
+    // Children of an element, fragment or component. Whitespace-only text
+    // nodes were already dropped by `visit_text` as the whitespace mode says;
+    // here only the indentation around the children goes: leading and trailing
+    // whitespace containing a line break. Other whitespace is content, e.g.
+    // the spaces of `<b>bold</b> text` or `<p> a </p>`.
     #[inline]
     fn finalize(self) -> String {
+        trim_indentation(&self.out).to_string()
+    }
+
+    // Code of an embedded expression, with the JSX nested in it.
+    #[inline]
+    fn finalize_code(self) -> String {
         self.out.trim().to_string()
     }
+
+    // Pretty mode: like `finalize`, but keeps block children on their own
+    // lines and puts the parent's closing tag on a line at `indent`.
+    #[inline]
+    fn finalize_pretty(self, indent: &str) -> String {
+        if self.has_blocks {
+            format!("{}\n{indent}", self.out.trim_end())
+        } else {
+            self.finalize()
+        }
+    }
 }
 
-// Filter: true when an expression is exactly a JSX block comment (e.g., {/* ... */})
-#[inline]
-fn is_js_block_comment_only(s: &str) -> bool {
-    let t = s.trim();
-    t.len() >= 4 && t.starts_with("/*") && t.ends_with("*/")
+// Whether an expression holds no code, only whitespace and comments: `{}`,
+// or a JSX comment `{/* ... */}`.
+pub(crate) fn is_empty_expression(s: &str) -> bool {
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        rest = if let Some(comment) = rest.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => comment[end + 2..].trim_start(),
+                None => return false,
+            }
+        } else if let Some(comment) = rest.strip_prefix("//") {
+            comment
+                .find('\n')
+                .map_or("", |end| comment[end..].trim_start())
+        } else {
+            return false;
+        };
+    }
+    true
 }
 
-// Scans an expression to determine if it should be wrapped with __jsxList(...).
-// It ignores content inside strings and template literals, and detects calls on
-// array-producing/copying methods (map/flatMap/filter/concat/flat/...).
-#[inline]
-fn needs_list_wrapper(s: &str) -> bool {
-    // Minimal scanner that ignores strings/templates and detects array-producing/copying calls.
-    let mut in_squote = false;
-    let mut in_dquote = false;
-    let mut in_tmpl = false;
-    let mut escape = false;
+// `<Fragment>` / `<React.Fragment>` with at most a `key`; other props mean a
+// user component that happens to be named `Fragment`.
+fn is_named_fragment(tag: &str, attributes: &[JSXAttribute]) -> bool {
+    matches!(tag, "Fragment" | "React.Fragment")
+        && attributes
+            .iter()
+            .all(|attr| attr.name == "key" && attr.value.is_some())
+}
 
-    let mut iter = s.chars().peekable();
+// Strips leading and trailing whitespace that contains a line break.
+fn trim_indentation(s: &str) -> &str {
+    let start = s.len() - s.trim_start().len();
+    let s = if s[..start].contains('\n') {
+        &s[start..]
+    } else {
+        s
+    };
+    let end = s.trim_end().len();
+    if s[end..].contains('\n') {
+        &s[..end]
+    } else {
+        s
+    }
+}
 
-    while let Some(ch) = iter.next() {
-        if escape {
-            escape = false;
-            continue;
-        }
-        match ch {
-            '\\' => {
-                escape = true;
-                continue;
-            }
-            '\'' if !in_dquote && !in_tmpl => {
-                in_squote = !in_squote;
-                continue;
-            }
-            '"' if !in_squote && !in_tmpl => {
-                in_dquote = !in_dquote;
-                continue;
+// Replaces each run of whitespace with a single space.
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
             }
-            '`' if !in_squote && !in_dquote => {
-                in_tmpl = !in_tmpl;
-                continue;
-            }
-            _ => {}
-        }
-
-        if in_squote || in_dquote || in_tmpl {
-            continue;
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
         }
+    }
+    out
+}
 
-        // Detect ".name" or "?.name"
-        let mut at_property = false;
-        if ch == '.' {
-            at_property = true;
-        } else if ch == '?' {
-            if let Some('.') = iter.peek().copied() {
-                iter.next();
-                at_property = true;
+// Statically decidable conditionals: `true && <A/>`, `false && <A/>`,
+// `true ? <A/> : <B/>` and `false ? <A/> : <B/>`. Returns the template of the
+// branch that renders; a false `&&` renders nothing, as in JSX.
+fn fold_condition(segments: &[ExprSegment]) -> Option<&str> {
+    let compact = |code: &str| code.split_whitespace().collect::<String>();
+    let segments = match segments {
+        [rest @ .., ExprSegment::Code(tail)] if tail.trim().is_empty() => rest,
+        _ => segments,
+    };
+    match segments {
+        [ExprSegment::Code(cond), ExprSegment::Jsx(then)] => match compact(cond).as_str() {
+            "true&&" => Some(then),
+            "false&&" => Some(""),
+            _ => None,
+        },
+        [ExprSegment::Code(cond), ExprSegment::Jsx(then), ExprSegment::Code(sep), ExprSegment::Jsx(otherwise)]
+            if sep.trim() == ":" =>
+        {
+            match compact(cond).as_str() {
+                "true?" => Some(then),
+                "false?" => Some(otherwise),
+                _ => None,
             }
         }
+        _ => None,
+    }
+}
 
-        if at_property {
-            // Read identifier: first char [A-Za-z_$], subsequent [A-Za-z0-9_$]
-            let mut name = String::new();
-            if let Some(&c0) = iter.peek() {
-                if c0.is_ascii_alphabetic() || c0 == '_' || c0 == '$' {
-                    name.push(c0);
-                    iter.next();
-                    while let Some(&cn) = iter.peek() {
-                        if cn.is_ascii_alphanumeric() || cn == '_' || cn == '$' {
-                            name.push(cn);
-                            iter.next();
-                        } else {
-                            break;
-                        }
-                    }
-                }
-            }
+// Template text of a statically known string: a string or number literal, or
+// a template literal whose interpolations are such literals. Whitespace-only
+// results are left alone, as `{" "}` must survive the trimming of children.
+fn fold_literal(expr: &str) -> Option<String> {
+    let t = expr.trim();
+    let text = fold_number(t)
+        .or_else(|| fold_quoted(t))
+        .or_else(|| fold_template(t))?;
+    if !text.is_empty() && text.trim().is_empty() {
+        return None;
+    }
+    Some(text)
+}
 
-            // Skip whitespace before call indicator
-            let mut la = iter.clone();
-            while let Some(&c) = la.peek() {
-                if c.is_whitespace() {
-                    la.next();
-                } else {
-                    break;
+// A decimal literal that JavaScript prints exactly as written (no leading or
+// trailing zeros, no exponent, within double precision).
+pub(crate) fn fold_number(t: &str) -> Option<String> {
+    let digits = t.strip_prefix('-').unwrap_or(t);
+    let (int, frac) = match digits.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (digits, ""),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let int_ok = int == "0" || (!int.is_empty() && !int.starts_with('0') && all_digits(int));
+    let frac_ok = !digits.ends_with('.') && all_digits(frac) && !frac.ends_with('0');
+    // Below 1e-6 JavaScript switches to exponent notation
+    let small_ok = int != "0" || frac.len() - frac.trim_start_matches('0').len() < 6;
+    if int_ok && frac_ok && small_ok && int.len() + frac.len() <= 15 && t != "-0" {
+        Some(t.to_string())
+    } else {
+        None
+    }
+}
+
+// A single- or double-quoted string literal rewritten as template text. Escape
+// sequences carry over unchanged; legacy octal escapes are rejected since
+// templates do not allow them.
+pub(crate) fn fold_quoted(t: &str) -> Option<String> {
+    let quote = t.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+    let inner = t.strip_prefix(quote)?.strip_suffix(quote)?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let next = chars.next()?;
+                if next.is_ascii_digit()
+                    && (next != '0' || chars.peek().is_some_and(|c| c.is_ascii_digit()))
+                {
+                    return None;
                 }
+                out.push('\\');
+                out.push(next);
             }
+            '\n' => return None,
+            c if c == quote => return None,
+            '`' => out.push_str("\\`"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
 
-            // Allow "(" or "?.("
-            let is_call = match la.peek().copied() {
-                Some('(') => true,
-                Some('?') => {
-                    la.next();
-                    if let Some('.') = la.peek().copied() {
-                        la.next();
-                        matches!(la.peek().copied(), Some('('))
-                    } else {
-                        false
+// A template literal whose interpolations all fold, e.g. `Hi ${"there"}`.
+fn fold_template(t: &str) -> Option<String> {
+    let inner = t.strip_prefix('`')?.strip_suffix('`')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.push(chars.next()?.1);
+            }
+            '`' => return None,
+            '$' if chars.peek().is_some_and(|&(_, c)| c == '{') => {
+                chars.next();
+                let start = i + 2;
+                let mut depth = 1;
+                let mut quote: Option<char> = None;
+                let mut end = None;
+                while let Some((j, c)) = chars.next() {
+                    match (quote, c) {
+                        (Some(_), '\\') => {
+                            chars.next();
+                        }
+                        (Some(q), c) if c == q => quote = None,
+                        (Some(_), _) => {}
+                        (None, '"' | '\'') => quote = Some(c),
+                        (None, '`') => return None,
+                        (None, '{') => depth += 1,
+                        (None, '}') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                end = Some(j);
+                                break;
+                            }
+                        }
+                        _ => {}
                     }
                 }
-                _ => false,
-            };
-
-            if is_call
-                && matches!(
-                    name.as_str(),
-                    // Core sequence builders
-                    "map" | "flatMap" | "filter" |
-                    // Array-producing transforms
-                    "slice" | "concat" | "flat" |
-                    // Modern immutable array-producing
-                    "toReversed" | "toSorted" | "toSpliced" | "with" |
-                    // Mutating but array-returning
-                    "reverse" | "sort" | "splice" | "fill" | "copyWithin" |
-                    // Scalar reducers (kept for compatibility)
-                    "reduce" | "reduceRight" |
-                    // Compatibility: returns undefined but kept for parity
-                    "forEach"
-                )
-            {
-                return true;
+                let value = inner[start..end?].trim();
+                out.push_str(&fold_number(value).or_else(|| fold_quoted(value))?);
             }
+            c => out.push(c),
         }
     }
-    false
+    Some(out)
 }
 
 // Returns Some(inner) when input is a backtick template containing only a single interpolation: `${inner}`
 #[inline]
 fn extract_single_expr_from_backtick(expr: &str) -> Option<&str> {
     let s = expr.trim();
-    if s.starts_with('`') && s.ends_with('`') {
+    if s.len() >= 2 && s.starts_with('`') && s.ends_with('`') {
         let inner = &s[1..s.len() - 1];
         let trimmed = inner.trim();
         if trimmed.starts_with("${")
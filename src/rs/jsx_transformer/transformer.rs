@@ -0,0 +1,62 @@
+//! A transformer built once from its options and shared by any number of
+//! transforms, e.g. across the requests of a dev server or the worker
+//! threads of a parallel build.
+//!
+//! The free functions take their options by reference, but
+//! `jsx_transformer` builds the defaults on every call, allocating the list
+//! and stream method names and the void tags each time. A `Transformer` keeps
+//! them, and holds no state between transforms: `transform(&self, ..)` needs
+//! no lock, and the transformer is `Send + Sync` to share behind an `Arc`.
+
+use super::errors::JSXError;
+use super::options::TransformOptions;
+use super::output::TransformOutput;
+use super::transform::transform_to_template;
+use super::transform_islands;
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::JSXNode;
+
+/// Reusable, thread-safe transformer of JSX sources with fixed options.
+#[derive(Debug, Clone, Default)]
+pub struct Transformer {
+    options: TransformOptions,
+}
+
+// Shared across threads by design: fails to build if an option stops being so
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Transformer>();
+};
+
+impl Transformer {
+    pub fn new(options: TransformOptions) -> Self {
+        Self { options }
+    }
+
+    /// The options every transform uses; per-file pragmas still apply on top
+    /// of them.
+    pub fn options(&self) -> &TransformOptions {
+        &self.options
+    }
+
+    /// Same as `jsx_transform` with the options of the transformer.
+    pub fn transform(&self, source: &str) -> Result<TransformOutput, JSXError> {
+        transform_islands(source, &self.options, &find_next_jsx_start, None, None)
+    }
+
+    /// Same as `transform`, returning the transformed code only.
+    pub fn transform_code(&self, source: &str) -> Result<String, JSXError> {
+        self.transform(source).map(|output| output.code)
+    }
+
+    /// Same as `transform_node` with the options of the transformer.
+    pub fn transform_node(&self, node: &JSXNode) -> Result<String, JSXError> {
+        transform_to_template(node, &self.options, &[], &mut TransformOutput::default())
+    }
+}
+
+impl From<TransformOptions> for Transformer {
+    fn from(options: TransformOptions) -> Self {
+        Self::new(options)
+    }
+}
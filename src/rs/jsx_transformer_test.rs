@@ -1,14 +1,20 @@
+use crate::assert_jsx_eq;
 use crate::jsx_transformer::tags_attrs::{
-    classify_tag, normalize_html_attr_name, transform_attribute, TagType,
+    classify_tag, classify_tag_with, expected_case, is_boolean_attr, is_void,
+    normalize_html_attr_name, transform_attribute, transform_attribute_with_options, TagType,
 };
-use crate::jsx_transformer::{format_diagnostic, jsx_transformer};
-use std::collections::HashMap;
-
-// Test helper to make whitespace-insensitive comparisons for outputs where
-// internal formatting (spaces/newlines) is not semantically relevant.
-fn normalize_ws(s: &str) -> String {
-    s.split_whitespace().collect::<Vec<_>>().join(" ")
-}
+use crate::jsx_transformer::{
+    format_diagnostic, jsx_retransform, jsx_transform, jsx_transform_in_ranges,
+    jsx_transform_incremental, jsx_transform_prose, jsx_transform_with_progress, jsx_transformer,
+    jsx_transformer_in_ranges, jsx_transformer_with_options, parse_jsx, render_static,
+    transform_node, ComponentUsage, CspNonce, EventHandlerMode, ExpressionSite, HydrationManifest,
+    JSXError, JsParseResult, JsTransformError, JsTransformOptions, JsTransformResult,
+    JsxStartContext, ListMode, OutputTarget, QuoteStyle, SpreadOrder, StrippedProp,
+    TransformOptions, Transformer, DEFAULT_ATTRIBUTE_HELPER, DEFAULT_FRAGMENT_COMPONENT,
+    DEFAULT_MAX_ERRORS,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 #[test]
 fn test_unified_transform_attribute() {
@@ -71,6 +77,36 @@ fn test_unified_transform_attribute() {
     );
 }
 
+#[test]
+fn test_transform_attribute_with_options() {
+    use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
+
+    let options = TransformOptions {
+        quote_style: QuoteStyle::Single,
+        ..TransformOptions::default()
+    };
+    let attr_str = JSXAttribute {
+        name: "className".into(),
+        value: Some(JSXAttributeValue::DoubleQuote("foo".into())),
+    };
+    assert_eq!(
+        transform_attribute_with_options(&attr_str, TagType::Element, &options).unwrap(),
+        "class='foo'"
+    );
+
+    // Nested JSX that does not transform is an error, kept as written by
+    // `transform_attribute`
+    let attr_jsx = JSXAttribute {
+        name: "icon".into(),
+        value: Some(JSXAttributeValue::Expression("<b>x</i>".into())),
+    };
+    assert!(transform_attribute_with_options(&attr_jsx, TagType::Component, &options).is_err());
+    assert_eq!(
+        transform_attribute(&attr_jsx, TagType::Component),
+        r#"{"icon":<b>x</i>}"#
+    );
+}
+
 #[test]
 fn test_basic_jsx_element() {
     let source = "const el = <div>Hello</div>;";
@@ -295,6 +331,28 @@ fn test_normalize_html_attr_name() {
     for (input, expected) in values {
         assert_eq!(normalize_html_attr_name(input), expected);
     }
+
+    // Names needing no change are not copied
+    for name in ["class", "onclick", "data-testId", "my-attr"] {
+        assert!(matches!(normalize_html_attr_name(name), Cow::Borrowed(n) if n == name));
+    }
+    assert!(matches!(
+        normalize_html_attr_name("className"),
+        Cow::Borrowed("class")
+    ));
+    assert!(matches!(normalize_html_attr_name("myAttr"), Cow::Owned(_)));
+}
+
+#[test]
+fn test_html_data_queries() {
+    assert!(is_void("br") && is_void("wbr"));
+    assert!(!is_void("div") && !is_void("circle"));
+    assert!(is_boolean_attr("disabled") && is_boolean_attr("readonly"));
+    assert!(!is_boolean_attr("value") && !is_boolean_attr("readOnly"));
+    assert_eq!(expected_case("className"), Some("class"));
+    assert_eq!(expected_case("strokeWidth"), Some("stroke-width"));
+    assert_eq!(expected_case("viewBox"), Some("viewBox"));
+    assert_eq!(expected_case("title"), None);
 }
 
 #[test]
@@ -446,7 +504,7 @@ fn test_fragment_with_child_expresion() {
         </>;"#;
     let result = jsx_transformer(source).unwrap();
     let expected = r#"const el = `<label>After Image</label><input type="text"/><span>After Input</span>${description ? ( `<span>${description}</span>` ) : ( "" )}`;"#;
-    assert_eq!(normalize_ws(&result), normalize_ws(expected));
+    assert_jsx_eq!(result.as_str(), expected);
 }
 
 #[test]
@@ -501,11 +559,12 @@ fn test_flat_map_and_for_each_transformations() {
         "const el = `<div>${__jsxList(items.flatMap(item => `<li>${item}</li>`))}</div>`;"
     );
 
+    // forEach returns undefined, so it is not treated as a list
     let src2 = r#"const el = <div>{items.forEach(item => <li>{item}</li>)}</div>;"#;
     let out2 = jsx_transformer(src2).unwrap();
     assert_eq!(
         out2,
-        "const el = `<div>${__jsxList(items.forEach(item => `<li>${item}</li>`))}</div>`;"
+        "const el = `<div>${items.forEach(item => `<li>${item}</li>`)}</div>`;"
     );
 }
 
@@ -538,11 +597,71 @@ fn test_filter_transformation() {
 
 #[test]
 fn test_reduce_transformation() {
+    // reduce returns a scalar, so it is not wrapped with __jsxList
     let source = r#"const el = <div>{items.reduce((acc, item) => acc + item, 0)}</div>;"#;
     let result = jsx_transformer(source).unwrap();
     assert_eq!(
         result,
-        "const el = `<div>${__jsxList(items.reduce((acc, item) => acc + item, 0))}</div>`;"
+        "const el = `<div>${items.reduce((acc, item) => acc + item, 0)}</div>`;"
+    );
+}
+
+#[test]
+fn test_list_detection_requires_jsx_in_list_callback() {
+    // JSX outside the map callback: the value is a single template, not a list
+    let src1 =
+        r#"const el = <div>{count(items.map(i => i.id)) > 1 ? <b>many</b> : <i>one</i>}</div>;"#;
+    assert_eq!(
+        jsx_transformer(src1).unwrap(),
+        "const el = `<div>${count(items.map(i => i.id)) > 1 ? `<b>many</b>` : `<i>one</i>`}</div>`;"
+    );
+
+    // Optional-call forms and chains still count as list calls
+    let src2 = r#"const el = <div>{items?.filter(Boolean).map?.(i => <li>{i}</li>)}</div>;"#;
+    assert_eq!(
+        jsx_transformer(src2).unwrap(),
+        "const el = `<div>${__jsxList(items?.filter(Boolean).map?.(i => `<li>${i}</li>`))}</div>`;"
+    );
+
+    // A list call inside a template interpolation is string-interpolated, not a list
+    let src3 = r#"const el = <div>{`${items.map(i => i.name)}`.length > 0 && <hr/>}</div>;"#;
+    assert_eq!(
+        jsx_transformer(src3).unwrap(),
+        "const el = `<div>${`${items.map(i => i.name)}`.length > 0 && `<hr/>`}</div>`;"
+    );
+
+    // A list call whose result is accessed or called is not the value
+    let src4 = r#"const el = <p>{items.filter(x => x).length}{names.map(f).join(", ")}{rows.map(r => <td>{r}</td>).join("")}{a.filter(f)?.[0]}</p>;"#;
+    assert_eq!(
+        jsx_transformer(src4).unwrap(),
+        r#"const el = `<p>${items.filter(x => x).length}${names.map(f).join(", ")}${rows.map(r => `<td>${r}</td>`).join("")}${a.filter(f)?.[0]}</p>`;"#
+    );
+    let src5 = r#"const el = <p>{names.map(f).filter(Boolean)}{x.slice(1).join("").split(",").map(g)}</p>;"#;
+    assert_eq!(
+        jsx_transformer(src5).unwrap(),
+        r#"const el = `<p>${names.map(f).filter(Boolean)}${x.slice(1).join("").split(",").map(g)}</p>`;"#
+    );
+
+    // Without JSX in a callback or argument, list methods of strings and
+    // arrays are values
+    let src6 = r#"const el = <p>{name.slice(0,3)}{first.concat(last)}{a.concat(<b/>)}</p>;"#;
+    assert_eq!(
+        jsx_transformer(src6).unwrap(),
+        r#"const el = `<p>${name.slice(0,3)}${first.concat(last)}${__jsxList(a.concat(`<b></b>`))}</p>`;"#
+    );
+}
+
+#[test]
+fn test_list_methods_option() {
+    let options = TransformOptions {
+        list_methods: vec!["each".to_string()],
+        ..TransformOptions::default()
+    };
+    let source =
+        r#"const el = <ul>{rows.each(r => <li>{r}</li>)}{rows.map(r => <li>{r}</li>)}</ul>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<ul>${__jsxList(rows.each(r => `<li>${r}</li>`))}${rows.map(r => `<li>${r}</li>`)}</ul>`;"
     );
 }
 
@@ -573,7 +692,7 @@ onChange={() => onToggle(index)}
     let result = jsx_transformer(source).unwrap();
     let expected = "const TodoList = ({items, onToggle}) => (\n`<div class=\"${`todo-list ${items.length ? 'has-items' : ''}`}\"><header class=\"todo-header\"><h1>${items.length} Tasks Remaining</h1><input type=\"text\"${__jsxSpread(inputProps)} placeholder=\"Add new task\"/></header><ul class=\"todo-items\">${__jsxList(items.map((item, index) => ( `<li key=\"${item.id}\" class=\"${item.completed ? 'completed' : ''}\"><input type=\"checkbox\" checked=\"${item.completed}\" onchange=\"${() => onToggle(index)}\"/><span class=\"todo-text\">${item.text}</span><button onclick=\"${() => onDelete(item.id)}\">Delete</button></li>` )))}</ul></div>`)";
 
-    assert_eq!(normalize_ws(&result), normalize_ws(expected));
+    assert_jsx_eq!(result.as_str(), expected);
 }
 
 #[test]
@@ -626,7 +745,7 @@ fn test_complex_jsx_with_conditions() {
         ;"#;
     let result = jsx_transformer(input).unwrap();
     let expected = "const el = `<div class=\"${`container ${theme}`}\"><header class=\"${styles.header}\"><h1>${title || \"Default Title\"}</h1><nav>${__jsxList(menuItems.map((item, index) => ( `<a key=\"${index}\" href=\"${item.href}\" class=\"${`${styles.link} ${currentPath === item.href ? styles.active : ''}`}\">${item.icon && `${__jsxComponent(Icon, [{\"name\":item.icon}])}`}<span>${item.label}</span>${item.badge && ( `${__jsxComponent(Badge, [{\"count\":item.badge},{\"type\":item.badgeType}])}` )}</a>` )))}</nav>${user ? ( `<div class=\"${styles.userMenu}\"><img src=\"${user.avatar}\" alt=\"User avatar\"/><span>${user.name}</span><button onclick=\"${handleLogout}\">Logout</button></div>` ) : ( `<button class=\"${styles.loginButton}\" onclick=\"${handleLogin}\">Login</button>` )}</header><main class=\"${styles.main}\">${loading ? ( `<div class=\"${styles.loader}\">${__jsxComponent(Spinner, [{\"size\":\"large\"},{\"color\":theme === 'dark' ? 'white' : 'black'}])}</div>` ) : error ? ( `${__jsxComponent(ErrorMessage, [{\"message\":error},{\"onRetry\":handleRetry}])}` ) : ( `${children}` )}</main><footer class=\"${styles.footer}\"><p>&copy; ${currentYear} My Application</p></footer></div>`\n        ;";
-    assert_eq!(normalize_ws(&result), normalize_ws(expected));
+    assert_jsx_eq!(result.as_str(), expected);
 }
 
 #[test]
@@ -939,11 +1058,10 @@ fn test_jsx_precompile_aggregated_diagnostics_multiple_errors() {
 
     assert!(s.contains("JSX parsing error:"), "missing header: {s}");
     assert!(s.contains("  --> "), "missing diagnostics arrow: {s}");
-    assert!(s.contains(":2:"), "missing line 2: {s}");
-    assert!(
-        s.contains(":3:") || s.contains(":1:"),
-        "missing line 3 or 1: {s}"
-    );
+    assert!(s.contains(":1:"), "missing line 1: {s}");
+    assert!(s.contains(":3:"), "missing line 3: {s}");
+    // The nested <span> of the broken <div> is not reported again
+    assert!(!s.contains(":2:"), "cascading error on line 2: {s}");
     assert!(s.contains('^'), "missing caret: {s}");
     assert!(s.contains("= note:"), "missing note lines: {s}");
     assert!(
@@ -1063,7 +1181,7 @@ fn test_complex_li_with_conditional_link_and_spread_attrs() {
     "#;
     let result = jsx_transformer(source).unwrap();
     let expected = r#"`<li>${href && !isCurrent ? ( `<a href="${href}" class="${linkClass}"${__jsxSpread(item?.attrs ?? {})}>${label}</a>` ) : ( `<span class="${currentClass}" aria-current="${isCurrent ? "page" : null}"${__jsxSpread(item?.attrs ?? {})}>${label}</span>` )}</li>`"#;
-    assert_eq!(normalize_ws(&result), normalize_ws(expected));
+    assert_jsx_eq!(result.as_str(), expected);
 }
 
 #[test]
@@ -1135,5 +1253,2074 @@ fn test_accordion_nested_components() {
     "#;
     let result = jsx_transformer(source).unwrap();
     let expected = r#"`${__jsxComponent(Accordion, [{"type":"single"},{"collapsible":true}], `${__jsxComponent(Accordion.Item, [{"value":"item-1"},{"open":true}], `${__jsxComponent(Accordion.Header, [], `Is it accessible?`)}${__jsxComponent(Accordion.Content, [], `Yes. It adheres to the WAI-ARIA design pattern.`)}`)}`)}`"#;
-    assert_eq!(normalize_ws(&result), normalize_ws(expected));
+    assert_jsx_eq!(result.as_str(), expected);
+}
+
+#[test]
+fn test_nested_embedded_expressions() {
+    let source =
+        r#"const el = <ul>{groups.map(g => <li>{g.items.map(i => <b>{i}</b>)}</li>)}</ul>;"#;
+    let result = jsx_transformer(source).unwrap();
+    assert_eq!(
+        result,
+        "const el = `<ul>${__jsxList(groups.map(g => `<li>${__jsxList(g.items.map(i => `<b>${i}</b>`))}</li>`))}</ul>`;"
+    );
+}
+
+#[test]
+fn test_embedded_expression_error_points_at_nested_jsx() {
+    let src = "<div>\n  {ok && <span>bad</p>}\n</div>";
+    let err = jsx_transformer(src).expect_err("expected nested parsing error");
+    let s = err.to_string();
+
+    assert!(s.contains("input:2:10"), "expected nested position: {s}");
+    assert!(
+        s.contains("Mismatched closing tag"),
+        "missing mismatch: {s}"
+    );
+}
+
+#[test]
+fn test_list_mode_option() {
+    let source =
+        r#"const el = <ul>{items.map(i => <li>{i}</li>)}{show && rows.map(r => <b>{r}</b>)}</ul>;"#;
+
+    let helper = TransformOptions {
+        list_mode: ListMode::Helper("join".to_string()),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &helper).unwrap(),
+        "const el = `<ul>${join(items.map(i => `<li>${i}</li>`))}${join(show && rows.map(r => `<b>${r}</b>`))}</ul>`;"
+    );
+
+    let inline = TransformOptions {
+        list_mode: ListMode::InlineJoin,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &inline).unwrap(),
+        "const el = `<ul>${items.map(i => `<li>${i}</li>`).join(\"\")}${show && rows.map(r => `<b>${r}</b>`).join(\"\")}</ul>`;"
+    );
+
+    let ternary =
+        r#"const el = <ul>{items.length ? items.map(i => <li>{i}</li>) : <li>none</li>}</ul>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(ternary, &inline).unwrap(),
+        "const el = `<ul>${[].concat((items.length ? items.map(i => `<li>${i}</li>`) : `<li>none</li>`) ?? []).join(\"\")}</ul>`;"
+    );
+
+    let disabled = TransformOptions {
+        list_mode: ListMode::Disabled,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &disabled).unwrap(),
+        "const el = `<ul>${items.map(i => `<li>${i}</li>`)}${show && rows.map(r => `<b>${r}</b>`)}</ul>`;"
+    );
+}
+
+#[test]
+fn test_component_and_element_patterns() {
+    let options = TransformOptions {
+        component_patterns: vec!["my-app-*".to_string()],
+        element_patterns: vec!["Swiper".to_string(), "Legacy*".to_string()],
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <my-app-shell title="Home"><Swiper loop><LegacyBox/></Swiper><my-icon/></my-app-shell>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `${__jsxComponent(MyAppShell, [{\"title\":\"Home\"}], `<Swiper loop><LegacyBox></LegacyBox></Swiper><my-icon></my-icon>`)}`;"
+    );
+}
+
+#[test]
+fn test_drop_and_keep_attributes() {
+    let options = TransformOptions {
+        drop_attributes: vec!["data-test-*".to_string(), "on*".to_string()],
+        keep_attributes: vec!["data-test-keep".to_string()],
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <button data-test-id="save" data-test-keep="1" data-id={id} onClick={save} {...rest}>Save</button>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<button data-test-keep=\"1\" data-id=\"${id}\"${__jsxSpread(rest)}>Save</button>`;"
+    );
+
+    let source = r#"const el = <Card data-test-id="card" />;"#;
+    assert!(jsx_transformer_with_options(source, &options)
+        .unwrap()
+        .contains("data-test-id"));
+}
+
+#[test]
+fn test_quote_style() {
+    let source = r#"const el = <p title='say "hi"' lang="en" data-x='a' class={c}>x</p>;"#;
+    let transform = |quote_style| {
+        let options = TransformOptions {
+            quote_style,
+            ..TransformOptions::default()
+        };
+        jsx_transformer_with_options(source, &options).unwrap()
+    };
+    assert_eq!(
+        transform(QuoteStyle::Preserve),
+        r#"const el = `<p title='say "hi"' lang="en" data-x='a' class="${c}">x</p>`;"#
+    );
+    assert_eq!(
+        transform(QuoteStyle::Double),
+        r#"const el = `<p title='say "hi"' lang="en" data-x="a" class="${c}">x</p>`;"#
+    );
+    assert_eq!(
+        transform(QuoteStyle::Single),
+        r#"const el = `<p title='say "hi"' lang='en' data-x='a' class='${c}'>x</p>`;"#
+    );
+
+    let options: JsTransformOptions = serde_json::from_str(r#"{"quoteStyle":"single"}"#).unwrap();
+    assert_eq!(
+        options.apply(&TransformOptions::default()).quote_style,
+        QuoteStyle::Single
+    );
+}
+
+#[test]
+fn test_classify_tag_with_options() {
+    let options = TransformOptions {
+        component_patterns: vec!["x-*".to_string(), "Card".to_string()],
+        element_patterns: vec!["x-native".to_string()],
+        ..TransformOptions::default()
+    };
+    assert_eq!(classify_tag_with("x-widget", &options), TagType::Component);
+    assert_eq!(
+        classify_tag_with("x-native", &options),
+        TagType::WebComponent
+    );
+    assert_eq!(classify_tag_with("Card", &options), TagType::Component);
+    assert_eq!(classify_tag_with("img", &options), TagType::Void);
+    assert_eq!(classify_tag_with("Header", &options), TagType::Component);
+}
+
+#[test]
+fn test_svg_shapes_are_not_void() {
+    let source =
+        r#"const el = <svg><circle r="4"/><path d="M0"><animate attributeName="d"/></path></svg>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const el = `<svg><circle r=\"4\"/><path d=\"M0\"><animate attributeName=\"d\"/></path></svg>`;"
+    );
+}
+
+#[test]
+fn test_self_closed_and_closed_void_elements() {
+    // Self-closed HTML elements get a closing tag, closed void elements lose it
+    assert_eq!(
+        jsx_transformer("const el = <div><div /><br></br><textarea/></div>;").unwrap(),
+        "const el = `<div><div></div><br/><textarea></textarea></div>`;"
+    );
+}
+
+#[test]
+fn test_void_tags_option() {
+    let options = TransformOptions {
+        void_tags: vec!["img".to_string(), "SPACER".to_string()],
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <p><spacer/><br/><img src="a.png"/></p>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<p><spacer/><br></br><img src=\"a.png\"/></p>`;"
+    );
+}
+
+#[test]
+fn test_close_custom_elements_option() {
+    let source = r#"const el = <div><my-icon name="x"/><my-card><b>hi</b></my-card></div>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const el = `<div><my-icon name=\"x\"></my-icon><my-card><b>hi</b></my-card></div>`;"
+    );
+    assert_eq!(
+        jsx_transformer("<my-icon {...p}/>").unwrap(),
+        "`<my-icon${__jsxSpread(p)}></my-icon>`"
+    );
+    let minify = TransformOptions {
+        minify: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(r#"<my-icon name="x"/>"#, &minify).unwrap(),
+        "`<my-icon name=x></my-icon>`"
+    );
+
+    let options = TransformOptions {
+        close_custom_elements: false,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<div><my-icon name=\"x\"/><my-card><b>hi</b></my-card></div>`;"
+    );
+}
+
+#[test]
+fn test_attr_name_normalization_options() {
+    let source = r#"const el = <svg viewBox="0 0 1 1" className="a"><feSpotLight limitingConeAngle="3" specularExponentX="1" dataTrack/></svg>;"#;
+
+    let preserve = TransformOptions {
+        preserve_unknown_attr_case: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &preserve).unwrap(),
+        "const el = `<svg viewBox=\"0 0 1 1\" class=\"a\"><feSpotLight limitingConeAngle=\"3\" specularExponentX=\"1\" dataTrack/></svg>`;"
+    );
+
+    let disabled = TransformOptions {
+        normalize_attr_names: false,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(r#"<p className="a" tabIndex="1"/>"#, &disabled).unwrap(),
+        "`<p className=\"a\" tabIndex=\"1\"></p>`"
+    );
+
+    let mut attr_name_map = HashMap::new();
+    attr_name_map.insert("className".to_string(), "klass".to_string());
+    attr_name_map.insert("dataTrack".to_string(), "data-track".to_string());
+    let mapped = TransformOptions {
+        attr_name_map,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(r#"<p className="a" tabIndex="1" dataTrack/>"#, &mapped)
+            .unwrap(),
+        "`<p klass=\"a\" tabindex=\"1\" data-track></p>`"
+    );
+}
+
+#[test]
+fn test_data_aria_attr_case() {
+    let source =
+        r#"<button data-testId="save" aria-describedBy="hint" tabIndex="0" onClick="go()"/>"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"`<button data-testId="save" aria-describedBy="hint" tabindex="0" onclick="go()"></button>`"#
+    );
+    assert_eq!(normalize_html_attr_name("data-testId"), "data-testId");
+
+    let options = TransformOptions {
+        preserve_data_aria_case: false,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"`<button data-testid="save" aria-describedby="hint" tabindex="0" onclick="go()"></button>`"#
+    );
+}
+
+#[test]
+fn test_svg_and_mathml_namespaces() {
+    let source = r#"const el = <div><svg><marker refX="1"/><foreignObject><div dataX="1"></div><br/></foreignObject></svg><math><mi mathVariant="bold"/></math><span/></div>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const el = `<div><svg><marker refX=\"1\"/><foreignObject><div datax=\"1\"></div><br/></foreignObject></svg><math><mi mathVariant=\"bold\"/></math><span></span></div>`;"
+    );
+}
+
+#[test]
+fn test_namespace_aware_option() {
+    let options = TransformOptions {
+        namespace_aware: false,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <svg><marker dataRef="1"/></svg>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<svg><marker dataref=\"1\"></marker></svg>`;"
+    );
+}
+
+#[test]
+fn test_event_handler_modes() {
+    let source = r#"const el = <button onClick={save} onDoubleClick={edit} onmouseover="hint()" title="Save">Save</button>;"#;
+    let transform = |mode| {
+        let options = TransformOptions {
+            event_handlers: mode,
+            ..TransformOptions::default()
+        };
+        jsx_transformer_with_options(source, &options).unwrap()
+    };
+
+    assert_eq!(
+        transform(EventHandlerMode::Lowercase),
+        "const el = `<button onclick=\"${save}\" ondoubleclick=\"${edit}\" onmouseover=\"hint()\" title=\"Save\">Save</button>`;"
+    );
+    assert_eq!(
+        transform(EventHandlerMode::PreserveCamel),
+        "const el = `<button onClick=\"${save}\" onDoubleClick=\"${edit}\" onmouseover=\"hint()\" title=\"Save\">Save</button>`;"
+    );
+    assert_eq!(
+        transform(EventHandlerMode::Strip),
+        "const el = `<button onmouseover=\"hint()\" title=\"Save\">Save</button>`;"
+    );
+    assert_eq!(
+        transform(EventHandlerMode::Hydrate),
+        "const el = `<button onmouseover=\"hint()\" title=\"Save\" data-action=\"click:${__jsxAction(save)} dblclick:${__jsxAction(edit)}\">Save</button>`;"
+    );
+
+    let options = TransformOptions {
+        event_handlers: EventHandlerMode::Hydrate,
+        action_helper: "register".to_string(),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(r#"<a onClick={go}>x</a>"#, &options).unwrap(),
+        r#"`<a data-action="click:${register(go)}">x</a>`"#
+    );
+
+    // Merged into the actions already on the element
+    assert_eq!(
+        jsx_transformer_with_options(
+            r#"<a data-action="focus:track" onClick={go} data-x="1">x</a><b data-action={more} onClick={go} />"#,
+            &options
+        )
+        .unwrap(),
+        r#"`<a data-x="1" data-action="focus:track click:${register(go)}">x</a>``<b data-action="${more} click:${register(go)}"></b>`"#
+    );
+}
+
+#[test]
+fn test_event_handler_mode_leaves_component_props() {
+    let options = TransformOptions {
+        event_handlers: EventHandlerMode::Strip,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <Button onClick={save} />;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        jsx_transformer(source).unwrap()
+    );
+}
+
+#[test]
+fn test_hydration_markers() {
+    let options = TransformOptions {
+        hydration_markers: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const a = <main><Counter start={1}><Icon/></Counter><Card header={<Title/>}/></main>;
+const b = <Counter/>;"#;
+    let output = jsx_transform(source, &options).unwrap();
+    assert_eq!(
+        output.code,
+        "const a = `<main><!--sxo:Counter:sxo-0-->${__jsxComponent(Counter, [{\"start\":1}], `${__jsxComponent(Icon, [])}`)}<!--/sxo:sxo-0--><!--sxo:Card:sxo-1-->${__jsxComponent(Card, [{\"header\":`${__jsxComponent(Title, [])}`}])}<!--/sxo:sxo-1--></main>`;
+const b = `<!--sxo:Counter:sxo-2-->${__jsxComponent(Counter, [])}<!--/sxo:sxo-2-->`;"
+    );
+    let mut expected = HydrationManifest::new();
+    expected.insert(
+        "Counter".to_string(),
+        vec!["sxo-0".to_string(), "sxo-2".to_string()],
+    );
+    expected.insert("Card".to_string(), vec!["sxo-1".to_string()]);
+    assert_eq!(output.hydration, expected);
+}
+
+#[test]
+fn test_hydration_markers_disabled_by_default() {
+    let output = jsx_transform("const a = <Counter/>;", &TransformOptions::default()).unwrap();
+    assert_eq!(output.code, "const a = `${__jsxComponent(Counter, [])}`;");
+    assert!(output.hydration.is_empty());
+}
+
+#[test]
+fn test_component_usage_metadata() {
+    let source = r#"const a = <Layout><Card header={<Title/>}/><Card/><my-icon/><my-icon/></Layout>;
+const b = <my-app-shell><ui-tabs/></my-app-shell>;"#;
+    let options = TransformOptions {
+        component_patterns: vec!["my-app-*".to_string()],
+        ..TransformOptions::default()
+    };
+    let usage = jsx_transform(source, &options).unwrap().usage;
+
+    let counts = |pairs: &[(&str, usize)]| {
+        pairs
+            .iter()
+            .map(|(name, count)| (name.to_string(), *count))
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(
+        usage,
+        ComponentUsage {
+            components: counts(&[("Card", 2), ("Layout", 1), ("MyAppShell", 1), ("Title", 1)]),
+            web_components: counts(&[("my-icon", 2), ("ui-tabs", 1)]),
+        }
+    );
+}
+
+#[test]
+fn test_source_annotations() {
+    let options = TransformOptions {
+        source_annotations: Some("src/page.jsx".to_string()),
+        ..TransformOptions::default()
+    };
+    let source = "const page = (\n  <main>\n    <Card>{items.map(i => <li>é{i}</li>)}</Card>\n    <img/>\n  </main>\n);";
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const page = (\n  `<main data-sxo-source=\"src/page.jsx:2:3\">${__jsxComponent(Card, [], `${__jsxList(items.map(i => `<li data-sxo-source=\"src/page.jsx:3:27\">é${i}</li>`))}`)}<img data-sxo-source=\"src/page.jsx:4:5\"/></main>`\n);"
+    );
+}
+
+#[test]
+fn test_source_annotations_escape_file_names() {
+    let annotate = |file: &str| {
+        let options = TransformOptions {
+            source_annotations: Some(file.to_string()),
+            ..TransformOptions::default()
+        };
+        jsx_transformer_with_options("<br/>", &options).unwrap()
+    };
+    assert_eq!(
+        annotate(r"C:\users\x.jsx"),
+        r#"`<br data-sxo-source="C:\\users\\x.jsx:1:1"/>`"#
+    );
+    assert_eq!(
+        annotate(r#"a`b${c}"d".jsx"#),
+        r#"`<br data-sxo-source="a\`b\${c}&quot;d&quot;.jsx:1:1"/>`"#
+    );
+}
+
+#[test]
+fn test_source_comments() {
+    let options = TransformOptions {
+        source_comments: true,
+        ..TransformOptions::default()
+    };
+    let source = "const a = <p>{/* note */ x}</p>;\nconst b = <br/>;";
+    let expected = "const a = /* sxo: <p>{/* note *\\/ x}</p> */ `<p>${/* note */ x}</p>`;\nconst b = /* sxo: <br/> */ `<br/>`;";
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        expected
+    );
+
+    // Kept on reused regions
+    let previous = jsx_transform_incremental(source, &options).unwrap();
+    let edited = source.replace("<br/>", "<hr/>");
+    let edit = source.find("<br/>").unwrap()..source.find("<br/>").unwrap() + 5;
+    let output = jsx_retransform(&previous, source, &edited, edit, &options).unwrap();
+    assert_eq!(output.reused, 1);
+    assert_eq!(output.output.code, expected.replace("<br/>", "<hr/>"));
+}
+
+#[test]
+fn test_constant_folding() {
+    let options = TransformOptions {
+        fold_constants: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <div>{true && <A/>}{false && <b>x</b>}{false ? <i>a</i> : <em>b</em>}{ true ? <i>a</i> : <em>b</em> }{"Hi `you` ${x}"}{" "}{"a\"b"}{ok && <A/>}</div>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = `<div>${__jsxComponent(A, [])}<em>b</em><i>a</i>Hi \`you\` \${x}${" "}a\"b${ok && `${__jsxComponent(A, [])}`}</div>`;"#
+    );
+
+    // Off by default
+    assert_eq!(
+        jsx_transformer(r#"const el = <p>{"Hi"}{false && <b/>}</p>;"#).unwrap(),
+        r#"const el = `<p>${"Hi"}${false && `<b></b>`}</p>`;"#
+    );
+}
+
+#[test]
+fn test_fold_literal_expressions() {
+    let source = r#"const el = <p>{"a\"b\n"}{'it\'s'}{42}{-1.5}{0.1}{`Hi ${"there"} ${7}!`}{1.50}{1e3}{0x10}{"\1"}{`x ${name}`}{"a" + "b"}</p>;"#;
+    let options = TransformOptions {
+        fold_constants: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = `<p>a\"b\nit\'s42-1.50.1Hi there 7!${1.50}${1e3}${0x10}${"\1"}${`x ${name}`}${"a" + "b"}</p>`;"#
+    );
+}
+
+#[test]
+fn test_inline_text_whitespace() {
+    let cases = [
+        ("<p><b>bold</b> text</p>", "<p><b>bold</b> text</p>"),
+        ("<p>text <b>bold</b> </p>", "<p>text <b>bold</b> </p>"),
+        ("<p> <i>a</i> <i>b</i> </p>", "<p> <i>a</i> <i>b</i> </p>"),
+        ("<pre>  code  </pre>", "<pre>  code  </pre>"),
+        ("<Card> hi </Card>", "${__jsxComponent(Card, [], ` hi `)}"),
+        ("<> a </>", " a "),
+        // Indentation around children is dropped, whitespace within kept
+        (
+            "<p>\n  Hello <b>world</b>,\n  again\n</p>",
+            "<p>Hello <b>world</b>,\n  again</p>",
+        ),
+        (
+            "<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>",
+            "<ul><li>a</li><li>b</li></ul>",
+        ),
+    ];
+    for (jsx, html) in cases {
+        assert_eq!(
+            jsx_transformer(&format!("const el = {jsx};")).unwrap(),
+            format!("const el = `{html}`;"),
+            "{jsx}"
+        );
+    }
+}
+
+#[test]
+fn test_transform_parsed_node() {
+    let source = r#"const a = <ul class="x">{items.map(i => <li>{i}</li>)}<Card /></ul>;"#;
+    let options = TransformOptions::default();
+    let nodes = parse_jsx(source).unwrap();
+    let (node, (start, end)) = &nodes[0];
+    let code = transform_node(node, &options).unwrap();
+    assert_eq!(
+        format!("{}{code}{}", &source[..*start], &source[*end..]),
+        jsx_transformer(source).unwrap()
+    );
+}
+
+#[test]
+fn test_minify_option() {
+    let options = TransformOptions {
+        minify: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <div   class="card"  id='main' title="two words" data-x="">
+    <p>  Hello,   <b>world</b>  </p>   <img src="/a.png" alt={alt}/>
+    <pre>  keep   this  </pre><svg><path d="M0"/></svg><my-el role="x"></my-el>
+</div>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = `<div class=card id=main title="two words" data-x=""><p> Hello, <b>world</b></p><img src=/a.png alt="${alt}"><pre>  keep   this  </pre><svg><path d="M0"/></svg><my-el role=x></my-el></div>`;"#
+    );
+}
+
+#[test]
+fn test_pretty_option() {
+    let options = TransformOptions {
+        pretty: true,
+        ..TransformOptions::default()
+    };
+    let source =
+        r#"const el = <ul class="list"><li>One</li><li>Two <b>2</b></li><Item>{x}</Item></ul>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<ul class=\"list\">\n  <li>One</li>\n  <li>Two\n    <b>2</b>\n  </li>\n  ${__jsxComponent(Item, [], `${x}`)}\n</ul>`;"
+    );
+}
+
+#[test]
+fn test_jsx_pragmas() {
+    let source = r#"#!/usr/bin/env node
+// build: server
+/** @jsx h */
+/* @jsxFrag Frag */
+const el = <><Card title="x"><p>Hi</p></Card><br/></>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"#!/usr/bin/env node
+// build: server
+/** @jsx h */
+/* @jsxFrag Frag */
+const el = `${h(Frag, [], `${h(Card, [{"title":"x"}], `<p>Hi</p>`)}<br/>`)}`;"#
+    );
+
+    // Only leading comments count
+    let source = "const a = 1; /** @jsx h */ const el = <Card/>;";
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const a = 1; /** @jsx h */ const el = `${__jsxComponent(Card, [])}`;"
+    );
+}
+
+#[test]
+fn test_fragment_component_option() {
+    let options = TransformOptions {
+        fragment_component: Some(DEFAULT_FRAGMENT_COMPONENT.to_string()),
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <ul><>{items.map(i => <><li>{i}</li></>)}</></ul>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<ul>${__jsxComponent(__jsxFragment, [], `${__jsxList(items.map(i => `${__jsxComponent(__jsxFragment, [], `<li>${i}</li>`)}`))}`)}</ul>`;"
+    );
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const el = `<ul>${__jsxList(items.map(i => `<li>${i}</li>`))}</ul>`;"
+    );
+}
+
+#[test]
+fn test_keyed_fragments() {
+    let options = TransformOptions {
+        named_fragments: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <dl>{items.map(i => <Fragment key={i.id}><dt>{i.term}</dt><dd>{i.text}</dd></Fragment>)}<Fragment><hr/></Fragment><Fragment title="x"/></dl>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const el = `<dl>${__jsxList(items.map(i => `${__jsxComponent(__jsxFragment, [{\"key\":i.id}], `<dt>${i.term}</dt><dd>${i.text}</dd>`)}`))}<hr/>${__jsxComponent(Fragment, [{\"title\":\"x\"}])}</dl>`;"
+    );
+
+    let options = TransformOptions {
+        fragment_component: Some("Frag".to_string()),
+        ..options
+    };
+    assert_eq!(
+        jsx_transformer_with_options(
+            r#"const el = <React.Fragment><b/></React.Fragment>;"#,
+            &options
+        )
+        .unwrap(),
+        "const el = `${__jsxComponent(Frag, [], `<b></b>`)}`;"
+    );
+
+    // Off by default: `Fragment` is a component like any other
+    assert_eq!(
+        jsx_transformer("const el = <Fragment><hr/></Fragment>;").unwrap(),
+        "const el = `${__jsxComponent(Fragment, [], `<hr/>`)}`;"
+    );
+}
+
+#[test]
+fn test_await_expressions_use_async_templates() {
+    let source = r#"const el = <main title={await getTitle()}><Card>{await user.name()}</Card>{items.map(async i => <li>{(await load(i)).label}</li>)}{"awaiting"}</main>;
+const plain = <p>{awaited}</p>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"const el = __jsxAsync`<main title="${getTitle()}">${__jsxComponent(Card, [], __jsxAsync`${user.name()}`)}${__jsxList(items.map(async i => __jsxAsync`<li>${(async () => ((await load(i)).label))()}</li>`))}${"awaiting"}</main>`;
+const plain = `<p>${awaited}</p>`;"#
+    );
+
+    let options = TransformOptions {
+        async_helper: "renderAsync".to_string(),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(r#"const el = <p>{(await a) + b}</p>;"#, &options).unwrap(),
+        "const el = renderAsync`<p>${(async () => ((await a) + b))()}</p>`;"
+    );
+
+    // Awaited props make the component call a promise
+    assert_eq!(
+        jsx_transformer("<p><A x={await y} z={z} /></p>").unwrap(),
+        r#"__jsxAsync`<p>${(async () => (__jsxComponent(A, [{"x":await y},{"z":z}])))()}</p>`"#
+    );
+}
+
+#[test]
+fn test_stream_iterables() {
+    let source = r#"const el = <ul>{users.values().map(u => <li>{u.name}</li>)}{Object.keys(tags).map(t => <b>{t}</b>)}</ul>;
+const rows = <table>{rows()}{cells.entries()}</table>;"#;
+    let options = TransformOptions {
+        stream_functions: vec!["rows".to_string()],
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = __jsxAsync`<ul>${__jsxStream(users.values().map(u => __jsxAsync`<li>${u.name}</li>`))}${__jsxList(Object.keys(tags).map(t => __jsxAsync`<b>${t}</b>`))}</ul>`;
+const rows = __jsxAsync`<table>${__jsxStream(rows())}${__jsxStream(cells.entries())}</table>`;"#
+    );
+    assert_eq!(
+        jsx_transformer("const el = <ul>{rows()}{Object.entries(o).map(e => <li/>)}</ul>;")
+            .unwrap(),
+        "const el = `<ul>${rows()}${__jsxList(Object.entries(o).map(e => `<li></li>`))}</ul>`;"
+    );
+}
+
+#[test]
+fn test_stream_output_target() {
+    let options = TransformOptions {
+        output: OutputTarget::Stream,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <main class={cls}>{"Hi `x`"}<h1>{await title()}</h1>{items.map(i => <li>{`${i}!`}</li>)}<Card>{label}</Card></main>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = (function* () { yield `<main class="`; yield (cls); yield `">`; yield ("Hi `x`"); yield `<h1>`; yield (title()); yield `</h1>`; yield (__jsxList(items.map(i => __jsxAsync`<li>${`${i}!`}</li>`))); yield (__jsxComponent(Card, [], __jsxAsync`${label}`)); yield `</main>`; }).call(this);"#
+    );
+}
+
+#[test]
+fn test_render_static() {
+    assert_eq!(
+        render_static(
+            r#"<main className="page"><img src="a.png" /><p>Fish &amp; {"chips é"}{`\`ok\``}</p></main>
+<svg viewBox="0 0 1 1"><circle r="1" /></svg>"#
+        )
+        .unwrap(),
+        r#"<main class="page"><img src="a.png"/><p>Fish &amp; chips é`ok`</p></main><svg viewBox="0 0 1 1"><circle r="1"/></svg>"#
+    );
+    assert_eq!(render_static("<><b>1</b>{42}</>").unwrap(), "<b>1</b>42");
+
+    for source in [
+        "<p>{name}</p>",
+        "<p class={cls}>x</p>",
+        "<Card />",
+        "<ul>{items.map(i => <li/>)}</ul>",
+        "const a = <p/>;",
+    ] {
+        assert!(
+            matches!(render_static(source), Err(JSXError::TransformError(_))),
+            "{source}"
+        );
+    }
+    assert!(matches!(
+        render_static("<p>x</b>"),
+        Err(JSXError::ParsingError(_))
+    ));
+    // An interpolation left open is not static, and not a panic either
+    assert!(matches!(
+        render_static("<script>x${</script>"),
+        Err(JSXError::TransformError(message)) if message.contains("unterminated interpolation")
+    ));
+}
+
+#[test]
+fn test_jsx_transform_prose() {
+    let source = r#"# It's "prose" / not JS
+
+Say <Greeting name="Ada" /> when 1 < 2, see <https://sxo.dev> or <me@sxo.dev>.
+Inline `<Card />` and \<b> stay as they are. <!-- <Note /> -->
+
+```jsx
+const el = <Card />;
+```
+
+<section className="hero">
+  <h1>{title}</h1>
+</section>
+"#;
+    let output = jsx_transform_prose(source, &TransformOptions::default()).unwrap();
+    assert_eq!(
+        output.code,
+        r#"# It's "prose" / not JS
+
+Say `${__jsxComponent(Greeting, [{"name":"Ada"}])}` when 1 < 2, see <https://sxo.dev> or <me@sxo.dev>.
+Inline `<Card />` and \<b> stay as they are. <!-- <Note /> -->
+
+```jsx
+const el = <Card />;
+```
+
+`<section class="hero"><h1>${title}</h1></section>`
+"#
+    );
+    assert_eq!(output.usage.components.get("Greeting"), Some(&1));
+    assert!(matches!(
+        jsx_transform_prose("Broken <p>island", &TransformOptions::default()),
+        Err(JSXError::ParsingError(_))
+    ));
+}
+
+#[test]
+fn test_rewrite_url_option() {
+    fn cdn(tag: &str, attr: &str, value: &str) -> String {
+        match value.strip_prefix('/') {
+            Some(path) if attr != "href" || tag == "link" => format!("https://cdn.test/{path}"),
+            _ => format!("{value}?\"q\""),
+        }
+    }
+    let options = TransformOptions {
+        rewrite_url: Some(cdn),
+        ..TransformOptions::default()
+    };
+    let source = r##"const el = <div><link href="/app.css" /><img src='/a.png' srcSet="/a2.png 2x" alt="/x" /><video poster={poster} /><a href="#top">Top</a><Logo src="/logo.svg" /></div>;"##;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r##"const el = `<div><link href="https://cdn.test/app.css"/><img src='https://cdn.test/a.png' srcset="https://cdn.test/a2.png 2x" alt="/x"/><video poster="${poster}"></video><a href="#top?&quot;q&quot;">Top</a>${__jsxComponent(Logo, [{"src":"/logo.svg"}])}</div>`;"##
+    );
+}
+
+#[test]
+fn test_rewrite_url_srcset_candidates() {
+    fn cdn(_: &str, _: &str, value: &str) -> String {
+        format!("https://cdn.test{value}")
+    }
+    let options = TransformOptions {
+        rewrite_url: Some(cdn),
+        ..TransformOptions::default()
+    };
+    let source = r#"<img srcSet="/a.png, /a-2x.png 2x,/a-800.png   800w" />"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"`<img srcset="https://cdn.test/a.png, https://cdn.test/a-2x.png 2x,https://cdn.test/a-800.png   800w"/>`"#
+    );
+}
+
+#[test]
+fn test_rewrite_expression_option() {
+    fn track(site: ExpressionSite<'_>, expr: &str) -> String {
+        match site {
+            ExpressionSite::Child => format!("track({expr})"),
+            ExpressionSite::Attribute { tag, name } => format!("track({expr}, \"{tag}.{name}\")"),
+        }
+    }
+    let options = TransformOptions {
+        rewrite_expression: Some(track),
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <p class={cls} {...rest}>{name}{/* note */}<Card title={title} />{items.map(i => <b>{i}</b>)}</p>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = `<p class="${track(cls, "p.class")}"${__jsxSpread(rest)}>${track(name)}${__jsxComponent(Card, [{"title":track(title, "Card.title")}])}${__jsxList(items.map(i => `<b>${track(i)}</b>`))}</p>`;"#
+    );
+}
+
+#[test]
+fn test_scoped_css_prop() {
+    let source = r#"const el = <section css={`
+    padding: 1rem; /* spacing */
+    & > h2, &:hover { color: red; }
+    a { color: blue }
+    @media (min-width: 40rem) { padding: 2rem; }
+    @keyframes fade { from { opacity: 0 } }
+  `}><h2 className={cls} css="margin: 0">Hi</h2><Card css="x" /><p css="margin: 0">p</p></section>;"#;
+    let options = TransformOptions {
+        scoped_css: true,
+        ..TransformOptions::default()
+    };
+    let output = jsx_transform(source, &options).unwrap();
+    assert_eq!(output.styles.len(), 2);
+    let (section, h2) = {
+        let mut classes = output.styles.keys().cloned();
+        let first = classes.next().unwrap();
+        let second = classes.next().unwrap();
+        if output.styles[&first].contains("padding") {
+            (first, second)
+        } else {
+            (second, first)
+        }
+    };
+    assert!(section.starts_with("sxo-") && section.len() == 12);
+    assert_eq!(
+        output.code,
+        format!(
+            r#"const el = `<section class="{section}"><h2 class="${{`${{cls}} {h2}`}}">Hi</h2>${{__jsxComponent(Card, [{{"css":"x"}}])}}<p class="{h2}">p</p></section>`;"#
+        )
+    );
+    assert_eq!(
+        output.styles[&section],
+        format!(
+            ".{section}{{padding: 1rem;}}.{section} > h2,.{section}:hover{{color: red;}}.{section} a{{color: blue}}@media (min-width: 40rem){{.{section}{{padding: 2rem;}}}}@keyframes fade{{from {{ opacity: 0 }}}}"
+        )
+    );
+    assert_eq!(output.styles[&h2], format!(".{h2}{{margin: 0;}}"));
+    assert_eq!(
+        output.stylesheet(),
+        output.styles.values().cloned().collect::<String>()
+    );
+
+    assert!(matches!(
+        jsx_transformer_with_options(r#"const el = <p css={`color: ${c}`}>x</p>;"#, &options),
+        Err(JSXError::TransformError(_))
+    ));
+    // Quoted strings and URLs may hold `;`, `{` and `}`
+    let output = jsx_transform(
+        r#"<p css="background:url('a;b{');color:red;content:'}';mask:url(c;d{}.svg)">x</p>"#,
+        &options,
+    )
+    .unwrap();
+    let class = output.styles.keys().next().unwrap();
+    assert_eq!(
+        output.styles[class],
+        format!(
+            ".{class}{{background:url('a;b{{');color:red;content:'}}';mask:url(c;d{{}}.svg);}}"
+        )
+    );
+    let output =
+        jsx_transform(r#"<p css={`a[title="}"] { color: red }`}>x</p>"#, &options).unwrap();
+    let class = output.styles.keys().next().unwrap();
+    assert_eq!(
+        output.styles[class],
+        format!(r#".{class} a[title="}}"]{{color: red}}"#)
+    );
+
+    // Opt-in: the `css` prop is a plain attribute by default
+    assert_eq!(
+        jsx_transformer(r#"<div css={styles}>x</div>"#).unwrap(),
+        r#"`<div css="${styles}">x</div>`"#
+    );
+}
+
+#[test]
+fn test_scoped_css_in_component_props() {
+    let options = TransformOptions {
+        scoped_css: true,
+        ..TransformOptions::default()
+    };
+    let output = jsx_transform(
+        r#"const el = <Card title={<b css="color: red">T</b>} />;"#,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(output.styles.len(), 1);
+    assert!(output.stylesheet().ends_with("{color: red;}"));
+}
+
+#[test]
+fn test_csp_nonce_option() {
+    let source = r#"const el = <head><script>boot()</script><script src="/app.js"></script><style>{css}</style><script nonce="own">x()</script></head>;"#;
+    let transform = |nonce| {
+        let options = TransformOptions {
+            csp_nonce: Some(nonce),
+            ..TransformOptions::default()
+        };
+        jsx_transformer_with_options(source, &options).unwrap()
+    };
+
+    assert_eq!(
+        transform(CspNonce::Static("r4nd0m".to_string())),
+        r#"const el = `<head><script nonce="r4nd0m">boot()</script><script src="/app.js"></script><style nonce="r4nd0m">${css}</style><script nonce="own">x()</script></head>`;"#
+    );
+    assert_eq!(
+        transform(CspNonce::Expression("ctx.nonce".to_string())),
+        r#"const el = `<head><script nonce="${ctx.nonce}">boot()</script><script src="/app.js"></script><style nonce="${ctx.nonce}">${css}</style><script nonce="own">x()</script></head>`;"#
+    );
+    assert_eq!(
+        jsx_transformer("<script>boot()</script>").unwrap(),
+        "`<script>boot()</script>`"
+    );
+}
+
+#[test]
+fn test_script_hashes_option() {
+    let options = TransformOptions {
+        script_hashes: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const page = <html>
+  <script>alert('Hello, world.');</script>
+  <script type="module">import "/app.js";</script>
+  <script>window.user = ${user}</script>
+  <script src="/vendor.js"></script>
+  <script {...attrs}>spread()</script>
+  <script>alert('Hello, world.');</script>
+  <Layout head={<script></script>} foot={<script>a
+</script>} />
+</html>;"#;
+    let output = jsx_transform(source, &options).unwrap();
+    assert_eq!(
+        output.script_hashes,
+        vec![
+            // Example from the CSP Level 3 specification
+            "sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng=".to_string(),
+            "sha256-vOWEJ+IaLefzaAC9VTndHcG/R8RWB02IsaFguBtIDNM=".to_string(),
+            // The emitted body, `a`, with JSX whitespace trimmed
+            "sha256-ypeBEsobvcr6wjGzmiPcTaeG7/gUfE5yuYB3ha/uSLs=".to_string(),
+        ]
+    );
+    assert!(jsx_transform(source, &TransformOptions::default())
+        .unwrap()
+        .script_hashes
+        .is_empty());
+    for source in ["<script>x${</script>", "<script>x${a</script>"] {
+        assert!(jsx_transform(source, &options)
+            .unwrap()
+            .script_hashes
+            .is_empty());
+    }
+}
+
+#[test]
+fn test_js_transform_options() {
+    let js: JsTransformOptions = serde_json::from_str(
+        r#"{
+            "minify": true,
+            "componentHelper": "h",
+            "listMode": "inlineJoin",
+            "eventHandlers": "preserveCamel",
+            "actionHelper": "register",
+            "filename": "app.jsx",
+            "sourceAnnotations": true,
+            "cspNonceExpression": "ctx.nonce",
+            "attrNameMap": { "tw": "class" }
+        }"#,
+    )
+    .unwrap();
+    let options = js.apply(&TransformOptions::default());
+    assert!(options.minify);
+    assert_eq!(options.component_helper, "h");
+    assert_eq!(options.list_mode, ListMode::InlineJoin);
+    assert_eq!(options.event_handlers, EventHandlerMode::PreserveCamel);
+    assert_eq!(options.action_helper, "register");
+    assert_eq!(options.source_annotations.as_deref(), Some("app.jsx"));
+    assert_eq!(
+        options.csp_nonce,
+        Some(CspNonce::Expression("ctx.nonce".to_string()))
+    );
+    assert_eq!(
+        options.attr_name_map.get("tw").map(String::as_str),
+        Some("class")
+    );
+    // Fields left out keep the base values
+    assert!(!options.fold_constants);
+    assert_eq!(options.output, OutputTarget::Template);
+
+    let js: JsTransformOptions =
+        serde_json::from_str(r#"{ "listHelper": "join", "output": "stream" }"#).unwrap();
+    let options = js.apply(&TransformOptions::default());
+    assert_eq!(options.list_mode, ListMode::Helper("join".to_string()));
+    assert_eq!(options.output, OutputTarget::Stream);
+
+    assert!(serde_json::from_str::<JsTransformOptions>(r#"{ "minfy": true }"#).is_err());
+    assert!(serde_json::from_str::<JsTransformOptions>(r#"{ "output": "html" }"#).is_err());
+}
+
+#[test]
+fn test_js_transform_result() {
+    let options = TransformOptions {
+        hydration_markers: true,
+        scoped_css: true,
+        ..TransformOptions::default()
+    };
+    let result = JsTransformResult::transform(
+        r#"const el = <main><Counter /><my-icon /><p css="color: red">x</p></main>;"#,
+        &options,
+    );
+    let json = serde_json::to_value(&result).unwrap();
+    let class = result.metadata.styles.keys().next().unwrap().clone();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "code": format!("const el = `<main><!--sxo:Counter:sxo-0-->${{__jsxComponent(Counter, [])}}<!--/sxo:sxo-0--><my-icon></my-icon><p class=\"{class}\">x</p></main>`;"),
+            "map": result.map,
+            "diagnostics": [],
+            "metadata": {
+                "hydration": { "Counter": ["sxo-0"] },
+                "components": { "Counter": 1 },
+                "webComponents": { "my-icon": 1 },
+                "styles": { &class: format!(".{class}{{color: red;}}") },
+                "stylesheet": format!(".{class}{{color: red;}}"),
+                "scriptHashes": [],
+                "spreadOrder": "source"
+            }
+        })
+    );
+}
+
+#[test]
+fn test_js_transform_source_map() {
+    let options = TransformOptions {
+        source_annotations: Some("app.jsx".to_string()),
+        ..TransformOptions::default()
+    };
+    let source = "const a = 1;\nconst el = <ul>\n  <li>é{a}</li>\n</ul>;\nf(el);";
+    let result = JsTransformResult::transform(source, &options);
+    assert_eq!(
+        result.map.as_deref(),
+        Some(
+            r#"{"version":3,"sources":["app.jsx"],"sourcesContent":["const a = 1;\nconst el = <ul>\n  <li>é{a}</li>\n</ul>;\nf(el);"],"names":[],"mappings":"AAAA;AACA,WAAW,sFAEN;AACL"}"#
+        )
+    );
+
+    // Lines of a template map to the lines of its JSX; the code after it to
+    // where the JSX ends
+    let source = "x(<p>\n  {`a\nb`}\n  <i/>\n</p>, 1);";
+    let result = JsTransformResult::transform(source, &TransformOptions::default());
+    assert_eq!(
+        result.code.as_deref(),
+        Some("x(`<p>${`a\nb`}<i></i></p>`, 1);")
+    );
+    let map: serde_json::Value = serde_json::from_str(result.map.as_deref().unwrap()).unwrap();
+    assert_eq!(map["sources"], serde_json::json!(["<anonymous>"]));
+    assert_eq!(map["mappings"], "AAAA,EAAE;AACF,eAGI");
+
+    let failed = JsTransformResult::transform("<p>", &TransformOptions::default());
+    assert_eq!(failed.map, None);
+}
+
+#[test]
+fn test_js_transform_result_diagnostics() {
+    let result = JsTransformResult::transform(
+        "const a = <p>ok</p>;\nconst é = <div><b></div>;",
+        &TransformOptions::default(),
+    );
+    assert_eq!(result.code, None);
+    let located: Vec<_> = result
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.line, d.column, d.span))
+        .collect();
+    assert!(located.iter().all(|d| d.0 == "parse-error"));
+    assert!(located.iter().all(|d| d.1 == Some(2)));
+    // UTF-16 offsets and character columns are not byte offsets past `é`
+    let (_, _, Some(column), Some((start, end))) = located[0] else {
+        panic!("missing location");
+    };
+    assert_eq!(start, end);
+    assert_eq!(start, 21 + column - 1);
+
+    // Emoji count as two UTF-16 code units
+    let result = JsTransformResult::transform("/* \u{1F600} */ <p>", &TransformOptions::default());
+    let d = &result.diagnostics[0];
+    assert_eq!(d.utf16_column, Some(d.column.unwrap() + 1));
+    assert_eq!(d.span.unwrap().0 + 1, d.utf16_column.unwrap());
+
+    let scoped_css = TransformOptions {
+        scoped_css: true,
+        ..TransformOptions::default()
+    };
+    let result = JsTransformResult::transform(r#"<p css={`a: ${b}`}>x</p>"#, &scoped_css);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, "transform-error");
+    assert_eq!(result.diagnostics[0].line, None);
+
+    let json = serde_json::to_value(JsTransformResult::error(
+        "invalid-options",
+        "Invalid options: x",
+    ))
+    .unwrap();
+    assert_eq!(
+        json["diagnostics"],
+        serde_json::json!([{ "code": "invalid-options", "message": "Invalid options: x", "line": null, "column": null, "utf16Column": null, "span": null }])
+    );
+}
+
+#[test]
+fn test_js_transform_error() {
+    let source = "const a = <p>ok</p>;\nconst b = <div></span>;";
+    let error = jsx_transformer(source).unwrap_err();
+    let js_error = JsTransformError::new(source, &TransformOptions::default(), &error);
+    assert_eq!(js_error.message, error.to_string());
+    assert_eq!(js_error.code, "parse-error");
+    assert_eq!(js_error.line, Some(2));
+    assert_eq!(js_error.column, js_error.diagnostics[0].column);
+    assert_eq!(js_error.span, js_error.diagnostics[0].span);
+
+    let json = serde_json::to_value(&js_error).unwrap();
+    for field in [
+        "code",
+        "message",
+        "line",
+        "column",
+        "utf16Column",
+        "span",
+        "diagnostics",
+    ] {
+        assert!(json.get(field).is_some(), "missing {field}");
+    }
+
+    let scoped_css = TransformOptions {
+        scoped_css: true,
+        ..TransformOptions::default()
+    };
+    let error =
+        jsx_transformer_with_options(r#"<p css={`a: ${b}`}>x</p>"#, &scoped_css).unwrap_err();
+    let js_error = JsTransformError::new("", &scoped_css, &error);
+    assert_eq!(js_error.code, "transform-error");
+    assert_eq!((js_error.line, js_error.span), (None, None));
+}
+
+#[test]
+fn test_transform_with_progress() {
+    let source = "const a = <p>a</p>;\nconst b = <p>b</p>;\nconst c = <p>c</p>;\n";
+    let mut reports = Vec::new();
+    let output = jsx_transform_with_progress(
+        source,
+        &TransformOptions::default(),
+        20,
+        &mut |processed, total| {
+            reports.push((processed, total));
+            true
+        },
+    )
+    .unwrap();
+    assert_eq!(output.code, jsx_transformer(source).unwrap());
+    assert!(reports.len() >= 3);
+    assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+    assert_eq!(reports.last(), Some(&(source.len(), source.len())));
+
+    let mut calls = 0;
+    let result =
+        jsx_transform_with_progress(source, &TransformOptions::default(), 20, &mut |_, _| {
+            calls += 1;
+            false
+        });
+    assert!(matches!(result, Err(JSXError::Cancelled(at)) if at >= 20 && at < source.len()));
+    assert_eq!(calls, 1);
+
+    let result = JsTransformResult::from_result(source, &TransformOptions::default(), result);
+    assert_eq!(result.code, None);
+    assert_eq!(result.diagnostics[0].code, "cancelled");
+}
+
+#[test]
+fn test_transform_limits() {
+    let source = "const a = <p>1</p>;\nconst b = <ul><li>2</li><li>{3}</li></ul>;";
+    let with = |options: TransformOptions| jsx_transform(source, &options);
+
+    let result = with(TransformOptions {
+        max_input_bytes: 16,
+        ..TransformOptions::default()
+    });
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        format!(
+            "JSX transform limit exceeded: the input is {} bytes, over max_input_bytes (16)",
+            source.len()
+        )
+    );
+    assert!(with(TransformOptions {
+        max_input_bytes: source.len(),
+        ..TransformOptions::default()
+    })
+    .is_ok());
+
+    // <p>, its text, <ul>, two <li>, their text and expression
+    let max_nodes = |max_nodes| {
+        with(TransformOptions {
+            max_nodes,
+            ..TransformOptions::default()
+        })
+    };
+    assert!(max_nodes(7).is_ok());
+    assert_eq!(
+        max_nodes(6).unwrap_err().to_string(),
+        "JSX transform limit exceeded: more than max_nodes (6) JSX nodes at byte 30"
+    );
+
+    let result = with(TransformOptions {
+        timeout: Some(std::time::Duration::ZERO),
+        ..TransformOptions::default()
+    });
+    assert!(
+        matches!(&result, Err(JSXError::LimitExceeded(msg)) if msg.starts_with("timeout of 0 ms"))
+    );
+    let result = JsTransformResult::from_result(source, &TransformOptions::default(), result);
+    assert_eq!(result.diagnostics[0].code, "limit-exceeded");
+
+    let js: JsTransformOptions =
+        serde_json::from_str(r#"{ "maxInputBytes": 1024, "maxNodes": 10, "timeoutMs": 500 }"#)
+            .unwrap();
+    let options = js.apply(&TransformOptions::default());
+    assert_eq!(
+        (options.max_input_bytes, options.max_nodes, options.timeout),
+        (1024, 10, Some(std::time::Duration::from_millis(500)))
+    );
+}
+
+#[test]
+fn test_js_parse_result() {
+    let source = "const é = <a href=\"/\" title={t}>Hi {name}</a>;";
+    let json = serde_json::to_value(JsParseResult::parse(source)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "nodes": [{
+                "span": [10, 45],
+                "type": "element",
+                "tag": "a",
+                "attributes": [
+                    { "name": "href", "value": "/", "expression": false },
+                    { "name": "title", "value": "t", "expression": true }
+                ],
+                "children": [
+                    { "type": "text", "value": "Hi " },
+                    { "type": "expression", "code": "name" }
+                ]
+            }],
+            "diagnostics": []
+        })
+    );
+
+    let result = JsParseResult::parse("const a = <div></span>;");
+    assert!(result.nodes.is_empty());
+    assert_eq!(result.diagnostics[0].code, "parse-error");
+    assert_eq!(result.diagnostics[0].line, Some(1));
+}
+
+#[test]
+fn test_incremental_retransform() {
+    let options = TransformOptions::default();
+    let before = "const a = <p>a</p>;\nconst b = <Card title=\"b\" />;\nconst c = <p>c</p>;\n";
+    let first = jsx_transform_incremental(before, &options).unwrap();
+    assert_eq!(first.output, jsx_transform(before, &options).unwrap());
+    assert_eq!(first.reused, 0);
+
+    // Edit inside the second node: the first and third are reused
+    let edit = before.find("\"b\"").unwrap() + 1;
+    let after = before.replacen("\"b\"", "\"bee\"", 1);
+    let second = jsx_retransform(&first, before, &after, edit..edit + 1, &options).unwrap();
+    assert_eq!(second.output, jsx_transform(&after, &options).unwrap());
+    assert_eq!(second.reused, 2);
+
+    // An edit opening a comment changes what follows
+    let edit = after.find("const c").unwrap();
+    let commented = format!("{}/* {}", &after[..edit], &after[edit..]);
+    let third = jsx_retransform(&second, &after, &commented, edit..edit, &options).unwrap();
+    assert_eq!(third.output, jsx_transform(&commented, &options).unwrap());
+    assert!(!third.output.code.contains("`<p>c</p>`"));
+
+    // A wrong range is caught by comparing the reused text
+    let fourth = jsx_retransform(&first, before, &after, 0..0, &options).unwrap();
+    assert_eq!(fourth.output, jsx_transform(&after, &options).unwrap());
+
+    // Position-dependent output is never reused
+    let options = TransformOptions {
+        hydration_markers: true,
+        ..Default::default()
+    };
+    let first = jsx_transform_incremental(before, &options).unwrap();
+    let second = jsx_retransform(&first, before, before, 0..0, &options).unwrap();
+    assert_eq!(second.reused, 0);
+    assert_eq!(second.output, jsx_transform(before, &options).unwrap());
+}
+
+#[test]
+fn test_max_errors() {
+    let source = "const a = <p></b>;\n".repeat(80);
+    let error = jsx_transformer(&source).unwrap_err().to_string();
+    assert_eq!(error.matches("  --> input:").count(), DEFAULT_MAX_ERRORS);
+    assert!(error.ends_with("too many errors, stopped after 50; raise `max_errors` to report more"));
+
+    let options = TransformOptions {
+        max_errors: 3,
+        ..Default::default()
+    };
+    let result = JsTransformResult::transform(&source, &options);
+    assert_eq!(result.diagnostics.len(), 4);
+    assert_eq!(result.diagnostics[2].line, Some(3));
+    assert!(result.diagnostics[3].message.starts_with("too many errors"));
+
+    let options = TransformOptions {
+        max_errors: 0,
+        ..Default::default()
+    };
+    let error = jsx_transform(&source, &options).unwrap_err().to_string();
+    assert_eq!(error.matches("  --> input:").count(), 80);
+    assert!(!error.contains("too many errors"));
+}
+
+#[test]
+fn test_parse_error_resync() {
+    // The nested tags of a broken element are not reported again
+    let source = "const a = <div><b><i>x</i></div>;\nconst c = <p></b>;";
+    let errors = parse_jsx(source).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].position, source.find("<div>").unwrap());
+    assert_eq!(errors[1].position, source.find("<p>").unwrap());
+
+    // An unclosed tag does not hide the mistakes after it
+    let source = "const a = <p>;\nconst b = <ok/>;\nconst c = <i></b>;";
+    let errors = parse_jsx(source).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].position, source.find("<i>").unwrap());
+    assert_eq!(
+        jsx_transformer(source)
+            .unwrap_err()
+            .to_string()
+            .matches("  --> input:")
+            .count(),
+        2
+    );
+}
+
+#[cfg(feature = "unicode-identifiers")]
+#[test]
+fn test_unicode_identifiers() {
+    assert_eq!(
+        jsx_transformer("const a = <Über größe=\"1\"><ñandú/></Über>;").unwrap(),
+        "const a = `${__jsxComponent(Über, [{\"größe\":\"1\"}], `<ñandú></ñandú>`)}`;"
+    );
+    let output = jsx_transform_prose("Ciao <Ünïcode/>", &TransformOptions::default()).unwrap();
+    assert!(output.code.contains("__jsxComponent(Ünïcode"));
+}
+
+#[test]
+fn test_bom_and_line_endings() {
+    let source = "\u{FEFF}const a = <p>x</p>;";
+    assert_eq!(jsx_transformer(source).unwrap(), "const a = `<p>x</p>`;");
+    let options = TransformOptions {
+        strip_bom: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        jsx_transform(source, &options).unwrap().code,
+        "\u{FEFF}const a = `<p>x</p>`;"
+    );
+
+    // Columns do not count the byte order mark
+    let source = "\u{FEFF}const a = <p>;";
+    let error = jsx_transformer(source).unwrap_err().to_string();
+    assert!(error.contains("input:1:11"), "{error}");
+    let result = JsTransformResult::transform(source, &TransformOptions::default());
+    let d = &result.diagnostics[0];
+    assert_eq!((d.column, d.utf16_column), (Some(11), Some(11)));
+    // JS string index, past the byte order mark
+    assert_eq!(d.span, Some((11, 11)));
+
+    let source = "const a = <ul>\r\n  <li>x</li>\r\n</ul>;\r\n";
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        "const a = `<ul><li>x</li></ul>`;\r\n"
+    );
+    let options = TransformOptions {
+        normalize_line_endings: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        jsx_transform(source, &options).unwrap().code,
+        "const a = `<ul><li>x</li></ul>`;\n"
+    );
+
+    // No carriage return in the quoted line
+    let error = jsx_transformer("const a = <p>\r\n<b></p>;\r\n")
+        .unwrap_err()
+        .to_string();
+    assert!(!error.contains('\r'), "{error:?}");
+
+    // Stripping the byte order mark shifts spans: nothing is reused
+    let options = TransformOptions::default();
+    let before = "\u{FEFF}const a = <p>a</p>;\nconst b = <p>b</p>;";
+    let after = "\u{FEFF}const a = <p>A</p>;\nconst b = <p>b</p>;";
+    let at = before.find("<p>a").unwrap() + 3;
+    let edit = at..at + 1;
+    let previous = jsx_transform_incremental(before, &options).unwrap();
+    let output = jsx_retransform(&previous, before, after, edit, &options).unwrap();
+    assert_eq!(output.reused, 0);
+    assert_eq!(output.output.code, jsx_transformer(after).unwrap());
+}
+
+#[test]
+fn test_raw_expression_marker() {
+    let source = "const el = <div>{/* @sxo-raw */ h(<b>x</b>)}<Card render={/*@sxo-raw*/ () => <i/>}/>{ok && <i/>}</div>;";
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"const el = `<div>${/* @sxo-raw */ h(<b>x</b>)}${__jsxComponent(Card, [{"render":/*@sxo-raw*/ () => <i/>}])}${ok && `<i></i>`}</div>`;"#
+    );
+
+    // An empty marker transforms every expression
+    let options = TransformOptions {
+        raw_expression_marker: String::new(),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = `<div>${/* @sxo-raw */ h(`<b>x</b>`)}${__jsxComponent(Card, [{"render":/*@sxo-raw*/ () => `<i></i>`}])}${ok && `<i></i>`}</div>`;"#
+    );
+
+    // Elements after a raw expression keep their positions
+    let options = TransformOptions {
+        source_annotations: Some("a.jsx".to_string()),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options("<p>{/* @sxo-raw */ <b/>}<i/></p>", &options).unwrap(),
+        r#"`<p data-sxo-source="a.jsx:1:1">${/* @sxo-raw */ <b/>}<i data-sxo-source="a.jsx:1:25"></i></p>`"#
+    );
+}
+
+#[test]
+fn test_comparisons_in_expressions() {
+    assert_eq!(
+        jsx_transformer("<div>{count < 3 && <Badge/>}{a<b && <i/>}{x<y}{a<b>c}{f(x) <b && <i/>}</div>").unwrap(),
+        "`<div>${count < 3 && `${__jsxComponent(Badge, [])}`}${a<b && `<i></i>`}${x<y}${a<b>c}${f(x) <b && `<i></i>`}</div>`"
+    );
+    assert_eq!(
+        jsx_transformer("<ul>{items.filter(i => i<max).map(i => <li>{i}</li>)}</ul>").unwrap(),
+        "`<ul>${__jsxList(items.filter(i => i<max).map(i => `<li>${i}</li>`))}</ul>`"
+    );
+    // Component props
+    assert_eq!(
+        jsx_transformer("<Card badge={n<3 && <b/>}/>").unwrap(),
+        r#"`${__jsxComponent(Card, [{"badge":n<3 && `<b></b>`}])}`"#
+    );
+    // After a statement head or block, JSX if it parses, a comparison if not
+    assert_eq!(
+        jsx_transformer("<p>{() => { if (a) <i/>; }}{ {}<y }</p>").unwrap(),
+        "`<p>${() => { if (a) `<i></i>`; }}${ {}<y }</p>`"
+    );
+}
+
+#[test]
+fn test_template_literals_in_expressions() {
+    // Kept verbatim, braces and tags in their text included
+    assert_eq!(
+        jsx_transformer("<div>{html`<b>${x}</b>`}{css`a {`}{html`<b>${ok && <i/>}</b>`}{`a${`}`}b`}<i/></div>").unwrap(),
+        "`<div>${html`<b>${x}</b>`}${css`a {`}${html`<b>${ok && <i/>}</b>`}${`a${`}`}b`}<i></i></div>`"
+    );
+    assert_eq!(
+        jsx_transformer("<Card style={css`p { color: ${c} }`} label={`}`}/>").unwrap(),
+        r#"`${__jsxComponent(Card, [{"style":css`p { color: ${c} }`},{"label":`}`}])}`"#
+    );
+    assert_eq!(jsx_transformer("<p>{`}`}</p>").unwrap(), "`<p>${`}`}</p>`");
+}
+
+#[test]
+fn test_children_prop() {
+    assert_eq!(
+        jsx_transformer(r#"<Card title="x" children={items} />"#).unwrap(),
+        r#"`${__jsxComponent(Card, [{"title":"x"}], items)}`"#
+    );
+    assert_eq!(
+        jsx_transformer(r#"<Card children={<b>{x}</b>} />"#).unwrap(),
+        "`${__jsxComponent(Card, [], `<b>${x}</b>`)}`"
+    );
+    // Overrides JSX children
+    assert_eq!(
+        jsx_transformer(r#"<Card children="hi">ignored <i/></Card>"#).unwrap(),
+        r#"`${__jsxComponent(Card, [], "hi")}`"#
+    );
+    // Elements keep it as an attribute
+    assert_eq!(
+        jsx_transformer(r#"<div children="hi"></div>"#).unwrap(),
+        r#"`<div children="hi"></div>`"#
+    );
+}
+
+#[test]
+fn test_jsx_in_attribute_values() {
+    // Transformed as in embedded expressions, components recorded
+    let source = r#"<p title={cond ? <Icon/> : 'x'} class={c}>x</p>"#;
+    let output = jsx_transform(source, &TransformOptions::default()).unwrap();
+    assert_eq!(
+        output.code,
+        r#"`<p title="${cond ? `${__jsxComponent(Icon, [])}` : 'x'}" class="${c}">x</p>`"#
+    );
+    assert!(output.usage.components.contains_key("Icon"));
+
+    // Errors in nested JSX are reported rather than kept as written
+    for source in [
+        "<Card title={<b>x</i>} />",
+        "<Card children={<b>x</i>} />",
+        "<p title={ok && <b>x</i>}>y</p>",
+    ] {
+        let err = jsx_transformer(source).expect_err(source);
+        assert!(
+            err.to_string().contains("Mismatched closing tag"),
+            "{source}: {err}"
+        );
+    }
+}
+
+#[test]
+fn test_coerce_literal_props() {
+    let source = r#"<Counter count="42" step='-1.5' flag={false} max={10} id="007" big="1e3" label="a" on/>"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"`${__jsxComponent(Counter, [{"count":"42"},{"step":'-1.5'},{"flag":false},{"max":10},{"id":"007"},{"big":"1e3"},{"label":"a"},{"on":true}])}`"#
+    );
+
+    let options = TransformOptions {
+        coerce_literal_props: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"`${__jsxComponent(Counter, [{"count":42},{"step":-1.5},{"flag":false},{"max":10},{"id":"007"},{"big":"1e3"},{"label":"a"},{"on":true}])}`"#
+    );
+    // Element attributes are markup
+    assert_eq!(
+        jsx_transformer_with_options(r#"<input size="42"/>"#, &options).unwrap(),
+        r#"`<input size="42"/>`"#
+    );
+}
+
+#[test]
+fn test_strip_props() {
+    use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
+
+    let source = r#"<ul ref={list}>{items.map(i => <li key={i.id} class="a">{i}</li>)}<Card key="c" ref={r}/></ul>"#;
+    let options = TransformOptions {
+        strip_props: vec!["key".to_string(), "ref".to_string()],
+        ..TransformOptions::default()
+    };
+    let output = jsx_transform(source, &options).unwrap();
+    assert_eq!(
+        output.code,
+        r#"`<ul>${__jsxList(items.map(i => `<li class="a">${i}</li>`))}${__jsxComponent(Card, [{"key":"c"},{"ref":r}])}</ul>`"#
+    );
+    assert!(output.stripped_props.is_empty());
+
+    let options = TransformOptions {
+        collect_stripped_props: true,
+        ..options
+    };
+    let output = jsx_transform(source, &options).unwrap();
+    let prop = |tag: &str, name: &str, expr: &str| StrippedProp {
+        tag: tag.to_string(),
+        attribute: JSXAttribute {
+            name: name.to_string(),
+            value: Some(JSXAttributeValue::Expression(expr.to_string())),
+        },
+    };
+    assert_eq!(
+        output.stripped_props,
+        vec![prop("ul", "ref", "list"), prop("li", "key", "i.id")]
+    );
+}
+
+#[test]
+fn test_form_control_values() {
+    let options = TransformOptions {
+        form_control_values: true,
+        ..TransformOptions::default()
+    };
+    let transform = |source| jsx_transformer_with_options(source, &options).unwrap();
+    assert_eq!(
+        transform(r#"<textarea value={v} rows="3"/>"#),
+        r#"`<textarea rows="3">${v}</textarea>`"#
+    );
+    assert_eq!(
+        transform(r#"<textarea defaultValue="a < b & c"></textarea>"#),
+        r#"`<textarea>a &lt; b &amp; c</textarea>`"#
+    );
+    assert_eq!(
+        transform(r#"<select value={v}><option value="a">A</option></select>"#),
+        r#"`<select><option value="a"${String((v)) === String("a") ? " selected" : ""}>A</option></select>`"#
+    );
+    assert_eq!(
+        transform(
+            r#"<select defaultValue="b"><option value="a">A</option><optgroup label="g"><option value="b">B</option></optgroup></select>"#
+        ),
+        r#"`<select><option value="a">A</option><optgroup label="g"><option value="b" selected>B</option></optgroup></select>`"#
+    );
+    assert_eq!(
+        transform(r#"<select multiple value={vs}><option value="a">A</option></select>"#),
+        r#"`<select multiple><option value="a"${[].concat((vs)).map(String).includes(String("a")) ? " selected" : ""}>A</option></select>`"#
+    );
+
+    // Off by default
+    assert_eq!(
+        jsx_transformer(r#"<textarea value={v}/>"#).unwrap(),
+        r#"`<textarea value="${v}"></textarea>`"#
+    );
+}
+
+#[test]
+fn test_spread_order() {
+    let source = r#"<div id="a" {...props} class="b" {...rest}/>"#;
+    let render = |spread_order| {
+        let options = TransformOptions {
+            spread_order,
+            ..TransformOptions::default()
+        };
+        jsx_transformer_with_options(source, &options).unwrap()
+    };
+    assert_eq!(
+        render(SpreadOrder::Source),
+        r#"`<div id="a"${__jsxSpread(props)} class="b"${__jsxSpread(rest)}></div>`"#
+    );
+    assert_eq!(
+        render(SpreadOrder::ExplicitFirst),
+        r#"`<div id="a" class="b"${__jsxSpread(props)}${__jsxSpread(rest)}></div>`"#
+    );
+    assert_eq!(
+        render(SpreadOrder::SpreadFirst),
+        r#"`<div${__jsxSpread(props)}${__jsxSpread(rest)} id="a" class="b"></div>`"#
+    );
+
+    let js: JsTransformOptions =
+        serde_json::from_str(r#"{"spreadOrder":"explicitFirst"}"#).unwrap();
+    let options = js.apply(&TransformOptions::default());
+    assert_eq!(options.spread_order, SpreadOrder::ExplicitFirst);
+    let result = JsTransformResult::transform(source, &options);
+    assert_eq!(result.metadata.spread_order, "explicit-first");
+}
+
+#[test]
+fn test_transform_in_ranges() {
+    let source = "const cmp = a <b> c;\nfunction render() { return <p>{x}</p>; }\nconst d = <i/>;";
+    let body = source.find("{ return").unwrap()..source.find("}\n").unwrap() + 1;
+    assert_eq!(
+        jsx_transformer_in_ranges(source, std::slice::from_ref(&body)).unwrap(),
+        "const cmp = a <b> c;\nfunction render() { return `<p>${x}</p>`; }\nconst d = <i/>;"
+    );
+    let last = source.rfind("<i/>").unwrap()..source.len();
+    assert_eq!(
+        jsx_transformer_in_ranges(source, &[last, body]).unwrap(),
+        "const cmp = a <b> c;\nfunction render() { return `<p>${x}</p>`; }\nconst d = `<i></i>`;"
+    );
+    assert_eq!(jsx_transformer_in_ranges(source, &[]).unwrap(), source);
+
+    // Ranges are offsets of the source as given
+    let source = "\u{FEFF}a <b> c;\r\nconst d = <i/>;";
+    let options = TransformOptions {
+        normalize_line_endings: true,
+        ..TransformOptions::default()
+    };
+    let range = source.find("<i/>").unwrap()..source.len();
+    assert_eq!(
+        jsx_transform_in_ranges(source, &options, &[range])
+            .unwrap()
+            .code,
+        "a <b> c;\nconst d = `<i></i>`;"
+    );
+}
+
+#[test]
+fn test_jsx_start_filter() {
+    let source = "const [a] = useState<string>(\"\");\nconst b = <p>{a}</p>;";
+    assert!(jsx_transformer(source).is_err());
+
+    // `<` right after an identifier opens type arguments, not JSX
+    fn after_operand(start: JsxStartContext<'_>) -> bool {
+        start.previous == "return" || !start.previous.starts_with(|c: char| c.is_alphanumeric())
+    }
+    let options = TransformOptions {
+        jsx_start_filter: Some(after_operand),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const [a] = useState<string>(\"\");\nconst b = `<p>${a}</p>`;"
+    );
+    assert_eq!(
+        jsx_transformer_with_options("function f() { return<i/>; }", &options).unwrap(),
+        "function f() { return`<i></i>`; }"
+    );
+
+    fn previous(start: JsxStartContext<'_>) -> bool {
+        let expected = match start.at {
+            0 => "",
+            _ => "=",
+        };
+        assert_eq!(start.previous, expected);
+        true
+    }
+    let options = TransformOptions {
+        jsx_start_filter: Some(previous),
+        ..TransformOptions::default()
+    };
+    assert!(jsx_transformer_with_options("<br/>; x = <br/>", &options).is_ok());
+}
+
+#[test]
+fn test_multiple_roots_in_expression() {
+    // Adjacent roots join one template, as fragment children would
+    assert_eq!(
+        jsx_transformer("<div>{cond && <i/><b/>}</div>").unwrap(),
+        "`<div>${cond && `<i></i><b></b>`}</div>`"
+    );
+    assert_eq!(
+        jsx_transformer("<div>{cond ? <i/> <b>x</b> : null}</div>").unwrap(),
+        "`<div>${cond ? `<i></i> <b>x</b>` : null}</div>`"
+    );
+    assert_eq!(
+        jsx_transformer("<dl>{items.map(i => <dt>{i}</dt>\n  <dd/>)}</dl>").unwrap(),
+        "`<dl>${__jsxList(items.map(i => `<dt>${i}</dt><dd></dd>`))}</dl>`"
+    );
+    assert_eq!(
+        jsx_transformer("<div>{cond ? <A/><B/> : <C/>}</div>").unwrap(),
+        "`<div>${cond ? `${__jsxComponent(A, [])}${__jsxComponent(B, [])}` : `${__jsxComponent(C, [])}`}</div>`"
+    );
+    // Roots separated by code stay apart
+    assert_eq!(
+        jsx_transformer("<div>{cond ? <i/> : <b/>}</div>").unwrap(),
+        "`<div>${cond ? `<i></i>` : `<b></b>`}</div>`"
+    );
+
+    let options = TransformOptions {
+        fold_constants: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options("<div>{true ? <i/> <b/> : <u/>}</div>", &options).unwrap(),
+        "`<div><i></i> <b></b></div>`"
+    );
+}
+
+#[test]
+fn test_verify_output() {
+    let options = TransformOptions {
+        verify_output: true,
+        ..TransformOptions::default()
+    };
+    let source = r#"const el = <ul class={`a ${b}`} title="(">{items.map(i => <li hidden={i / 2 > 1}>{i} $ {"("} {`)`}</li>)}</ul>;"#;
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        jsx_transformer(source).unwrap()
+    );
+
+    fn unclosed(_: ExpressionSite<'_>, expr: &str) -> String {
+        format!("wrap({expr}")
+    }
+    let options = TransformOptions {
+        rewrite_expression: Some(unclosed),
+        verify_output: true,
+        ..TransformOptions::default()
+    };
+    let source = "const a = 1;\nconst el = <p>{name}</p>;";
+    let error = jsx_transformer_with_options(source, &options)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("generated code is not balanced: expected `)`, found `}`"));
+    assert!(error.contains("--> input:2:12"), "{error}");
+    // Off by default
+    let options = TransformOptions {
+        rewrite_expression: Some(unclosed),
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        "const a = 1;\nconst el = `<p>${wrap(name}</p>`;"
+    );
+}
+
+#[test]
+fn test_post_process() {
+    fn banner(code: &str) -> String {
+        format!("/* @license MIT */\n{}", code.replace("__jsx", "$jsx"))
+    }
+    let options = TransformOptions {
+        post_process: Some(banner),
+        ..TransformOptions::default()
+    };
+    // Called once, on the whole file: not on the JSX of the `icon` prop
+    assert_eq!(
+        jsx_transformer_with_options("const el = <Card icon={<i/>}>{x}</Card>;", &options).unwrap(),
+        "/* @license MIT */\nconst el = `${$jsxComponent(Card, [{\"icon\":`<i></i>`}], `${x}`)}`;"
+    );
+    assert_eq!(
+        jsx_transformer_with_options("", &options).unwrap(),
+        "/* @license MIT */\n"
+    );
+    let output = jsx_transform_incremental("const a = <p/>;", &options).unwrap();
+    assert_eq!(
+        output.output.code,
+        "/* @license MIT */\nconst a = `<p></p>`;"
+    );
+}
+
+#[test]
+fn test_attribute_helper() {
+    let source = r#"const el = <a class={cls} href="/x" title={await t()} {...rest}>{name}<Card title={title} /></a>;"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"const el = __jsxAsync`<a class="${cls}" href="/x" title="${t()}"${__jsxSpread(rest)}>${name}${__jsxComponent(Card, [{"title":title}])}</a>`;"#
+    );
+    let options = TransformOptions {
+        attribute_helper: Some(DEFAULT_ATTRIBUTE_HELPER.to_string()),
+        ..TransformOptions::default()
+    };
+    // Component props and children are left alone
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"const el = __jsxAsync`<a class="${__jsxAttr(cls)}" href="/x" title="${(async () => (__jsxAttr(await t())))()}"${__jsxSpread(rest)}>${name}${__jsxComponent(Card, [{"title":title}])}</a>`;"#
+    );
+
+    let options = TransformOptions {
+        attribute_helper: Some("esc".to_string()),
+        quote_style: QuoteStyle::Single,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options("<p data-x={x}/>", &options).unwrap(),
+        "`<p data-x='${esc(x)}'></p>`"
+    );
+}
+
+#[test]
+fn test_spread_options() {
+    let source = r#"<a id="a" {...props} {...{ className: "b", "data-x": 'y', tabIndex: -1, hidden: true, draggable: false, title: null, }}/>"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"`<a id="a"${__jsxSpread(props)}${__jsxSpread({ className: "b", "data-x": 'y', tabIndex: -1, hidden: true, draggable: false, title: null, })}></a>`"#
+    );
+
+    let options = TransformOptions {
+        spread_helper: "attrs".to_string(),
+        spread_leading_space: true,
+        expand_static_spreads: true,
+        spread_order: SpreadOrder::SpreadFirst,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"`<a ${attrs(props)} id="a" class="b" data-x="y" tabindex="-1" hidden></a>`"#
+    );
+    // Anything not static stays a runtime spread
+    for spread in [
+        "{...{ id }}",
+        "{...{ id: x }}",
+        "{...{ [k]: 1 }}",
+        r#"{...{ id: "a\"b" }}"#,
+        "{...{ ...rest }}",
+        "{...{ f(a, b) {} }}",
+    ] {
+        let output = jsx_transformer_with_options(&format!("<a {spread}/>"), &options).unwrap();
+        assert!(output.starts_with("`<a ${attrs("), "{spread}: {output}");
+    }
+    assert_eq!(
+        jsx_transformer_with_options(r#"<a {...({ title: 'say "hi"' })} {...{}}/>"#, &options)
+            .unwrap(),
+        r#"`<a title='say "hi"'></a>`"#
+    );
+}
+
+#[test]
+fn test_merge_classes() {
+    let source = r#"<p id="x" class="a b" className={cls} class='b c' {...rest}>{x}</p>"#;
+    assert_eq!(
+        jsx_transformer(source).unwrap(),
+        r#"`<p id="x" class="a b" class="${cls}" class='b c'${__jsxSpread(rest)}>${x}</p>`"#
+    );
+    let options = TransformOptions {
+        merge_classes: true,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(source, &options).unwrap(),
+        r#"`<p id="x" class="a b ${cls} c"${__jsxSpread(rest)}>${x}</p>`"#
+    );
+    // A single class attribute is left as is
+    assert_eq!(
+        jsx_transformer_with_options("<p className={cls}/>", &options).unwrap(),
+        r#"`<p class="${cls}"></p>`"#
+    );
+
+    let options = TransformOptions {
+        merge_classes: true,
+        expand_static_spreads: true,
+        attribute_helper: Some(DEFAULT_ATTRIBUTE_HELPER.to_string()),
+        quote_style: QuoteStyle::Single,
+        ..TransformOptions::default()
+    };
+    assert_eq!(
+        jsx_transformer_with_options(
+            r#"<p className={await tone()} {...{ class: "it's" }}/>"#,
+            &options
+        )
+        .unwrap(),
+        r#"__jsxAsync`<p class='${(async () => (__jsxAttr(await tone())))()} it&#39;s'></p>`"#
+    );
+}
+
+#[test]
+fn test_empty_expressions() {
+    // `${}` outside of JSX is left alone
+    assert_eq!(
+        jsx_transformer(r#"const s = "${}"; const el = <p>{}{ }{/* a */ /* b */}</p>;"#).unwrap(),
+        r#"const s = "${}"; const el = `<p></p>`;"#
+    );
+    assert_eq!(
+        jsx_transformer("<code>${}{// note\n}</code>").unwrap(),
+        "`<code>$</code>`"
+    );
+    assert_eq!(
+        jsx_transformer("<p title={}>{/* a */ x /* b */}</p>").unwrap(),
+        r#"`<p title="">${/* a */ x /* b */}</p>`"#
+    );
+    assert_eq!(
+        jsx_transformer("<Card>{}</Card>").unwrap(),
+        "`${__jsxComponent(Card, [])}`"
+    );
+}
+
+#[test]
+fn test_single_pass() {
+    let sources = [
+        r#"const a = <div id="x" class={cls}><span>Hi {name}!</span><br/></div>;"#,
+        "const b = <><li>one</li><li>{two}</li></>;",
+        r#"const c = <ul>{items.map((item) => <li key={item.id}>{item.label}</li>)}</ul>;"#,
+        "const d = <p>{ok ? <b>yes</b> : <i>no</i>}{count < 3 && <Badge n={count}/>}</p>;",
+        r#"const e = <Card title="t" {...rest}><Card.Body>{children}</Card.Body></Card>;"#,
+        r#"const f = <select value={v}><option value="a">A</option></select>;"#,
+        "const g = <script>if (a < b) { run(); }</script>;",
+        r#"const h = <svg viewBox="0 0 1 1"><path d="M0"/></svg>;"#,
+        "const i = <p>{await load()}</p>; const j = <p>{x}</p>;",
+        "const k = <ul>{rows.map((row) => <li>{row}</li>)}</ul>; <p>{}</p>",
+        "const l = <div>unclosed; const m = <p>ok</p>;",
+    ];
+    let options = [
+        TransformOptions::default(),
+        TransformOptions {
+            merge_classes: true,
+            source_comments: true,
+            verify_output: true,
+            fold_constants: true,
+            form_control_values: true,
+            ..TransformOptions::default()
+        },
+        TransformOptions {
+            output: OutputTarget::Stream,
+            ..TransformOptions::default()
+        },
+        TransformOptions {
+            hydration_markers: true,
+            ..TransformOptions::default()
+        },
+    ];
+    for options in options {
+        let single_pass = TransformOptions {
+            single_pass: true,
+            ..options.clone()
+        };
+        for source in sources {
+            assert_eq!(
+                format!("{:?}", jsx_transform(source, &single_pass)),
+                format!("{:?}", jsx_transform(source, &options)),
+                "{source}"
+            );
+        }
+    }
+
+    let options = TransformOptions {
+        single_pass: true,
+        max_nodes: 3,
+        ..TransformOptions::default()
+    };
+    assert!(matches!(
+        jsx_transform("<p><b>a</b><i>b</i></p>", &options),
+        Err(JSXError::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn test_transformer() {
+    let options = TransformOptions {
+        merge_classes: true,
+        ..TransformOptions::default()
+    };
+    let transformer = std::sync::Arc::new(Transformer::new(options.clone()));
+    let sources = [
+        r#"const a = <div class="a" className={b}>{name}</div>;"#,
+        "/** @jsx h */ const c = <Card>{children}</Card>;",
+        "const d = <p>unclosed;",
+    ];
+    let threads: Vec<_> = sources
+        .iter()
+        .map(|&source| {
+            let transformer = transformer.clone();
+            std::thread::spawn(move || format!("{:?}", transformer.transform(source)))
+        })
+        .collect();
+    for (source, thread) in sources.iter().zip(threads) {
+        assert_eq!(
+            thread.join().unwrap(),
+            format!("{:?}", jsx_transform(source, &options))
+        );
+    }
+
+    let transformer = Transformer::default();
+    assert_eq!(
+        transformer.transform_code("<p>{x}</p>").unwrap(),
+        jsx_transformer("<p>{x}</p>").unwrap()
+    );
+    let (node, _) = parse_jsx("<b>{y}</b>").unwrap().remove(0);
+    assert_eq!(
+        transformer.transform_node(&node).unwrap(),
+        transform_node(&node, transformer.options()).unwrap()
+    );
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_single_pass_matches_tree(node in crate::jsx_parser::strategies::jsx_node()) {
+        let source = format!("const node = {};", crate::jsx_parser::reprint(&node));
+        let options = TransformOptions {
+            single_pass: true,
+            ..TransformOptions::default()
+        };
+        proptest::prop_assert_eq!(
+            format!("{:?}", jsx_transform(&source, &options)),
+            format!("{:?}", jsx_transform(&source, &TransformOptions::default()))
+        );
+    }
 }
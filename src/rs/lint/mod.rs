@@ -0,0 +1,309 @@
+mod rules;
+mod security;
+
+use crate::jsx_parser::jsx_scanner::find_next_jsx_start;
+use crate::jsx_parser::{
+    recovery_offset, walk_node, ElementSpans, JSXAttribute, JSXVisitor, LineIndex, Parser,
+};
+
+pub use rules::{
+    default_rules, rules_for, AnchorWithoutHref, ChildrenPropConflict, DuplicateAttributes,
+    NestedButton, ObsoleteAttribute, UnknownTag, VoidElementChildren, VoidElementClosingTag,
+};
+pub use security::{security_rules, JavascriptUrl, SrcdocExpression, UnsafeUrlInterpolation};
+
+const PARSE_ERROR_RULE: &str = "parse-error";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Options of `lint_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    /// Report the security rules (`javascript-url`, `srcdoc-expression`,
+    /// `unsafe-url-interpolation`) as errors instead of warnings.
+    pub strict_security: bool,
+    /// Report unknown and obsolete HTML elements and obsolete attributes
+    /// (`unknown-tag`, `obsolete-attribute`) as errors instead of warnings.
+    pub strict_html: bool,
+}
+
+/// A problem found by a lint rule, located by byte offset and by 1-based
+/// line and column, in characters and in UTF-16 code units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub position: usize,
+    pub line: usize,
+    pub column: usize,
+    pub utf16_column: usize,
+}
+
+impl Diagnostic {
+    /// JSON object form, as returned by the WASM export.
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"rule":{},"severity":"{}","message":{},"position":{},"line":{},"column":{},"utf16Column":{}}}"#,
+            json_string(self.rule),
+            self.severity.as_str(),
+            json_string(&self.message),
+            self.position,
+            self.line,
+            self.column,
+            self.utf16_column
+        )
+    }
+}
+
+/// A lint rule. The linter walks each JSX tree with `walk_node` and calls the
+/// hooks of every rule, which report problems through the context.
+pub trait Rule {
+    /// Kebab-case identifier reported with each diagnostic.
+    fn name(&self) -> &'static str;
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    // Called before visiting the children of an element
+    fn enter_element(&mut self, _tag: &str, _attributes: &[JSXAttribute], _cx: &mut LintContext) {}
+
+    // Called after visiting the children of an element
+    fn exit_element(&mut self, _tag: &str, _cx: &mut LintContext) {}
+
+    // Called for a text node
+    fn visit_text(&mut self, _text: &str, _cx: &mut LintContext) {}
+
+    // Called for an expression child, including the ones embedding JSX
+    fn visit_expression(&mut self, _expr: &str, _cx: &mut LintContext) {}
+}
+
+/// What a rule sees of the current node: its position and the elements
+/// enclosing it.
+pub struct LintContext<'a> {
+    rule: &'static str,
+    severity: Severity,
+    position: usize,
+    closing_tag: Option<(usize, usize)>,
+    ancestors: &'a [(String, usize)],
+    reports: &'a mut Vec<Diagnostic>,
+}
+
+impl LintContext<'_> {
+    /// Byte offset of the current element, or of the innermost element
+    /// enclosing the current text or expression.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Span of the closing tag of the element entered or exited, None when it
+    /// is self-closed or for other nodes.
+    pub fn closing_tag(&self) -> Option<(usize, usize)> {
+        self.closing_tag
+    }
+
+    /// Enclosing elements as (tag, position), outermost first.
+    pub fn ancestors(&self) -> &[(String, usize)] {
+        self.ancestors
+    }
+
+    /// Tag of the innermost enclosing element.
+    pub fn parent(&self) -> Option<&str> {
+        self.ancestors.last().map(|(tag, _)| tag.as_str())
+    }
+
+    /// Reports a problem at the current position.
+    pub fn report(&mut self, message: impl Into<String>) {
+        self.report_at(self.position, message);
+    }
+
+    /// Reports a problem at a byte offset of the source.
+    pub fn report_at(&mut self, position: usize, message: impl Into<String>) {
+        // Line and column are filled in once linting is done
+        self.reports.push(Diagnostic {
+            rule: self.rule,
+            severity: self.severity,
+            message: message.into(),
+            position,
+            line: 0,
+            column: 0,
+            utf16_column: 0,
+        });
+    }
+}
+
+// Lints every JSX tree found in the source with the default rules.
+// Parse errors are reported as `parse-error` diagnostics.
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    lint_with_rules(source, &mut default_rules())
+}
+
+// Same as `lint`, with the built-in rules configured by the options.
+pub fn lint_with_options(source: &str, options: &LintOptions) -> Vec<Diagnostic> {
+    lint_with_rules(source, &mut rules_for(options))
+}
+
+// Same as `lint`, with an explicit set of rules.
+pub fn lint_with_rules(source: &str, rules: &mut [Box<dyn Rule>]) -> Vec<Diagnostic> {
+    let mut reports = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let Some(next) = find_next_jsx_start(source, i) else {
+            break;
+        };
+        i = next;
+        let mut p = Parser::new(&source[i..]);
+        match p.parse_next_with_span() {
+            Some(Ok((ast, (_, end)))) => {
+                let mut linter = Linter {
+                    rules: &mut *rules,
+                    elements: p.element_spans(),
+                    offset: i,
+                    next_element: 0,
+                    stack: Vec::new(),
+                    closing_tags: Vec::new(),
+                    reports: &mut reports,
+                };
+                walk_node(&mut linter, &ast);
+                i += end;
+            }
+            Some(Err(e)) => {
+                reports.push(Diagnostic {
+                    rule: PARSE_ERROR_RULE,
+                    severity: Severity::Error,
+                    message: e.message,
+                    position: i + e.position,
+                    line: 0,
+                    column: 0,
+                    utf16_column: 0,
+                });
+                // Skip the failed node, as the transform does
+                i = recovery_offset(source, i);
+            }
+            None => break,
+        }
+    }
+
+    reports.sort_by_key(|diagnostic| diagnostic.position);
+    let index = LineIndex::new(source);
+    for diagnostic in &mut reports {
+        let location = index.position(diagnostic.position);
+        diagnostic.line = location.line;
+        diagnostic.column = location.column;
+        diagnostic.utf16_column = location.utf16_column;
+    }
+    reports
+}
+
+// Dispatches visitor hooks to the rules, tracking element spans from the
+// parser (in visit order, relative to `offset`) and the stack of open
+// elements.
+struct Linter<'a> {
+    rules: &'a mut [Box<dyn Rule>],
+    elements: &'a [ElementSpans],
+    offset: usize,
+    next_element: usize,
+    stack: Vec<(String, usize)>,
+    // Closing tags of the open elements
+    closing_tags: Vec<Option<(usize, usize)>>,
+    reports: &'a mut Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn dispatch(&mut self, position: usize, hook: impl FnMut(&mut dyn Rule, &mut LintContext)) {
+        self.dispatch_with(position, None, hook);
+    }
+
+    fn dispatch_with(
+        &mut self,
+        position: usize,
+        closing_tag: Option<(usize, usize)>,
+        mut hook: impl FnMut(&mut dyn Rule, &mut LintContext),
+    ) {
+        for rule in self.rules.iter_mut() {
+            let mut cx = LintContext {
+                rule: rule.name(),
+                severity: rule.severity(),
+                position,
+                closing_tag,
+                ancestors: &self.stack,
+                reports: self.reports,
+            };
+            hook(rule.as_mut(), &mut cx);
+        }
+    }
+
+    fn parent_position(&self) -> usize {
+        self.stack.last().map_or(0, |(_, position)| *position)
+    }
+}
+
+impl JSXVisitor for Linter<'_> {
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute]) {
+        let spans = self.elements.get(self.next_element).copied();
+        self.next_element += 1;
+        let position = spans.map_or(0, |spans| self.offset + spans.open.0);
+        let closing_tag = spans
+            .and_then(|spans| spans.close)
+            .map(|(start, end)| (self.offset + start, self.offset + end));
+        self.dispatch_with(position, closing_tag, |rule, cx| {
+            rule.enter_element(tag, attributes, cx)
+        });
+        self.stack.push((tag.to_string(), position));
+        self.closing_tags.push(closing_tag);
+    }
+
+    fn exit_element(&mut self, tag: &str) {
+        if let Some((_, position)) = self.stack.pop() {
+            let closing_tag = self.closing_tags.pop().flatten();
+            self.dispatch_with(position, closing_tag, |rule, cx| rule.exit_element(tag, cx));
+        }
+    }
+
+    fn visit_text(&mut self, text: &str) {
+        let position = self.parent_position();
+        self.dispatch(position, |rule, cx| rule.visit_text(text, cx));
+    }
+
+    fn visit_expression(&mut self, expr: &str) {
+        let position = self.parent_position();
+        self.dispatch(position, |rule, cx| rule.visit_expression(expr, cx));
+    }
+
+    fn enter_embedded_expression(&mut self, raw: &str) {
+        let position = self.parent_position();
+        self.dispatch(position, |rule, cx| rule.visit_expression(raw, cx));
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,329 @@
+use std::borrow::Cow;
+
+use super::security::{security_rules, severity};
+use super::{LintContext, LintOptions, Rule, Severity};
+use crate::jsx_parser::JSXAttribute;
+use crate::jsx_transformer::tags_attrs::{
+    classify_tag, closest_html_tag, is_html_tag, is_obsolete_attribute, is_obsolete_tag,
+    normalize_html_attr_name, TagType,
+};
+
+/// The default rule set used by `lint`.
+pub fn default_rules() -> Vec<Box<dyn Rule>> {
+    rules_for(&LintOptions::default())
+}
+
+/// The built-in rules, configured by the options.
+pub fn rules_for(options: &LintOptions) -> Vec<Box<dyn Rule>> {
+    let mut rules: Vec<Box<dyn Rule>> = vec![
+        Box::new(VoidElementChildren::default()),
+        Box::new(VoidElementClosingTag::default()),
+        Box::new(DuplicateAttributes),
+        Box::new(AnchorWithoutHref),
+        Box::new(UnknownTag {
+            strict: options.strict_html,
+        }),
+        Box::new(ObsoleteAttribute {
+            strict: options.strict_html,
+        }),
+        Box::new(NestedButton),
+        Box::new(ChildrenPropConflict::default()),
+    ];
+    rules.extend(security_rules(options.strict_security));
+    rules
+}
+
+#[inline]
+fn is_spread(attribute: &JSXAttribute) -> bool {
+    attribute.name.starts_with("...")
+}
+
+#[inline]
+fn in_foreign_content(cx: &LintContext) -> bool {
+    cx.ancestors()
+        .iter()
+        .any(|(tag, _)| tag == "svg" || tag == "math")
+}
+
+/// Void elements such as `<img>` or `<br>` cannot have children.
+#[derive(Default)]
+pub struct VoidElementChildren {
+    reported: Option<usize>,
+}
+
+impl VoidElementChildren {
+    fn check_parent(&mut self, cx: &mut LintContext) {
+        let Some((tag, position)) = cx.ancestors().last() else {
+            return;
+        };
+        let position = *position;
+        if classify_tag(tag) == TagType::Void && self.reported != Some(position) {
+            let message = format!("<{tag}> is a void element and cannot have children");
+            cx.report_at(position, message);
+            self.reported = Some(position);
+        }
+    }
+}
+
+impl Rule for VoidElementChildren {
+    fn name(&self) -> &'static str {
+        "void-element-children"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enter_element(&mut self, _tag: &str, _attributes: &[JSXAttribute], cx: &mut LintContext) {
+        self.check_parent(cx);
+    }
+
+    fn visit_text(&mut self, text: &str, cx: &mut LintContext) {
+        if !text.trim().is_empty() {
+            self.check_parent(cx);
+        }
+    }
+
+    fn visit_expression(&mut self, _expr: &str, cx: &mut LintContext) {
+        self.check_parent(cx);
+    }
+}
+
+/// Void elements take no closing tag: `<br></br>` is compiled to `<br/>`, but
+/// reads as a container and is an error in HTML. Not reported along with
+/// children, which `void-element-children` reports.
+#[derive(Default)]
+pub struct VoidElementClosingTag {
+    // Whether each open element has children
+    children: Vec<bool>,
+}
+
+impl VoidElementClosingTag {
+    fn child(&mut self) {
+        if let Some(children) = self.children.last_mut() {
+            *children = true;
+        }
+    }
+}
+
+impl Rule for VoidElementClosingTag {
+    fn name(&self) -> &'static str {
+        "void-closing-tag"
+    }
+
+    fn enter_element(&mut self, _tag: &str, _attributes: &[JSXAttribute], _cx: &mut LintContext) {
+        self.child();
+        self.children.push(false);
+    }
+
+    fn exit_element(&mut self, tag: &str, cx: &mut LintContext) {
+        let children = self.children.pop().unwrap_or_default();
+        let Some((start, _)) = cx.closing_tag() else {
+            return;
+        };
+        if !children && classify_tag(tag) == TagType::Void && !in_foreign_content(cx) {
+            cx.report_at(
+                start,
+                format!("<{tag}> is a void element; write <{tag} /> without </{tag}>"),
+            );
+        }
+    }
+
+    fn visit_text(&mut self, text: &str, _cx: &mut LintContext) {
+        if !text.trim().is_empty() {
+            self.child();
+        }
+    }
+
+    fn visit_expression(&mut self, _expr: &str, _cx: &mut LintContext) {
+        self.child();
+    }
+}
+
+/// An attribute given twice, including HTML aliases such as `class` and
+/// `className`.
+pub struct DuplicateAttributes;
+
+impl Rule for DuplicateAttributes {
+    fn name(&self) -> &'static str {
+        "duplicate-attributes"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        let component = classify_tag(tag) == TagType::Component;
+        let mut seen: Vec<Cow<str>> = Vec::with_capacity(attributes.len());
+        for attribute in attributes.iter().filter(|a| !is_spread(a)) {
+            let name = if component {
+                Cow::Borrowed(attribute.name.as_str())
+            } else {
+                normalize_html_attr_name(&attribute.name)
+            };
+            if seen.contains(&name) {
+                cx.report(format!(
+                    "Duplicate attribute `{}` on <{tag}>",
+                    attribute.name
+                ));
+            } else {
+                seen.push(name);
+            }
+        }
+    }
+}
+
+/// `<a>` without `href` is not a link: it is not focusable nor announced as
+/// one. Spread attributes may provide it.
+pub struct AnchorWithoutHref;
+
+impl Rule for AnchorWithoutHref {
+    fn name(&self) -> &'static str {
+        "anchor-href"
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        if tag == "a"
+            && !in_foreign_content(cx)
+            && !attributes.iter().any(|a| a.name == "href" || is_spread(a))
+        {
+            cx.report("<a> without href; use a <button> for actions");
+        }
+    }
+}
+
+/// A lowercase tag that is not an HTML element, likely a typo, or an
+/// obsolete one such as `<center>`. Custom elements (with a hyphen),
+/// components and SVG/MathML content are not checked. Errors when `strict`.
+pub struct UnknownTag {
+    pub strict: bool,
+}
+
+impl Rule for UnknownTag {
+    fn name(&self) -> &'static str {
+        "unknown-tag"
+    }
+
+    fn severity(&self) -> Severity {
+        severity(self.strict)
+    }
+
+    fn enter_element(&mut self, tag: &str, _attributes: &[JSXAttribute], cx: &mut LintContext) {
+        if !matches!(classify_tag(tag), TagType::Element | TagType::Void)
+            || tag.contains('.')
+            || in_foreign_content(cx)
+            || is_html_tag(tag)
+        {
+            return;
+        }
+        if is_obsolete_tag(tag) {
+            cx.report(format!("Obsolete HTML element <{tag}>"));
+        } else if let Some(known) = closest_html_tag(tag) {
+            cx.report(format!(
+                "Unknown HTML element <{tag}>; did you mean <{known}>?"
+            ));
+        } else {
+            cx.report(format!("Unknown HTML element <{tag}>"));
+        }
+    }
+}
+
+/// A presentational attribute the HTML standard made obsolete, e.g. `align`
+/// or `bgcolor`; CSS replaces them. Errors when `strict`.
+pub struct ObsoleteAttribute {
+    pub strict: bool,
+}
+
+impl Rule for ObsoleteAttribute {
+    fn name(&self) -> &'static str {
+        "obsolete-attribute"
+    }
+
+    fn severity(&self) -> Severity {
+        severity(self.strict)
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        if !matches!(classify_tag(tag), TagType::Element | TagType::Void) || in_foreign_content(cx)
+        {
+            return;
+        }
+        for attribute in attributes.iter().filter(|a| !is_spread(a)) {
+            let name = normalize_html_attr_name(&attribute.name);
+            if is_obsolete_attribute(tag, &name) {
+                cx.report(format!(
+                    "Obsolete attribute `{name}` on <{tag}>; use CSS instead"
+                ));
+            }
+        }
+    }
+}
+
+/// A `<button>` inside another `<button>`, which browsers split apart.
+pub struct NestedButton;
+
+impl Rule for NestedButton {
+    fn name(&self) -> &'static str {
+        "nested-button"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn enter_element(&mut self, tag: &str, _attributes: &[JSXAttribute], cx: &mut LintContext) {
+        if tag == "button" && cx.ancestors().iter().any(|(t, _)| t == "button") {
+            cx.report("<button> cannot be nested inside another <button>");
+        }
+    }
+}
+
+/// A component given both a `children` prop and JSX children: the prop is
+/// passed and the JSX children are dropped.
+#[derive(Default)]
+pub struct ChildrenPropConflict {
+    // Position of each open element given a `children` prop, and whether it
+    // has JSX children
+    open: Vec<(Option<usize>, bool)>,
+}
+
+impl ChildrenPropConflict {
+    fn child(&mut self) {
+        if let Some((_, children)) = self.open.last_mut() {
+            *children = true;
+        }
+    }
+}
+
+impl Rule for ChildrenPropConflict {
+    fn name(&self) -> &'static str {
+        "children-prop"
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        self.child();
+        let prop = classify_tag(tag) == TagType::Component
+            && attributes.iter().any(|a| a.name == "children");
+        self.open.push((prop.then(|| cx.position()), false));
+    }
+
+    fn exit_element(&mut self, tag: &str, cx: &mut LintContext) {
+        if let Some((Some(position), true)) = self.open.pop() {
+            cx.report_at(
+                position,
+                format!("<{tag}> has both a children prop and JSX children; the JSX children are ignored"),
+            );
+        }
+    }
+
+    fn visit_text(&mut self, text: &str, _cx: &mut LintContext) {
+        if !text.trim().is_empty() {
+            self.child();
+        }
+    }
+
+    fn visit_expression(&mut self, _expr: &str, _cx: &mut LintContext) {
+        self.child();
+    }
+}
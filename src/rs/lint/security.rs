@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+
+use super::{LintContext, Rule, Severity};
+use crate::jsx_parser::{JSXAttribute, JSXAttributeValue};
+use crate::jsx_transformer::tags_attrs::{classify_tag, normalize_html_attr_name, TagType};
+
+// Attributes whose value the browser navigates to or fetches, by their HTML
+// name (`xlinkHref` and `formAction` normalize to `href` and `formaction`).
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "data",
+    "formaction",
+    "href",
+    "manifest",
+    "ping",
+    "poster",
+    "src",
+];
+
+// Calls accepted as escaping an interpolated URL: the standard encoders plus
+// any function named `sanitize*` or `escape*`.
+const URL_ENCODERS: &[&str] = &["encodeURI", "encodeURIComponent"];
+
+/// The security rules; they report warnings, or errors when `strict`.
+pub fn security_rules(strict: bool) -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(JavascriptUrl { strict }),
+        Box::new(SrcdocExpression { strict }),
+        Box::new(UnsafeUrlInterpolation { strict }),
+    ]
+}
+
+#[inline]
+pub(super) fn severity(strict: bool) -> Severity {
+    if strict {
+        Severity::Error
+    } else {
+        Severity::Warning
+    }
+}
+
+// HTML attributes of an element, with their normalized name. Components get
+// props rather than attributes and are skipped.
+fn html_attributes<'a>(
+    tag: &str,
+    attributes: &'a [JSXAttribute],
+) -> impl Iterator<Item = (Cow<'a, str>, &'a JSXAttributeValue)> {
+    let html = matches!(classify_tag(tag), TagType::Element | TagType::Void);
+    attributes
+        .iter()
+        .filter(move |_| html)
+        .filter_map(|a| Some((normalize_html_attr_name(&a.name), a.value.as_ref()?)))
+}
+
+// Contents of a string literal without interpolations: `"…"`, `'…'` or a
+// template literal without `${`.
+fn string_literal(expr: &str) -> Option<&str> {
+    let t = expr.trim();
+    let quote = t.chars().next()?;
+    if !matches!(quote, '"' | '\'' | '`') || t.len() < 2 || !t.ends_with(quote) {
+        return None;
+    }
+    let inner = &t[1..t.len() - 1];
+    if inner.contains(quote) || (quote == '`' && inner.contains("${")) {
+        return None;
+    }
+    Some(inner)
+}
+
+// Browsers strip leading whitespace and control characters, and remove tabs
+// and newlines anywhere, before reading the scheme.
+fn is_javascript_url(value: &str) -> bool {
+    let url: String = value
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .take("javascript:".len())
+        .collect();
+    url.eq_ignore_ascii_case("javascript:")
+}
+
+fn is_escaping_call(expr: &str) -> bool {
+    let t = expr.trim();
+    let Some(open) = t.find('(') else {
+        return false;
+    };
+    // The call must span the whole expression: `escape(a) + b` is not escaped
+    let mut depth = 0;
+    for (i, c) in t[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 && open + i + 1 < t.len() {
+            return false;
+        }
+    }
+    let callee = t[..open].trim();
+    if callee.is_empty()
+        || !callee
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.'))
+    {
+        return false;
+    }
+    let name = callee.rsplit('.').next().unwrap_or(callee);
+    URL_ENCODERS.contains(&name) || name.starts_with("sanitize") || name.starts_with("escape")
+}
+
+/// A `javascript:` URL given as a static value (or a string literal) of a URL
+/// attribute, e.g. `<a href="javascript:void(0)">`.
+pub struct JavascriptUrl {
+    pub strict: bool,
+}
+
+impl Rule for JavascriptUrl {
+    fn name(&self) -> &'static str {
+        "javascript-url"
+    }
+
+    fn severity(&self) -> Severity {
+        severity(self.strict)
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        for (name, value) in html_attributes(tag, attributes) {
+            let url = match value {
+                JSXAttributeValue::DoubleQuote(v) | JSXAttributeValue::SingleQuote(v) => Some(&**v),
+                JSXAttributeValue::Expression(expr) => string_literal(expr),
+            };
+            if URL_ATTRIBUTES.contains(&&*name) && url.is_some_and(is_javascript_url) {
+                cx.report(format!("`javascript:` URL in `{name}` on <{tag}>"));
+            }
+        }
+    }
+}
+
+/// `srcdoc` given an expression: its value is a whole HTML document, so any
+/// interpolated data is markup.
+pub struct SrcdocExpression {
+    pub strict: bool,
+}
+
+impl Rule for SrcdocExpression {
+    fn name(&self) -> &'static str {
+        "srcdoc-expression"
+    }
+
+    fn severity(&self) -> Severity {
+        severity(self.strict)
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        for (name, value) in html_attributes(tag, attributes) {
+            if name == "srcdoc"
+                && matches!(value, JSXAttributeValue::Expression(expr) if string_literal(expr).is_none())
+            {
+                cx.report(format!(
+                    "Expression-valued `srcdoc` on <{tag}> renders data as HTML"
+                ));
+            }
+        }
+    }
+}
+
+/// An expression interpolated as is into a URL attribute: attribute values are
+/// not escaped, so it may close the quotes or carry a `javascript:` URL.
+/// String literals and calls to `encodeURI`, `encodeURIComponent`,
+/// `sanitize*` or `escape*` functions are accepted.
+pub struct UnsafeUrlInterpolation {
+    pub strict: bool,
+}
+
+impl Rule for UnsafeUrlInterpolation {
+    fn name(&self) -> &'static str {
+        "unsafe-url-interpolation"
+    }
+
+    fn severity(&self) -> Severity {
+        severity(self.strict)
+    }
+
+    fn enter_element(&mut self, tag: &str, attributes: &[JSXAttribute], cx: &mut LintContext) {
+        for (name, value) in html_attributes(tag, attributes) {
+            let JSXAttributeValue::Expression(expr) = value else {
+                continue;
+            };
+            if URL_ATTRIBUTES.contains(&&*name)
+                && string_literal(expr).is_none()
+                && !is_escaping_call(expr)
+            {
+                cx.report(format!("Unescaped interpolation in `{name}` on <{tag}>"));
+            }
+        }
+    }
+}
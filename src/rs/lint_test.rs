@@ -0,0 +1,279 @@
+use crate::jsx_parser::JSXAttribute;
+use crate::lint::{
+    lint, lint_with_options, lint_with_rules, LintContext, LintOptions, Rule, Severity,
+};
+
+fn rules_of(source: &str) -> Vec<&'static str> {
+    lint(source).iter().map(|d| d.rule).collect()
+}
+
+#[test]
+fn test_lint_clean_source() {
+    assert!(lint(r#"const a = <div className="x"><a href="/">Home</a><Card /></div>;"#).is_empty());
+    assert!(lint("const n = 1 < 2;").is_empty());
+}
+
+#[test]
+fn test_lint_void_element_children() {
+    let diagnostics = lint("const a = <img>\n  <span>x</span> more\n</img>;");
+    assert_eq!(diagnostics.len(), 1);
+    let d = &diagnostics[0];
+    assert_eq!(d.rule, "void-element-children");
+    assert_eq!(d.severity, Severity::Error);
+    assert_eq!((d.position, d.line, d.column), (10, 1, 11));
+    assert_eq!(d.utf16_column, 11);
+    assert_eq!(
+        d.message,
+        "<img> is a void element and cannot have children"
+    );
+
+    assert_eq!(rules_of("<br>\n</br>"), vec!["void-closing-tag"]);
+    assert_eq!(
+        rules_of("<input>{value}</input>"),
+        vec!["void-element-children"]
+    );
+}
+
+#[test]
+fn test_lint_void_closing_tag() {
+    let diagnostics = lint("const a = <p><br></br><img /><hr/></p>;");
+    assert_eq!(diagnostics.len(), 1);
+    let d = &diagnostics[0];
+    assert_eq!(d.rule, "void-closing-tag");
+    assert_eq!(d.severity, Severity::Warning);
+    assert_eq!(d.column, 18);
+    assert_eq!(
+        d.message,
+        "<br> is a void element; write <br /> without </br>"
+    );
+
+    // Foreign content has no void elements
+    assert!(lint("<svg><image href=\"a.png\"></image></svg>").is_empty());
+}
+
+#[test]
+fn test_lint_duplicate_attributes() {
+    let diagnostics = lint(r#"<div class="a" className="b" id="x" {...rest} />"#);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "duplicate-attributes");
+    assert_eq!(
+        diagnostics[0].message,
+        "Duplicate attribute `className` on <div>"
+    );
+
+    // Component props are not aliased
+    assert!(lint(r#"<Card class="a" className="b" />"#).is_empty());
+    assert_eq!(
+        rules_of(r#"<Card a="1" a="2" />"#),
+        vec!["duplicate-attributes"]
+    );
+}
+
+#[test]
+fn test_lint_anchor_and_unknown_tag() {
+    assert_eq!(rules_of("<a onClick={go}>Go</a>"), vec!["anchor-href"]);
+    assert!(lint("<a {...props}>Go</a>").is_empty());
+    assert!(lint("<svg><a><circle /></a></svg>").is_empty());
+
+    let diagnostics = lint("<section>\n  <blink>old</blink>\n</section>");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "unknown-tag");
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 3));
+    assert!(lint("<my-widget><Foo.Bar /></my-widget>").is_empty());
+}
+
+#[test]
+fn test_lint_unknown_and_obsolete_html() {
+    let messages =
+        |source: &str| -> Vec<String> { lint(source).into_iter().map(|d| d.message).collect() };
+    assert_eq!(
+        messages("<dvi><center>x</center><foo /></dvi>"),
+        vec![
+            "Unknown HTML element <dvi>; did you mean <div>?",
+            "Obsolete HTML element <center>",
+            "Unknown HTML element <foo>",
+        ]
+    );
+
+    let diagnostics = lint(
+        r#"<table bgColor="white" width="100%"><tr><td align="left" valign="top" /></tr></table>"#,
+    );
+    let found: Vec<(&str, &str)> = diagnostics
+        .iter()
+        .map(|d| (d.rule, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "obsolete-attribute",
+                "Obsolete attribute `bgcolor` on <table>; use CSS instead"
+            ),
+            (
+                "obsolete-attribute",
+                "Obsolete attribute `width` on <table>; use CSS instead"
+            ),
+            (
+                "obsolete-attribute",
+                "Obsolete attribute `align` on <td>; use CSS instead"
+            ),
+            (
+                "obsolete-attribute",
+                "Obsolete attribute `valign` on <td>; use CSS instead"
+            ),
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+
+    // Still valid on other elements, and props of components are not checked
+    assert!(lint(r#"<div><img width="10" height="10" /><th abbr="n" /><Grid align="center" /><svg><text width="1" /></svg></div>"#).is_empty());
+
+    let options = LintOptions {
+        strict_html: true,
+        ..LintOptions::default()
+    };
+    let diagnostics = lint_with_options(r#"<font><hr size="2" /></font>"#, &options);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].rule, "unknown-tag");
+    assert_eq!(diagnostics[1].rule, "obsolete-attribute");
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+}
+
+#[test]
+fn test_lint_nested_button_and_embedded_jsx() {
+    assert_eq!(
+        rules_of("<button><span><button>x</button></span></button>"),
+        vec!["nested-button"]
+    );
+    assert_eq!(
+        rules_of("<button>{open && <button>x</button>}</button>"),
+        vec!["nested-button"]
+    );
+    assert!(lint("<div><button>a</button><button>b</button></div>").is_empty());
+}
+
+#[test]
+fn test_lint_children_prop() {
+    let diagnostics = lint("const a = <Card children={items}>\n  <li/>\n</Card>;");
+    assert_eq!(diagnostics.len(), 1);
+    let d = &diagnostics[0];
+    assert_eq!(d.rule, "children-prop");
+    assert_eq!(d.severity, Severity::Warning);
+    assert_eq!(d.position, 10);
+    assert_eq!(
+        d.message,
+        "<Card> has both a children prop and JSX children; the JSX children are ignored"
+    );
+
+    assert!(lint("<Card children={items} />").is_empty());
+    assert!(lint("<Card children={items}>\n</Card>").is_empty());
+    // Elements render their children attribute as markup
+    assert!(lint("<div children=\"x\">y</div>").is_empty());
+}
+
+#[test]
+fn test_lint_parse_error_and_multiple_roots() {
+    let diagnostics = lint("const a = <p>ok</p>;\nconst b = <div><foo></div>;");
+    assert!(diagnostics.iter().any(|d| d.rule == "parse-error"));
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.line == 2 || d.rule != "parse-error"));
+
+    let diagnostics = lint("const a = <blink />;\nconst b = <marquee />;");
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].line, 1);
+    assert_eq!(diagnostics[1].line, 2);
+}
+
+#[test]
+fn test_lint_javascript_url() {
+    let diagnostics = lint("<a href=\" JaVa\tScript:alert(1)\">x</a>");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "javascript-url");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].message, "`javascript:` URL in `href` on <a>");
+
+    assert_eq!(
+        rules_of(
+            r#"<form action={'javascript:void(0)'}><button formAction="javascript:go()" /></form>"#
+        ),
+        vec!["javascript-url", "javascript-url"]
+    );
+    assert!(lint(r#"<a href="/javascript:docs">x</a>"#).is_empty());
+    assert!(lint(r#"<Link href="javascript:void(0)" />"#).is_empty());
+}
+
+#[test]
+fn test_lint_srcdoc_and_url_interpolation() {
+    assert_eq!(
+        rules_of("<iframe srcdoc={html} />"),
+        vec!["srcdoc-expression"]
+    );
+    assert!(lint(r#"<iframe srcDoc="<p>hi</p>" />"#).is_empty());
+
+    let diagnostics = lint("<img src={user.avatar} />");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "unsafe-url-interpolation");
+    assert_eq!(
+        diagnostics[0].message,
+        "Unescaped interpolation in `src` on <img>"
+    );
+    assert_eq!(
+        rules_of("<a href={escapeUrl(a) + b}>x</a>"),
+        vec!["unsafe-url-interpolation"]
+    );
+    assert!(lint(
+        "<div><a href={encodeURI(url)}>x</a><img src={utils.sanitizeUrl(src)} /><a href={`/docs`}>d</a><a title={title} href=\"/\">t</a></div>"
+    )
+    .is_empty());
+    assert!(lint("<Avatar src={user.avatar} />").is_empty());
+}
+
+#[test]
+fn test_lint_strict_security() {
+    let options = LintOptions {
+        strict_security: true,
+        ..LintOptions::default()
+    };
+    let diagnostics = lint_with_options(r#"<a href={next}><blink /></a>"#, &options);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].rule, "unsafe-url-interpolation");
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[1].rule, "unknown-tag");
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+}
+
+struct NoDiv;
+
+impl Rule for NoDiv {
+    fn name(&self) -> &'static str {
+        "no-div"
+    }
+
+    fn enter_element(&mut self, tag: &str, _attributes: &[JSXAttribute], cx: &mut LintContext) {
+        if tag == "div" {
+            cx.report(format!("Avoid <div> inside {:?}", cx.parent()));
+        }
+    }
+}
+
+#[test]
+fn test_lint_with_custom_rules() {
+    let mut rules: Vec<Box<dyn Rule>> = vec![Box::new(NoDiv)];
+    let diagnostics = lint_with_rules("<main><div><blink /></div></main>", &mut rules);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "no-div");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].message, "Avoid <div> inside Some(\"main\")");
+    assert_eq!(diagnostics[0].position, 6);
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_lint_wasm_export_json() {
+    assert_eq!(crate::wasm::lint_jsx("<p>ok</p>"), "[]");
+    assert_eq!(
+        crate::wasm::lint_jsx("<a>\"x\"</a>"),
+        r#"[{"rule":"anchor-href","severity":"warning","message":"<a> without href; use a <button> for actions","position":0,"line":1,"column":1,"utf16Column":1}]"#
+    );
+}
@@ -1,7 +1,22 @@
+pub mod editor;
+pub mod extract;
+pub mod html_to_jsx;
 pub mod jsx_parser;
 pub mod jsx_transformer;
+pub mod lint;
+pub mod testing;
 
+#[cfg(test)]
+pub mod editor_test;
+#[cfg(test)]
+pub mod extract_test;
+#[cfg(test)]
+pub mod html_to_jsx_test;
 #[cfg(test)]
 pub mod jsx_parser_test;
 #[cfg(test)]
 pub mod jsx_transformer_test;
+#[cfg(test)]
+pub mod lint_test;
+#[cfg(test)]
+pub mod testing_test;
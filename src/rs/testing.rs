@@ -0,0 +1,187 @@
+//! Golden tests for transforms: every JSX fixture of a directory is
+//! transformed and compared with the `.expected` file next to it, so suites
+//! of framework transforms can be maintained as plain files.
+//!
+//! ```text
+//! fixtures/
+//!   card.jsx            input
+//!   card.jsx.expected   `code` of the transform, or the error it fails with
+//! ```
+//!
+//! Setting `SXO_UPDATE_FIXTURES=1` writes the actual outputs to the
+//! `.expected` files instead of comparing them.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::jsx_transformer::{jsx_transform, TransformOptions};
+
+/// Extensions of the fixture inputs.
+pub const FIXTURE_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx"];
+
+/// Environment variable that turns comparisons into updates of the
+/// `.expected` files.
+pub const UPDATE_ENV: &str = "SXO_UPDATE_FIXTURES";
+
+/// A fixture whose output differs from its `.expected` file, or that has none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureFailure {
+    pub input: PathBuf,
+    /// Line diff from the expected output to the actual one; the actual
+    /// output when the `.expected` file is missing.
+    pub diff: String,
+    pub missing: bool,
+}
+
+impl fmt::Display for FixtureFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.missing {
+            writeln!(f, "{}: no .expected file, got:", self.input.display())?;
+        } else {
+            writeln!(
+                f,
+                "{}: output differs (-expected +actual):",
+                self.input.display()
+            )?;
+        }
+        write!(f, "{}", self.diff)
+    }
+}
+
+/// Outcome of `run_fixtures`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixtureReport {
+    pub passed: usize,
+    /// `.expected` files written because `SXO_UPDATE_FIXTURES` is set.
+    pub updated: usize,
+    pub failures: Vec<FixtureFailure>,
+}
+
+impl FixtureReport {
+    /// Panics listing every failure, if any.
+    pub fn assert_passed(&self) {
+        if !self.failures.is_empty() {
+            let failures: Vec<String> = self.failures.iter().map(|f| f.to_string()).collect();
+            panic!(
+                "{} of {} fixtures failed (set {UPDATE_ENV}=1 to update them)\n\n{}",
+                self.failures.len(),
+                self.failures.len() + self.passed,
+                failures.join("\n")
+            );
+        }
+    }
+}
+
+/// Transforms every fixture under `dir` (recursively, in path order) with
+/// `options` and compares each output with its `.expected` file. Fails only
+/// when the directory or a file cannot be read or written.
+pub fn run_fixtures(
+    dir: impl AsRef<Path>,
+    options: &TransformOptions,
+) -> io::Result<FixtureReport> {
+    let update = std::env::var_os(UPDATE_ENV).is_some_and(|v| !v.is_empty() && v != "0");
+    let mut inputs = Vec::new();
+    collect_fixtures(dir.as_ref(), &mut inputs)?;
+    inputs.sort();
+
+    let mut report = FixtureReport::default();
+    for input in inputs {
+        let source = fs::read_to_string(&input)?;
+        let actual = match jsx_transform(&source, options) {
+            Ok(output) => output.code,
+            Err(error) => error.to_string(),
+        };
+        let expected_path = expected_path(&input);
+        if update {
+            fs::write(&expected_path, &actual)?;
+            report.updated += 1;
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => report.passed += 1,
+            Ok(expected) => report.failures.push(FixtureFailure {
+                input,
+                diff: line_diff(&expected, &actual),
+                missing: false,
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => report.failures.push(FixtureFailure {
+                input,
+                diff: actual,
+                missing: true,
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(report)
+}
+
+/// Same as `run_fixtures`, panicking on any failure; for use in a `#[test]`.
+pub fn assert_fixtures(dir: impl AsRef<Path>, options: &TransformOptions) {
+    let dir = dir.as_ref();
+    match run_fixtures(dir, options) {
+        Ok(report) => report.assert_passed(),
+        Err(e) => panic!("{}: {e}", dir.display()),
+    }
+}
+
+/// Unified-style diff of two texts by lines: unchanged lines start with a
+/// space, removed ones with `-` and added ones with `+`.
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let (old, new): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let mut line = |prefix: char, text: &str| {
+        out.push(prefix);
+        out.push_str(text);
+        out.push('\n');
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            line(' ', old[i]);
+            (i, j) = (i + 1, j + 1);
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            line('-', old[i]);
+            i += 1;
+        } else {
+            line('+', new[j]);
+            j += 1;
+        }
+    }
+    out
+}
+
+// `card.jsx` to `card.jsx.expected`.
+fn expected_path(input: &Path) -> PathBuf {
+    let mut path = input.as_os_str().to_owned();
+    path.push(".expected");
+    PathBuf::from(path)
+}
+
+fn collect_fixtures(dir: &Path, inputs: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_fixtures(&path, inputs)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| FIXTURE_EXTENSIONS.contains(&e))
+        {
+            inputs.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::jsx_transformer::TransformOptions;
+use crate::testing::{line_diff, run_fixtures};
+
+// A fresh directory for the fixtures of one test.
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sxo-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    dir
+}
+
+#[test]
+fn test_run_fixtures() {
+    let dir = fixture_dir("fixtures");
+    fs::write(dir.join("ok.jsx"), "const a = <p>{a}</p>;\n").unwrap();
+    fs::write(dir.join("ok.jsx.expected"), "const a = `<p>${a}</p>`;\n").unwrap();
+    fs::write(dir.join("nested/bad.tsx"), "const a = <p>;").unwrap();
+    fs::write(dir.join("nested/bad.tsx.expected"), "JSX parsing error").unwrap();
+    fs::write(dir.join("new.jsx"), "<br/>").unwrap();
+    fs::write(dir.join("notes.txt"), "not a fixture").unwrap();
+
+    let report = run_fixtures(&dir, &TransformOptions::default()).unwrap();
+    assert_eq!((report.passed, report.updated), (1, 0));
+    let failures: Vec<(String, bool)> = report
+        .failures
+        .iter()
+        .map(|f| {
+            let name = f.input.strip_prefix(&dir).unwrap();
+            (name.display().to_string(), f.missing)
+        })
+        .collect();
+    assert_eq!(
+        failures,
+        [
+            ("nested/bad.tsx".to_string(), false),
+            ("new.jsx".to_string(), true)
+        ]
+    );
+    assert!(report.failures[0]
+        .diff
+        .starts_with("-JSX parsing error\n+JSX parsing error: "));
+    assert_eq!(report.failures[1].diff, "`<br/>`");
+    assert!(report.failures[1].to_string().contains("no .expected file"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_line_diff() {
+    assert_eq!(line_diff("a\nb\nc", "a\nB\nc\nd"), " a\n-b\n+B\n c\n+d\n");
+    assert_eq!(line_diff("same", "same"), " same\n");
+}
@@ -0,0 +1,352 @@
+//! WASM bindings: the functions exported to JS through wasm-bindgen.
+
+use std::cell::RefCell;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{editor, jsx_transformer, lint};
+
+// When the `console_error_panic_hook` feature is enabled, we can call the
+// `set_panic_hook` function at least once during initialization, and then
+// we will get better error messages if our code ever panics.
+#[cfg(feature = "console_error_panic_hook")]
+pub fn set_panic_hook() {
+    console_error_panic_hook::set_once();
+}
+
+// Initialize the WASM module
+// WASM entrypoint for initialization
+#[wasm_bindgen(start)]
+pub fn init() {
+    #[cfg(feature = "console_error_panic_hook")]
+    set_panic_hook();
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const SXO_ERROR_TYPES: &'static str = r#"
+/** A problem found while transforming, located in the input when possible. */
+export interface SxoDiagnostic {
+    /** "parse-error", "transform-error", "extraction-error", "invalid-options" or "cancelled". */
+    code: string;
+    message: string;
+    /** 1-based line and column (in characters). */
+    line: number | null;
+    column: number | null;
+    /** 1-based column in UTF-16 code units, as counted by editors. */
+    utf16Column: number | null;
+    /** Start and end indices in the input string. */
+    span: [number, number] | null;
+}
+
+/** Error thrown by `jsx()`; location fields are those of the first diagnostic. */
+export interface SxoError extends Error {
+    name: "SxoError";
+    code: string;
+    line: number | null;
+    column: number | null;
+    utf16Column: number | null;
+    span: [number, number] | null;
+    diagnostics: SxoDiagnostic[];
+}
+
+/** Kind of a tag, as returned by `classifyTag()`. */
+export type SxoTagType = "component" | "web-component" | "void" | "element";
+"#;
+
+/// Expose the Rust jsx_transformer function to JS/WASM.
+/// `options` is an optional plain object of `JsTransformOptions` fields.
+/// Throws an `SxoError` on failure.
+#[wasm_bindgen]
+pub fn jsx(input: &str, options: Option<JsValue>) -> Result<String, JsValue> {
+    let options = transform_options_or_throw(options)?;
+    jsx_transformer::jsx_transformer_with_options(input, &options)
+        .map_err(|e| sxo_error(jsx_transformer::JsTransformError::new(input, &options, &e)))
+}
+
+/// Classifies a tag as the compiler does: "component", "web-component",
+/// "void" or "element". `options` may set `componentPatterns`,
+/// `elementPatterns` and `voidTags`.
+#[wasm_bindgen(js_name = classifyTag, unchecked_return_type = "SxoTagType")]
+pub fn classify_tag(tag: &str, options: Option<JsValue>) -> Result<String, JsValue> {
+    let options = transform_options_or_throw(options)?;
+    Ok(
+        jsx_transformer::tags_attrs::classify_tag_with(tag, &options)
+            .as_str()
+            .to_string(),
+    )
+}
+
+/// Normalizes a JSX attribute name to its HTML form as the compiler does for
+/// HTML elements, e.g. `className` to `class`. `options` may set
+/// `attrNameMap`, `normalizeAttrNames` and `preserveUnknownAttrCase`.
+#[wasm_bindgen(js_name = normalizeHtmlAttrName)]
+pub fn normalize_html_attr_name(name: &str, options: Option<JsValue>) -> Result<String, JsValue> {
+    let options = transform_options_or_throw(options)?;
+    Ok(jsx_transformer::tags_attrs::normalize_attr_name_with(name, false, &options).into_owned())
+}
+
+// A JS `Error` named `SxoError` with the fields of `error` assigned to it.
+fn sxo_error(error: jsx_transformer::JsTransformError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.message);
+    js_error.set_name("SxoError");
+    if let Ok(fields) = error.serialize(&serde_wasm_bindgen::Serializer::json_compatible()) {
+        js_sys::Object::assign(&js_error, fields.unchecked_ref());
+    }
+    js_error.into()
+}
+
+/// Same as `jsx`, returning `{ code, map, diagnostics, metadata }` (see
+/// `JsTransformResult`) instead of throwing on errors.
+#[wasm_bindgen]
+pub fn transform(input: &str, options: Option<JsValue>) -> Result<JsValue, JsValue> {
+    let result = match transform_options(options) {
+        Ok(options) => jsx_transformer::JsTransformResult::transform(input, &options),
+        Err(e) => jsx_transformer::JsTransformResult::error("invalid-options", e),
+    };
+    result
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
+
+/// Same as `transform`, calling `onProgress(processed, total)` every
+/// `interval` bytes of input (64 KiB by default) and once at the end, between
+/// top-level JSX nodes. Returning `false` from it cancels the transform, which
+/// then fails with a `cancelled` diagnostic; an exception thrown by it is
+/// rethrown.
+#[wasm_bindgen(js_name = transformWithProgress)]
+pub fn transform_with_progress(
+    input: &str,
+    options: Option<JsValue>,
+    on_progress: &js_sys::Function,
+    interval: Option<usize>,
+) -> Result<JsValue, JsValue> {
+    let mut thrown = None;
+    let result = match transform_options(options) {
+        Ok(options) => {
+            let result = jsx_transformer::jsx_transform_with_progress(
+                input,
+                &options,
+                interval.unwrap_or(jsx_transformer::DEFAULT_PROGRESS_INTERVAL),
+                &mut |processed, total| {
+                    let processed = JsValue::from_f64(processed as f64);
+                    let total = JsValue::from_f64(total as f64);
+                    match on_progress.call2(&JsValue::NULL, &processed, &total) {
+                        Ok(value) => value.as_bool() != Some(false),
+                        Err(e) => {
+                            thrown = Some(e);
+                            false
+                        }
+                    }
+                },
+            );
+            jsx_transformer::JsTransformResult::from_result(input, &options, result)
+        }
+        Err(e) => jsx_transformer::JsTransformResult::error("invalid-options", e),
+    };
+    if let Some(e) = thrown {
+        return Err(e);
+    }
+    result
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
+
+/// Parses the JSX nodes of `input` without transforming them, returning
+/// `{ nodes, diagnostics }` (see `JsParseResult`).
+#[wasm_bindgen]
+pub fn parse(input: &str) -> Result<JsValue, JsValue> {
+    jsx_transformer::JsParseResult::parse(input)
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
+
+thread_local! {
+    // Options set by `setDefaultOptions`, the base of the options of each call.
+    static DEFAULT_OPTIONS: RefCell<jsx_transformer::TransformOptions> =
+        RefCell::new(jsx_transformer::TransformOptions::default());
+}
+
+/// Sets the options used by subsequent calls, for hosts that configure the
+/// transformer once at startup. Options given to a call still apply over
+/// them; `undefined` or `null` restores the built-in defaults.
+/// Throws an `SxoError` on invalid options, leaving the defaults unchanged.
+#[wasm_bindgen(js_name = setDefaultOptions)]
+pub fn set_default_options(options: Option<JsValue>) -> Result<(), JsValue> {
+    let options = match options.filter(|options| !options.is_null()) {
+        Some(options) => read_options(options, &jsx_transformer::TransformOptions::default())
+            .map_err(invalid_options_error)?,
+        None => jsx_transformer::TransformOptions::default(),
+    };
+    replace_default_options(options);
+    Ok(())
+}
+
+fn replace_default_options(options: jsx_transformer::TransformOptions) {
+    DEFAULT_OPTIONS.with(|defaults| *defaults.borrow_mut() = options);
+}
+
+// Same as `transform_options`, throwing an `SxoError` with an
+// `invalid-options` diagnostic.
+fn transform_options_or_throw(
+    options: Option<JsValue>,
+) -> Result<jsx_transformer::TransformOptions, JsValue> {
+    transform_options(options).map_err(invalid_options_error)
+}
+
+fn invalid_options_error(message: String) -> JsValue {
+    let diagnostic = jsx_transformer::JsDiagnostic::new("invalid-options", message.as_str());
+    sxo_error(jsx_transformer::JsTransformError::from_diagnostics(
+        message,
+        vec![diagnostic],
+    ))
+}
+
+// Reads the options object passed from JS over the defaults set by
+// `setDefaultOptions`; `undefined` and `null` select those defaults.
+fn transform_options(
+    options: Option<JsValue>,
+) -> Result<jsx_transformer::TransformOptions, String> {
+    let defaults = DEFAULT_OPTIONS.with(|defaults| defaults.borrow().clone());
+    match options.filter(|options| !options.is_null()) {
+        Some(options) => read_options(options, &defaults),
+        None => Ok(defaults),
+    }
+}
+
+fn read_options(
+    options: JsValue,
+    base: &jsx_transformer::TransformOptions,
+) -> Result<jsx_transformer::TransformOptions, String> {
+    let options: jsx_transformer::JsTransformOptions =
+        serde_wasm_bindgen::from_value(options).map_err(|e| format!("Invalid options: {e}"))?;
+    Ok(options.apply(base))
+}
+
+/// Compiler version, git commit and enabled features, e.g.
+/// `0.1.0 (abc1234; features: wasm, console_error_panic_hook, parallel)`.
+/// Hosts can key cached outputs on it.
+#[wasm_bindgen]
+pub fn version() -> String {
+    let features: Vec<&str> = [
+        ("wasm", cfg!(feature = "wasm")),
+        ("napi", cfg!(feature = "napi")),
+        (
+            "console_error_panic_hook",
+            cfg!(feature = "console_error_panic_hook"),
+        ),
+        ("cli", cfg!(feature = "cli")),
+        ("parallel", cfg!(feature = "parallel")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect();
+    let features = if features.is_empty() {
+        "none".to_string()
+    } else {
+        features.join(", ")
+    };
+    format!(
+        "{} ({}; features: {features})",
+        env!("CARGO_PKG_VERSION"),
+        env!("SXO_GIT_HASH")
+    )
+}
+
+/// Semantic tokens of the JSX of `input`, encoded as LSP semantic tokens
+/// data; token types index `semanticTokenTypes()`.
+#[wasm_bindgen(js_name = semanticTokens)]
+pub fn semantic_tokens(input: &str) -> Vec<u32> {
+    editor::encode_semantic_tokens(input, &editor::semantic_tokens(input))
+}
+
+/// The LSP token types legend of `semanticTokens()`.
+#[wasm_bindgen(js_name = semanticTokenTypes)]
+pub fn semantic_token_types() -> Vec<String> {
+    editor::SEMANTIC_TOKEN_TYPES
+        .iter()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Lint JSX source with the default rules, returning a JSON array of diagnostics
+#[wasm_bindgen(js_name = lint)]
+pub fn lint_jsx(input: &str) -> String {
+    let diagnostics: Vec<String> = lint::lint(input).iter().map(|d| d.to_json()).collect();
+    format!("[{}]", diagnostics.join(","))
+}
+
+// TODO: bring test from query/jsx_parser
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize_ws(s: &str) -> String {
+        s.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn test_transform_jsx_export() {
+        let input = r#"\
+            <App>
+                <Header title="Welcome!" />
+                <Content>
+                    <p>This is a <strong>complex</strong> JSX example.</p>
+                    <CustomComponent prop1={42} prop2="hello" />
+                </Content>
+                <Footer />
+            </App>
+        "#;
+        let result = jsx(input, None);
+
+        println!("Transformed JSX: {}", result.clone().unwrap());
+
+        assert!(result.is_ok());
+        let output = result.unwrap();
+        let expected_snippet = "${__jsxComponent(App, [], `${__jsxComponent(Header, [{\"title\":\"Welcome!\"}])}${__jsxComponent(Content, [], `<p>This is a <strong>complex</strong> JSX example.</p>${__jsxComponent(CustomComponent, [{\"prop1\":42},{\"prop2\":\"hello\"}])}`)}${__jsxComponent(Footer, [])}`)}";
+        assert!(normalize_ws(&output).contains(&normalize_ws(expected_snippet)));
+    }
+
+    #[test]
+    fn test_default_options_apply_to_calls() {
+        let input = "<div><Card /></div>";
+        replace_default_options(jsx_transformer::TransformOptions {
+            component_helper: "h".to_string(),
+            ..Default::default()
+        });
+        let with_defaults = jsx(input, None).unwrap();
+        set_default_options(None).unwrap();
+        let restored = jsx(input, None).unwrap();
+
+        assert!(with_defaults.contains("${h(Card, [])}"));
+        assert!(restored.contains("${__jsxComponent(Card, [])}"));
+    }
+
+    #[test]
+    fn test_version_export() {
+        let version = version();
+        assert!(version.starts_with(concat!(env!("CARGO_PKG_VERSION"), " (")));
+        assert!(version.ends_with(')'));
+        assert!(version.contains("; features: "));
+    }
+
+    #[test]
+    fn test_tag_helper_exports() {
+        assert_eq!(classify_tag("Card", None).unwrap(), "component");
+        assert_eq!(classify_tag("my-card", None).unwrap(), "web-component");
+        assert_eq!(classify_tag("br", None).unwrap(), "void");
+        assert_eq!(classify_tag("div", None).unwrap(), "element");
+        assert_eq!(
+            normalize_html_attr_name("className", None).unwrap(),
+            "class"
+        );
+        assert_eq!(
+            normalize_html_attr_name("strokeWidth", None).unwrap(),
+            "stroke-width"
+        );
+        assert_eq!(
+            normalize_html_attr_name("onClick", None).unwrap(),
+            "onclick"
+        );
+    }
+}